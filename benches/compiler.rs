@@ -0,0 +1,94 @@
+//! Compares translating the programs of `tests/data` one at a time with
+//! [`CompileOptions::compile`] against translating them in one [`Compiler`]
+//! session.
+//!
+//! Run with `cargo bench --bench compiler`. Under `cargo test` every program
+//! is translated once, as a smoke test.
+
+use std::time::{Duration, Instant};
+
+use inkwell::context::Context;
+use qir_qis::{CompileOptions, Compiler, Error, qir_ll_to_bc};
+
+/// Bitcode of every program in `tests/data` that translates without a WASM
+/// module.
+fn programs(options: &CompileOptions) -> Vec<Vec<u8>> {
+    let mut paths: Vec<_> = std::fs::read_dir("tests/data")
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "ll"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let ll_text = std::fs::read_to_string(&path).ok()?;
+            let bc_bytes = qir_ll_to_bc(&ll_text).ok()?;
+            options.compile(&bc_bytes).ok()?;
+            Some(bc_bytes)
+        })
+        .collect()
+}
+
+fn time(iterations: u32, mut run: impl FnMut() -> Result<(), Error>) -> Result<Duration, Error> {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        run()?;
+    }
+    Ok(start.elapsed())
+}
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().collect();
+    // Test runners list the test cases of every target first; there are none.
+    if args.iter().any(|arg| arg == "--list") {
+        return Ok(());
+    }
+    let iterations = if args.iter().any(|arg| arg == "--bench") {
+        20
+    } else {
+        1
+    };
+
+    let options = CompileOptions::new();
+    let programs = programs(&options);
+
+    let one_off = time(iterations, || {
+        for bc_bytes in &programs {
+            options.compile(bc_bytes)?;
+        }
+        Ok(())
+    })?;
+
+    let ctx = Context::create();
+    let setup = Instant::now();
+    let compiler = Compiler::new(&ctx, options.clone())?;
+    let setup = setup.elapsed();
+    let session = time(iterations, || {
+        for bc_bytes in &programs {
+            compiler.compile(bc_bytes)?;
+        }
+        Ok(())
+    })?;
+
+    let translations = u32::try_from(programs.len())
+        .unwrap_or(u32::MAX)
+        .saturating_mul(iterations);
+    println!(
+        "{} programs x {iterations} iterations ({translations} translations)",
+        programs.len()
+    );
+    println!(
+        "one-off:     {one_off:>12.2?} total, {:>10.2?} per program",
+        one_off.checked_div(translations).unwrap_or_default()
+    );
+    println!(
+        "Compiler:    {session:>12.2?} total, {:>10.2?} per program (setup {setup:.2?})",
+        session.checked_div(translations).unwrap_or_default()
+    );
+    Ok(())
+}
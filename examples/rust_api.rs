@@ -1,4 +1,4 @@
-use qir_qis::{get_entry_attributes, qir_ll_to_bc, qir_to_qis, validate_qir};
+use qir_qis::{CompileOptions, OptLevel, Target, get_entry_attributes, qir_ll_to_bc, validate_qir};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -17,10 +17,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Windows LLVM builds are only validated against the host target in CI.
     #[cfg(windows)]
-    let (opt_level, target) = (0, "native");
+    let (opt_level, target) = (OptLevel::O0, Target::Native);
     #[cfg(not(windows))]
-    let (opt_level, target) = (2, "aarch64");
-    let qis_bytes = qir_to_qis(&bc_bytes, opt_level, target, None)?;
+    let (opt_level, target) = (OptLevel::O2, Target::Aarch64);
+    let qis_bytes = CompileOptions::new()
+        .with_opt_level(opt_level)
+        .with_target(target)
+        .compile(&bc_bytes)?
+        .artifact;
 
     // Write output
     fs::write("output.qis.bc", qis_bytes)?;
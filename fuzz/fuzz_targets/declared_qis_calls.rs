@@ -1,6 +1,7 @@
 #![no_main]
 
 use libfuzzer_sys::{Corpus, fuzz_target};
+use qir_qis::{CompileOptions, OptLevel, Target};
 
 const BARRIER_ARITY_LIMIT: u8 = 4;
 
@@ -40,10 +41,13 @@ fn build_declared_qis_fixture(data: &[u8]) -> String {
             let stem = pick(
                 data,
                 &mut cursor,
-                &["h", "x", "y", "z", "s", "t", "cx", "cnot", "cz", "ccx"],
+                &[
+                    "h", "x", "y", "z", "s", "t", "cx", "cnot", "cz", "cy", "ch", "swap", "zzmax",
+                    "ccx", "ccz", "cswap", "mcx3", "mcz3",
+                ],
             );
             match stem {
-                "cx" | "cnot" | "cz" => {
+                "cx" | "cnot" | "cz" | "cy" | "ch" | "swap" | "zzmax" => {
                     let name = format!("__quantum__qis__{stem}{suffix}");
                     (
                         format!("declare void @{name}(%Qubit*, %Qubit*)"),
@@ -52,7 +56,7 @@ fn build_declared_qis_fixture(data: &[u8]) -> String {
                         1,
                     )
                 }
-                "ccx" => {
+                "ccx" | "ccz" | "cswap" | "mcx3" | "mcz3" => {
                     let name = format!("__quantum__qis__{stem}{suffix}");
                     (
                         format!("declare void @{name}(%Qubit*, %Qubit*, %Qubit*)"),
@@ -83,16 +87,21 @@ fn build_declared_qis_fixture(data: &[u8]) -> String {
             )
         }
         "angle_double" => {
-            let stem = pick(data, &mut cursor, &["rxy", "rzz", "u1q"]);
+            let stem = pick(
+                data,
+                &mut cursor,
+                &[
+                    "rxy", "rzz", "u1q", "phasedx", "zzphase", "crz", "cphase", "rxx", "ryy",
+                ],
+            );
             let name = format!("__quantum__qis__{stem}{suffix}");
-            let signature = if stem == "u1q" {
-                "double, double, %Qubit*"
-            } else if stem == "rzz" {
+            let two_qubit = matches!(stem, "rzz" | "zzphase" | "crz" | "cphase" | "rxx" | "ryy");
+            let signature = if two_qubit {
                 "double, %Qubit*, %Qubit*"
             } else {
                 "double, double, %Qubit*"
             };
-            let call = if stem == "rzz" {
+            let call = if two_qubit {
                 format!("  call void @{name}(double 1.0, %Qubit* %q0, %Qubit* %q1)")
             } else {
                 format!("  call void @{name}(double 1.0, double 0.5, %Qubit* %q0)")
@@ -100,7 +109,7 @@ fn build_declared_qis_fixture(data: &[u8]) -> String {
             (
                 format!("declare void @{name}({signature})"),
                 call,
-                if stem == "rzz" { 2 } else { 1 },
+                if two_qubit { 2 } else { 1 },
                 1,
             )
         }
@@ -197,7 +206,10 @@ fuzz_target!(|data: &[u8]| -> Corpus {
     };
 
     if qir_qis::validate_qir(&bitcode, None).is_ok() {
-        let _ = qir_qis::qir_to_qis(&bitcode, 0, "native", None);
+        let _ = CompileOptions::new()
+            .with_opt_level(OptLevel::O0)
+            .with_target(Target::Native)
+            .compile(&bitcode);
     }
     Corpus::Keep
 });
@@ -1,6 +1,7 @@
 #![no_main]
 
 use libfuzzer_sys::{Corpus, fuzz_target};
+use qir_qis::{CompileOptions, OptLevel, Target};
 use std::{ops::Range, sync::LazyLock};
 
 const FIXTURES: &[&str] = &[
@@ -156,7 +157,10 @@ fuzz_target!(|data: &[u8]| -> Corpus {
     };
 
     if qir_qis::validate_qir(&mutated_bc, None).is_ok() {
-        let _ = qir_qis::qir_to_qis(&mutated_bc, 0, "native", None);
+        let _ = CompileOptions::new()
+            .with_opt_level(OptLevel::O0)
+            .with_target(Target::Native)
+            .compile(&mutated_bc);
     }
     Corpus::Keep
 });
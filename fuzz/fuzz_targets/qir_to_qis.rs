@@ -1,7 +1,11 @@
 #![no_main]
 
 use libfuzzer_sys::fuzz_target;
+use qir_qis::{CompileOptions, OptLevel, Target};
 
 fuzz_target!(|data: &[u8]| {
-    let _ = qir_qis::qir_to_qis(data, 0, "native", None);
+    let _ = CompileOptions::new()
+        .with_opt_level(OptLevel::O0)
+        .with_target(Target::Native)
+        .compile(data);
 });
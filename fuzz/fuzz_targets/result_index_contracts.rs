@@ -1,6 +1,7 @@
 #![no_main]
 
 use libfuzzer_sys::{Corpus, fuzz_target};
+use qir_qis::{CompileOptions, OptLevel, Target};
 
 fn pick_u8_inclusive(data: &[u8], cursor: &mut usize, min: u8, max: u8) -> u8 {
     let span = max.saturating_sub(min).saturating_add(1);
@@ -85,7 +86,10 @@ fuzz_target!(|data: &[u8]| -> Corpus {
     };
 
     if qir_qis::validate_qir(&bitcode, None).is_ok() {
-        let _ = qir_qis::qir_to_qis(&bitcode, 0, "native", None);
+        let _ = CompileOptions::new()
+            .with_opt_level(OptLevel::O0)
+            .with_target(Target::Native)
+            .compile(&bitcode);
     }
     Corpus::Keep
 });
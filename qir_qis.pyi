@@ -1,28 +1,116 @@
 # This file is automatically generated by pyo3_stub_gen
 
 import builtins
+import typing
 
 __all__ = [
+    "Bundle",
+    "CompileOptions",
     "CompilerError",
+    "QirWarning",
     "ValidationError",
     "get_entry_attributes",
     "qir_ll_to_bc",
+    "qir_to_bundle",
     "qir_to_qis",
+    "read_bundle",
     "validate_qir",
 ]
 
+@typing.final
+class Bundle:
+    r"""A compiled QIS program with its WASM module and manifest."""
+
+    @property
+    def capability_flags(self) -> builtins.dict[builtins.str, builtins.bool]:
+        r"""The `dynamic_qubit_management`, `dynamic_result_management` and
+        `arrays` capability flags.
+        """
+    @property
+    def compiler_version(self) -> builtins.str:
+        r"""Version of `qir-qis` that compiled the QIS."""
+    @property
+    def entry_attributes(self) -> builtins.dict[builtins.str, builtins.str | None]:
+        r"""Entry point attributes, as returned by `get_entry_attributes`."""
+    @property
+    def qis(self) -> builtins.bytes:
+        r"""The QIS bitcode."""
+    @property
+    def target(self) -> builtins.str:
+        r"""Target architecture the QIS was compiled for."""
+    @property
+    def wasm(self) -> builtins.bytes | None:
+        r"""The WASM module providing the functions called by the program."""
+
+@typing.final
+class CompileOptions:
+    r"""Settings of a QIR to QIS translation, checked when created.
+
+    Pass it as `options=` to `qir_to_qis` or `qir_to_bundle` instead of the
+    individual keyword arguments, which it takes with the same defaults.
+    """
+
+    @property
+    def deny_warnings(self) -> builtins.bool:
+        r"""Whether warnings raise instead of issuing a `QirWarning`."""
+    @property
+    def emit(self) -> builtins.str:
+        r"""The artifact to return: "bc", "ll", "asm" or "obj"."""
+    @property
+    def opt_level(self) -> builtins.int:
+        r"""The optimization level (0-3)."""
+    @property
+    def target(self) -> builtins.str:
+        r"""The target architecture."""
+    @property
+    def wasm_bytes(self) -> builtins.bytes | None:
+        r"""The WASM module providing the functions called by the program."""
+    def __new__(
+        cls,
+        *,
+        opt_level: builtins.int | None = None,
+        target: builtins.str | None = None,
+        wasm_bytes: builtins.bytes | None = None,
+        emit: builtins.str | None = None,
+        deny_warnings: builtins.bool | None = None,
+    ) -> CompileOptions:
+        r"""# Errors
+        Raises `ValueError` for an invalid `opt_level`, `target` or
+        `emit`.
+        """
+
 class CompilerError(builtins.Exception):
     r"""QIR CompilerError.
 
     Raised when QIR to QIS compilation fails.
+
+    The `kind` attribute names the error variant (e.g. "lowering", "verifier", "target"), `code` is its stable code (see `qir-qis explain`) and `errors` lists the individual messages.
+    """
+
+    kind: builtins.str
+    code: builtins.str
+    errors: builtins.list[builtins.str]
+
+class QirWarning(builtins.UserWarning):
+    r"""QIR QirWarning.
+
+    Issued for suspicious but compilable QIR. Pass `deny_warnings=True` to raise instead.
     """
 
+    ...
+
 class ValidationError(builtins.Exception):
     r"""QIR ValidationError.
 
     Raised when the QIR is invalid.
+
+    The `kind` attribute names the error variant (e.g. "validation", "parse", "wasm"), `code` is its stable code (see `qir-qis explain`) and `errors` lists the individual messages.
     """
 
+    kind: builtins.str
+    code: builtins.str
+    errors: builtins.list[builtins.str]
+
 def get_entry_attributes(
     bc_bytes: builtins.bytes,
 ) -> builtins.dict[builtins.str, builtins.str | None]:
@@ -42,15 +130,47 @@ def qir_ll_to_bc(ll_text: builtins.str) -> builtins.bytes:
     Returns a `ValidationError` if the LLVM IR is invalid.
     """
 
+def qir_to_bundle(
+    bc_bytes: builtins.bytes,
+    *,
+    opt_level: builtins.int | None = None,
+    target: builtins.str | None = None,
+    wasm_bytes: builtins.bytes | None = None,
+    deny_warnings: builtins.bool | None = None,
+    options: CompileOptions | None = None,
+) -> builtins.bytes:
+    r"""Translate QIR bitcode to Quantinuum QIS and pack it into a single-file
+    bundle with the WASM module, entry point attributes and capability
+    flags.
+
+    Takes the same arguments as `qir_to_qis`, except `emit` and the stage
+    debugging options. Use `read_bundle` to unpack the result.
+
+    # Errors
+    Returns a `CompilerError` if the translation fails, or if
+    `deny_warnings` is set and any warning is raised. Raises `ValueError`
+    for an invalid `opt_level` or `target`, or if `options` is given with
+    the arguments it replaces.
+    """
+
 def qir_to_qis(
     bc_bytes: builtins.bytes,
     *,
-    opt_level: builtins.int = 2,
-    target: builtins.str = "aarch64",
+    opt_level: builtins.int | None = None,
+    target: builtins.str | None = None,
     wasm_bytes: builtins.bytes | None = None,
+    emit: builtins.str | None = None,
+    deny_warnings: builtins.bool | None = None,
+    print_after: typing.Sequence[builtins.str] | None = None,
+    print_after_all: builtins.bool = False,
+    stop_after: builtins.str | None = None,
+    options: CompileOptions | None = None,
 ) -> builtins.bytes:
     r"""Translate QIR bitcode to Quantinuum QIS.
 
+    The GIL is released during the translation, so calls from several
+    threads run in parallel.
+
     # Arguments
     - `bc_bytes` - The QIR bytes to translate.
     - `opt_level` - The optimization level to use (0-3). Default is 2 on
@@ -58,23 +178,49 @@ def qir_to_qis(
     - `target` - Target architecture (default: "aarch64" on Linux/macOS and
       "native" on Windows; options: "x86-64", "native").
     - `wasm_bytes` - Optional WASM bytes for Wasm codegen.
+    - `emit` - Artifact to return: "bc" (bitcode, default), "ll" (textual
+      LLVM IR), "asm" (assembly) or "obj" (object file).
+    - `deny_warnings` - Raise instead of issuing a `QirWarning`.
+    - `print_after` - Names of the stages (see `qir_qis::pipeline::STAGES`
+      in the Rust API) after which to print the IR to `sys.stderr`.
+    - `print_after_all` - Print the IR after every stage.
+    - `stop_after` - Return the module as left by this stage instead of
+      running the whole translation.
+    - `options` - A `CompileOptions` to use instead of `opt_level`,
+      `target`, `wasm_bytes`, `emit` and `deny_warnings`, which may not be
+      given with it.
 
     # Errors
-    Returns a `CompilerError` if the translation fails.
+    Returns a `CompilerError` if the translation fails, or if
+    `deny_warnings` is set and any warning is raised. Raises `ValueError`
+    for an invalid `opt_level`, `target`, `emit` kind or stage, or if
+    `options` is given with the arguments it replaces.
+    """
+
+def read_bundle(bundle_bytes: builtins.bytes) -> Bundle:
+    r"""Unpack a bundle written by `qir_to_bundle`.
+
+    # Errors
+    Returns a `ValidationError` if the bytes are not a valid bundle.
     """
 
 def validate_qir(
-    bc_bytes: builtins.bytes, *, wasm_bytes: builtins.bytes | None = None
+    bc_bytes: builtins.bytes,
+    *,
+    wasm_bytes: builtins.bytes | None = None,
+    deny_warnings: builtins.bool = False,
 ) -> None:
     r"""Validate the given QIR.
 
     # Arguments
     - `bc_bytes` - The QIR bytes to validate.
     - `wasm_bytes` - Optional WASM bytes to validate against.
+    - `deny_warnings` - Raise instead of issuing a `QirWarning`.
 
     # Errors
     Returns a `ValidationError`:
     - If the QIR is invalid.
     - If the WASM module is invalid.
     - If a QIR-referenced WASM function is missing from the WASM module.
+    - If `deny_warnings` is set and any warning is raised.
     """
@@ -0,0 +1,387 @@
+//! Single-file bundles of a compiled QIS program, its WASM module and a
+//! manifest describing how it was compiled.
+//!
+//! A bundle is a small archive: the 8-byte [`MAGIC`] followed by entries, each
+//! a little-endian `u16` name length, the UTF-8 name, a little-endian `u64`
+//! data length and the data. It holds these entries:
+//!
+//! - [`QIS_ENTRY`]: the QIS bitcode.
+//! - [`WASM_ENTRY`]: the WASM module, if the program calls WASM functions.
+//! - [`MANIFEST_ENTRY`]: `key=value` lines with the bundle format version,
+//!   compiler version, target, capability flags (`capability.<name>`) and
+//!   entry point attributes (`attribute.<name>`, without `=` when the
+//!   attribute has no value). `%`, `=`, carriage returns and newlines in keys
+//!   and values are percent-encoded.
+//!
+//! Readers ignore entries and manifest keys they do not know.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::{CapabilityFlags, Error};
+
+/// Magic number at the start of every bundle.
+pub const MAGIC: &[u8; 8] = b"QIRQISB\0";
+/// Version of the manifest written by this release.
+pub const FORMAT_VERSION: u32 = 1;
+/// Name of the entry holding the QIS bitcode.
+pub const QIS_ENTRY: &str = "qis.bc";
+/// Name of the entry holding the WASM module.
+pub const WASM_ENTRY: &str = "program.wasm";
+/// Name of the entry holding the manifest.
+pub const MANIFEST_ENTRY: &str = "manifest.txt";
+
+/// A compiled program with everything needed to run it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    /// The QIS bitcode.
+    pub qis: Vec<u8>,
+    /// The WASM module providing the functions called by the program.
+    pub wasm: Option<Vec<u8>>,
+    /// Entry point attributes of the QIR program; valueless attributes map to
+    /// `None`.
+    pub entry_attributes: BTreeMap<String, Option<String>>,
+    /// Capabilities enabled by the module flags of the QIR program.
+    pub capability_flags: CapabilityFlags,
+    /// Target architecture the QIS was compiled for.
+    pub target: String,
+    /// Version of `qir-qis` that compiled the QIS.
+    pub compiler_version: String,
+}
+
+impl Bundle {
+    /// Bundle `qis`, compiled for `target` from the QIR bitcode `bc_bytes`,
+    /// with the program's entry point attributes, capability flags and WASM
+    /// module.
+    ///
+    /// # Errors
+    /// Returns [`Error::Parse`] if `bc_bytes` cannot be parsed.
+    pub fn new(
+        bc_bytes: &[u8],
+        qis: Vec<u8>,
+        target: &str,
+        wasm: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        let ctx = inkwell::context::Context::create();
+        let module = crate::parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
+        Ok(Self {
+            qis,
+            ..Self::for_module(&module, target, wasm)
+        })
+    }
+
+    /// A bundle with the entry point attributes and capability flags of the
+    /// QIR `module` and its WASM module, for QIS compiled for `target`.
+    ///
+    /// Lowering rewrites the entry point attributes, so call this before
+    /// compiling `module` in place, then set [`qis`](Self::qis), which is
+    /// empty until then.
+    #[must_use]
+    pub fn for_module(
+        module: &inkwell::module::Module<'_>,
+        target: &str,
+        wasm: Option<&[u8]>,
+    ) -> Self {
+        Self {
+            qis: Vec::new(),
+            wasm: wasm.map(<[u8]>::to_vec),
+            entry_attributes: crate::get_module_entry_attributes(module),
+            capability_flags: crate::get_module_capability_flags(module),
+            target: target.to_owned(),
+            compiler_version: env!("CARGO_PKG_VERSION").to_owned(),
+        }
+    }
+
+    /// The manifest entry of the bundle.
+    #[must_use]
+    pub fn manifest(&self) -> String {
+        let mut manifest = format!("format_version={FORMAT_VERSION}\n");
+        let _ = writeln!(
+            manifest,
+            "compiler_version={}",
+            escape(&self.compiler_version)
+        );
+        let _ = writeln!(manifest, "target={}", escape(&self.target));
+        for (name, enabled) in self.capability_flags.entries() {
+            let _ = writeln!(manifest, "capability.{name}={enabled}");
+        }
+        for (name, value) in &self.entry_attributes {
+            let _ = match value {
+                Some(value) => writeln!(manifest, "attribute.{}={}", escape(name), escape(value)),
+                None => writeln!(manifest, "attribute.{}", escape(name)),
+            };
+        }
+        manifest
+    }
+
+    /// Serialize the bundle.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let manifest = self.manifest();
+        let mut entries = vec![
+            (MANIFEST_ENTRY, manifest.as_bytes()),
+            (QIS_ENTRY, self.qis.as_slice()),
+        ];
+        if let Some(wasm) = &self.wasm {
+            entries.push((WASM_ENTRY, wasm));
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        for (name, data) in entries {
+            // Entry names are the short constants above.
+            let name_len = u16::try_from(name.len()).unwrap_or(u16::MAX);
+            bytes.extend_from_slice(&name_len.to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+
+    /// Deserialize a bundle written by [`Bundle::to_bytes`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Bundle`] if `bytes` is not a bundle, is truncated,
+    /// lacks the QIS or manifest entry, or was written by a newer format
+    /// version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut rest = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or_else(|| Error::Bundle("Not a QIR-QIS bundle: bad magic number".to_owned()))?;
+        let mut entries = BTreeMap::new();
+        while !rest.is_empty() {
+            let name_len: [u8; 2] = take(&mut rest, 2)?.try_into().map_err(|_| truncated())?;
+            let name_len = usize::from(u16::from_le_bytes(name_len));
+            let name = std::str::from_utf8(take(&mut rest, name_len)?)
+                .map_err(|e| Error::Bundle(format!("Invalid bundle entry name: {e}")))?;
+            let data_len: [u8; 8] = take(&mut rest, 8)?.try_into().map_err(|_| truncated())?;
+            let data_len =
+                usize::try_from(u64::from_le_bytes(data_len)).map_err(|_| truncated())?;
+            entries.insert(name, take(&mut rest, data_len)?);
+        }
+
+        let entry = |name: &str| {
+            entries
+                .get(name)
+                .copied()
+                .ok_or_else(|| Error::Bundle(format!("Bundle has no `{name}` entry")))
+        };
+        let manifest = std::str::from_utf8(entry(MANIFEST_ENTRY)?)
+            .map_err(|e| Error::Bundle(format!("Invalid bundle manifest: {e}")))?;
+        let mut bundle = Self {
+            qis: entry(QIS_ENTRY)?.to_vec(),
+            wasm: entries.get(WASM_ENTRY).map(|wasm| wasm.to_vec()),
+            entry_attributes: BTreeMap::new(),
+            capability_flags: CapabilityFlags::default(),
+            target: String::new(),
+            compiler_version: String::new(),
+        };
+        bundle.read_manifest(manifest)?;
+        Ok(bundle)
+    }
+
+    fn read_manifest(&mut self, manifest: &str) -> Result<(), Error> {
+        let mut version = None;
+        for line in manifest.lines().filter(|line| !line.is_empty()) {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (unescape(key)?, Some(unescape(value)?)),
+                None => (unescape(line)?, None),
+            };
+            if let Some(name) = key.strip_prefix("attribute.") {
+                self.entry_attributes.insert(name.to_owned(), value);
+                continue;
+            }
+            let value = value.ok_or_else(|| {
+                Error::Bundle(format!("Bundle manifest key `{key}` has no value"))
+            })?;
+            if let Some(name) = key.strip_prefix("capability.") {
+                let enabled = value.parse::<bool>().map_err(|_| {
+                    Error::Bundle(format!("Invalid value for capability `{name}`: {value}"))
+                })?;
+                match name {
+                    "dynamic_qubit_management" => {
+                        self.capability_flags.dynamic_qubit_management = enabled;
+                    }
+                    "dynamic_result_management" => {
+                        self.capability_flags.dynamic_result_management = enabled;
+                    }
+                    "arrays" => self.capability_flags.arrays = enabled,
+                    _ => {}
+                }
+                continue;
+            }
+            match key.as_str() {
+                "format_version" => {
+                    version = Some(value.parse::<u32>().map_err(|_| {
+                        Error::Bundle(format!("Invalid bundle format version: {value}"))
+                    })?);
+                }
+                "compiler_version" => self.compiler_version = value,
+                "target" => self.target = value,
+                _ => {}
+            }
+        }
+        match version {
+            Some(version) if version <= FORMAT_VERSION => Ok(()),
+            Some(version) => Err(Error::Bundle(format!(
+                "Bundle format version {version} is newer than the supported version {FORMAT_VERSION}"
+            ))),
+            None => Err(Error::Bundle(
+                "Bundle manifest has no format version".to_owned(),
+            )),
+        }
+    }
+}
+
+/// Whether `bytes` starts with the bundle magic number.
+#[must_use]
+pub fn is_bundle(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+fn truncated() -> Error {
+    Error::Bundle("Truncated bundle".to_owned())
+}
+
+/// Split the first `len` bytes off `rest`.
+fn take<'a>(rest: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if rest.len() < len {
+        return Err(truncated());
+    }
+    let (head, tail) = rest.split_at(len);
+    *rest = tail;
+    Ok(head)
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '%' | '=' | '\r' | '\n' => {
+                let _ = write!(out, "%{:02X}", u32::from(ch));
+            }
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+fn unescape(text: &str) -> Result<String, Error> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let decoded = match (bytes.next(), bytes.next()) {
+                (Some(high), Some(low)) => std::str::from_utf8(&[high, low])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                _ => None,
+            }
+            .ok_or_else(|| Error::Bundle(format!("Invalid escape in bundle manifest: {text}")))?;
+            out.push(decoded);
+        } else {
+            out.push(byte);
+        }
+    }
+    String::from_utf8(out).map_err(|e| Error::Bundle(format!("Invalid bundle manifest: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use std::collections::BTreeMap;
+
+    use super::{Bundle, MAGIC, escape, is_bundle, unescape};
+    use crate::{CapabilityFlags, Error};
+
+    fn bundle() -> Bundle {
+        Bundle {
+            qis: vec![0x42, 0x43, 0xC0, 0xDE],
+            wasm: Some(b"\0asm\x01\0\0\0".to_vec()),
+            entry_attributes: BTreeMap::from([
+                ("entry_point".to_owned(), None),
+                ("required_num_qubits".to_owned(), Some("2".to_owned())),
+                ("odd=name".to_owned(), Some("50%\nof it".to_owned())),
+            ]),
+            capability_flags: CapabilityFlags {
+                arrays: true,
+                ..CapabilityFlags::default()
+            },
+            target: "aarch64".to_owned(),
+            compiler_version: "0.1.6".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trips() {
+        let bundle = bundle();
+        let bytes = bundle.to_bytes();
+        assert!(is_bundle(&bytes));
+        assert_eq!(
+            Bundle::from_bytes(&bytes).expect("bundle should parse"),
+            bundle
+        );
+
+        let without_wasm = Bundle {
+            wasm: None,
+            ..bundle
+        };
+        assert_eq!(
+            Bundle::from_bytes(&without_wasm.to_bytes()).expect("bundle should parse"),
+            without_wasm
+        );
+    }
+
+    #[test]
+    fn test_manifest_escapes_separators() {
+        assert_eq!(escape("a=b%\r\n"), "a%3Db%25%0D%0A");
+        assert_eq!(
+            unescape("a%3Db%25%0D%0A").expect("valid escapes"),
+            "a=b%\r\n"
+        );
+        assert!(unescape("50%").is_err());
+        assert!(
+            bundle()
+                .manifest()
+                .contains("attribute.odd%3Dname=50%25%0Aof it\n")
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_malformed_bundles() {
+        let bytes = bundle().to_bytes();
+        let truncated = bytes.get(..bytes.len().saturating_sub(1)).expect("slice");
+        let errors = [
+            Bundle::from_bytes(b"BC\xC0\xDE"),
+            Bundle::from_bytes(truncated),
+            Bundle::from_bytes(MAGIC),
+        ];
+        assert_eq!(
+            errors.map(Result::err),
+            [
+                Some(Error::Bundle(
+                    "Not a QIR-QIS bundle: bad magic number".to_owned()
+                )),
+                Some(Error::Bundle("Truncated bundle".to_owned())),
+                Some(Error::Bundle(
+                    "Bundle has no `manifest.txt` entry".to_owned()
+                )),
+            ]
+        );
+
+        let mut newer = bundle().to_bytes();
+        let at = newer
+            .windows(16)
+            .position(|window| window == b"format_version=1")
+            .expect("manifest should have a format version");
+        if let Some(digit) = newer.get_mut(at..).and_then(|rest| rest.get_mut(15)) {
+            *digit = b'9';
+        }
+        assert_eq!(
+            Bundle::from_bytes(&newer).err(),
+            Some(Error::Bundle(
+                "Bundle format version 9 is newer than the supported version 1".to_owned()
+            ))
+        );
+    }
+}
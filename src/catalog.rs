@@ -0,0 +1,575 @@
+//! Long-form explanations of the [`codes`](crate::diagnostics::codes) reported
+//! in [`Diagnostic`](crate::Diagnostic)s and by [`Error::code`](crate::Error::code).
+//!
+//! The same text is printed by `qir-qis explain <CODE>`.
+
+use std::fmt;
+
+use crate::diagnostics::codes;
+
+/// Catalog entry describing one diagnostic code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Explanation {
+    /// The diagnostic code, e.g. `"E007"`.
+    pub code: &'static str,
+    /// One-line summary of the problem.
+    pub title: &'static str,
+    /// Why the check exists and when it fires.
+    pub explanation: &'static str,
+    /// Example of the offending LLVM IR and how to fix it.
+    pub example: &'static str,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {}", self.code, self.title)?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.explanation)?;
+        writeln!(f)?;
+        writeln!(f, "Example fix:")?;
+        writeln!(f)?;
+        for line in self.example.lines() {
+            if line.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, "    {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Look up the catalog entry for `code`, ignoring ASCII case.
+#[must_use]
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    CATALOG
+        .iter()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Every documented diagnostic code, in code order.
+pub const CATALOG: &[Explanation] = &[
+    Explanation {
+        code: codes::PARSE_FAILED,
+        title: "Input is not valid LLVM IR",
+        explanation: "The input could be parsed neither as LLVM bitcode nor as LLVM IR text. \
+The error message contains the parser output, with the line and column of the problem for IR \
+text. qir-qis reads IR produced by LLVM 21; IR from newer releases may use syntax it does not \
+know.",
+        example: r#"; before
+define i64 @main() #0 {
+  call void @__quantum__qis__h__body(ptr null
+}
+
+; after
+define i64 @main() #0 {
+  call void @__quantum__qis__h__body(ptr null)
+  ret i64 0
+}"#,
+    },
+    Explanation {
+        code: codes::VERIFIER_FAILED,
+        title: "LLVM verifier rejected the module",
+        explanation: "The module parsed, but is not well-formed LLVM IR, for example because a \
+basic block has no terminator or a value does not dominate its uses. The input is verified \
+before compilation and the output after it; a failure of the output is a bug in qir-qis.",
+        example: r#"; before
+entry:
+  call void @__quantum__qis__h__body(ptr null)
+
+; after
+entry:
+  call void @__quantum__qis__h__body(ptr null)
+  ret i64 0"#,
+    },
+    Explanation {
+        code: codes::VALIDATION_FAILED,
+        title: "Program failed validation",
+        explanation: "The program violates one or more QIR or QIS constraints. Each violation \
+is reported as a diagnostic with a code of its own, which explains how to fix it.",
+        example: r#"# list the violations with their codes
+qir-qis validate program.ll
+
+# then explain each one
+qir-qis explain E007"#,
+    },
+    Explanation {
+        code: codes::LOWERING_FAILED,
+        title: "Lowering to QIS failed",
+        explanation: "A valid program could not be translated to Quantinuum QIS, for example \
+because a call has an unexpected operand. The message names the problem and, for IR text, the \
+location of the offending instruction. Validation rejects most such programs up front.",
+        example: r#"; before
+call void @__quantum__rt__result_record_output(ptr null, i64 0)
+
+; after
+call void @__quantum__rt__result_record_output(ptr null, ptr @r0)"#,
+    },
+    Explanation {
+        code: codes::UNKNOWN_STAGE,
+        title: "Unknown pipeline stage",
+        explanation: "`--print-after` and `--stop-after` (`print_after` and `stop_after` in \
+Python) name stages of the compilation pipeline, and `Pipeline` methods take the stage to act \
+on. The name matches none of the stages; the message lists the ones available. A stage after \
+`--stop-after` cannot be printed.",
+        example: r#"# before
+qir-qis compile --stop-after lowering program.ll
+
+# after
+qir-qis compile --stop-after lower-entry program.ll"#,
+    },
+    Explanation {
+        code: codes::TARGET_FAILED,
+        title: "Target machine or optimizer failed",
+        explanation: "LLVM could not create a target machine for the selected target, run the \
+optimization pipeline, or emit the requested output. The message contains the LLVM error. On \
+Windows, optimized compilation is currently unavailable.",
+        example: r#"# before (on Windows)
+qir-qis compile -O 2 -t aarch64 program.ll
+
+# after
+qir-qis compile -O 0 -t native program.ll"#,
+    },
+    Explanation {
+        code: codes::INVALID_WASM_MODULE,
+        title: "WASM module cannot be parsed",
+        explanation: "The file passed with `--wasm` (`wasm_bytes` in Python) is not a valid \
+WASM binary, or an export refers to a function that does not exist.",
+        example: r#"# before: the text format
+qir-qis compile --wasm module.wat program.ll
+
+# after: the binary format
+wat2wasm module.wat -o module.wasm
+qir-qis compile --wasm module.wasm program.ll"#,
+    },
+    Explanation {
+        code: codes::INVALID_BUNDLE,
+        title: "Bundle cannot be read",
+        explanation: "The input of `unbundle` is not a bundle written by `qir-qis bundle`, is \
+truncated, or was written by a newer release with an unsupported format version.",
+        example: r#"# recreate the bundle
+qir-qis bundle -o program.bundle program.ll
+qir-qis unbundle -o out program.bundle"#,
+    },
+    Explanation {
+        code: codes::INVALID_OPTION,
+        title: "Invalid compilation option",
+        explanation: "An option has a value that is not supported, such as an unknown \
+optimization level, target architecture, WASM call mode or output kind.",
+        example: r#"# before
+qir-qis compile -O 4 program.ll
+
+# after
+qir-qis compile -O 3 program.ll"#,
+    },
+    Explanation {
+        code: codes::MISSING_ENTRY_POINT,
+        title: "No entry point function",
+        explanation: "A QIR program must define exactly one function carrying the `entry_point` \
+attribute; it becomes the body of the generated `qmain`. No function in the module has it.",
+        example: r#"; before
+define i64 @main() {
+  ...
+}
+
+; after
+define i64 @main() #0 {
+  ...
+}
+attributes #0 = { "entry_point" ... }"#,
+    },
+    Explanation {
+        code: codes::EMPTY_ENTRY_POINT,
+        title: "Entry point has no body",
+        explanation: "The function marked `entry_point` is only declared. The entry point must be \
+defined in the module, with at least one basic block.",
+        example: r#"; before
+declare i64 @main() #0
+
+; after
+define i64 @main() #0 {
+entry:
+  ret i64 0
+}"#,
+    },
+    Explanation {
+        code: codes::MISSING_ENTRY_ATTRIBUTE,
+        title: "Missing entry point attribute",
+        explanation: "The entry point must carry the `qir_profiles`, `output_labeling_schema`, \
+`required_num_qubits` and `required_num_results` attributes. They describe the program to the \
+runtime and size the static qubit and result storage.",
+        example: r#"; before
+attributes #0 = { "entry_point" "qir_profiles"="base_profile" }
+
+; after
+attributes #0 = { "entry_point" "qir_profiles"="base_profile"
+  "output_labeling_schema"="schema_id"
+  "required_num_qubits"="2" "required_num_results"="2" }"#,
+    },
+    Explanation {
+        code: codes::ZERO_REQUIRED_QUBITS,
+        title: "Entry point requires zero qubits",
+        explanation: "Without `dynamic_qubit_management`, qubits are allocated up front from \
+`required_num_qubits`, which must therefore be at least 1. Programs that allocate qubits at \
+runtime should enable the `dynamic_qubit_management` module flag instead.",
+        example: r#"; before
+"required_num_qubits"="0"
+
+; after
+"required_num_qubits"="1""#,
+    },
+    Explanation {
+        code: codes::RESERVED_HELPER_NAME,
+        title: "Function uses a reserved helper name",
+        explanation: "Functions whose names start with `qir_qis.` are generated by the compiler \
+while lowering. Defining one in the input would clash with the generated helpers.",
+        example: r#"; before
+define void @qir_qis.my_helper(ptr %q) { ... }
+
+; after
+define void @my_helper(ptr %q) { ... }"#,
+    },
+    Explanation {
+        code: codes::BARRIER_ARITY_OVERFLOW,
+        title: "Barrier uses more qubits than the program has",
+        explanation: "`__quantum__qis__barrier<N>__body` takes N qubit operands. N may not exceed \
+the entry point's `required_num_qubits`, since the barrier could not refer to that many distinct \
+qubits.",
+        example: r#"; before ("required_num_qubits"="2")
+call void @__quantum__qis__barrier3__body(ptr null, ptr inttoptr (i64 1 to ptr), ptr inttoptr (i64 2 to ptr))
+
+; after
+call void @__quantum__qis__barrier2__body(ptr null, ptr inttoptr (i64 1 to ptr))"#,
+    },
+    Explanation {
+        code: codes::UNSUPPORTED_QIS_FUNCTION,
+        title: "Unsupported QIS function",
+        explanation: "The program declares a `__quantum__qis__*` function that the compiler cannot \
+lower to native Quantinuum operations. See `qtm-qir-reference.md` for the supported gate set. \
+Rewrite the gate in terms of supported ones, or decompose it before compilation.",
+        example: r#"; before
+call void @__quantum__qis__sx__body(ptr %q)
+
+; after: sx = rx(pi/2) up to global phase
+call void @__quantum__qis__rx__body(double 0x3FF921FB54442D18, ptr %q)"#,
+    },
+    Explanation {
+        code: codes::UNSUPPORTED_RT_FUNCTION,
+        title: "Unsupported runtime function",
+        explanation: "The program declares a `__quantum__rt__*` function that has no QIS \
+counterpart. Output recording, result reading and the qubit/result allocation functions listed \
+in `qtm-qir-reference.md` are supported.",
+        example: r#"; before
+call void @__quantum__rt__message(ptr @msg)
+
+; after: record the value as program output instead
+call void @__quantum__rt__int_record_output(i64 %value, ptr @label)"#,
+    },
+    Explanation {
+        code: codes::MALFORMED_RT_DECLARATION,
+        title: "Runtime function has the wrong signature",
+        explanation: "A supported `__quantum__rt__*` function is declared with a signature that does \
+not match the QIR specification, so calls to it cannot be lowered safely.",
+        example: r#"; before
+declare void @__quantum__rt__result_record_output(ptr)
+
+; after
+declare void @__quantum__rt__result_record_output(ptr, ptr)"#,
+    },
+    Explanation {
+        code: codes::UNSUPPORTED_QTM_FUNCTION,
+        title: "Unsupported Quantinuum function",
+        explanation: "Functions starting with `___` are Quantinuum QIS extensions. The program \
+declares one that this compiler does not know. Only the extensions listed in \
+`qtm-qir-reference.md` may be called directly.",
+        example: r#"; before
+declare void @___made_up(i64)
+
+; after: use a documented extension, e.g.
+declare i64 @___get_current_shot()"#,
+    },
+    Explanation {
+        code: codes::IR_FUNCTION_NAMED_MAIN,
+        title: "IR-defined function named `main`",
+        explanation: "The compiler generates its own `main`/`qmain` wrapper around the entry point. \
+A helper function defined in the input may not be called `main`.",
+        example: r#"; before
+define void @main(ptr %q) { ... }
+
+; after
+define void @apply_layer(ptr %q) { ... }"#,
+    },
+    Explanation {
+        code: codes::IR_FUNCTION_RETURNS_POINTER,
+        title: "IR-defined function returns a pointer",
+        explanation: "Qubits and results are rewritten from pointers to integer handles during \
+lowering, so helper functions may not return pointers: the returned value could not be \
+translated.",
+        example: r#"; before
+define ptr @pick(ptr %a, ptr %b) { ... }
+
+; after: branch in the caller instead of returning a qubit
+define void @apply_to(ptr %q) { ... }"#,
+    },
+    Explanation {
+        code: codes::INVALID_REQUIRED_NUM_RESULTS,
+        title: "Invalid `required_num_results`",
+        explanation: "The `required_num_results` entry point attribute must be a non-negative \
+decimal integer.",
+        example: r#"; before
+"required_num_results"="two"
+
+; after
+"required_num_results"="2""#,
+    },
+    Explanation {
+        code: codes::MALFORMED_RESULT_OPERAND,
+        title: "Result operand is not a static index",
+        explanation: "Without `dynamic_result_management`, results passed to `mz`, `read_result` or \
+`result_record_output` must be constant result indices (`null` or `inttoptr (i64 N to ptr)`), \
+so that they can be mapped onto the preallocated result storage.",
+        example: r#"; before
+%r = load ptr, ptr %slot
+call void @__quantum__qis__mz__body(ptr %q, ptr %r)
+
+; after
+call void @__quantum__qis__mz__body(ptr %q, ptr inttoptr (i64 1 to ptr))"#,
+    },
+    Explanation {
+        code: codes::RESULT_INDEX_OUT_OF_RANGE,
+        title: "Result index out of range",
+        explanation: "A static result index must be below the entry point's \
+`required_num_results`. Either raise the attribute or use a smaller index.",
+        example: r#"; before ("required_num_results"="1")
+call void @__quantum__qis__mz__body(ptr null, ptr inttoptr (i64 1 to ptr))
+
+; after
+"required_num_results"="2""#,
+    },
+    Explanation {
+        code: codes::MISSING_MODULE_FLAG,
+        title: "Missing required module flag",
+        explanation: "QIR modules must declare the `qir_major_version`, `qir_minor_version`, \
+`dynamic_qubit_management` and `dynamic_result_management` module flags.",
+        example: r#"!llvm.module.flags = !{!0, !1, !2, !3}
+!0 = !{i32 1, !"qir_major_version", i32 1}
+!1 = !{i32 7, !"qir_minor_version", i32 0}
+!2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+!3 = !{i32 1, !"dynamic_result_management", i1 false}"#,
+    },
+    Explanation {
+        code: codes::MALFORMED_MODULE_FLAG,
+        title: "Malformed module flag",
+        explanation: "A QIR module flag is present but is not a `!{i32 <behavior>, !\"<name>\", \
+<value>}` triple with a constant value, so it cannot be read.",
+        example: r#"; before
+!0 = !{!"qir_major_version", i32 1}
+
+; after
+!0 = !{i32 1, !"qir_major_version", i32 1}"#,
+    },
+    Explanation {
+        code: codes::UNSUPPORTED_MODULE_FLAG_VALUE,
+        title: "Unsupported module flag value",
+        explanation: "A module flag has a value this compiler does not support. `qir_major_version` \
+must be 1 or 2, `qir_minor_version` must be 0, and the `dynamic_*_management` and `arrays` flags \
+must be `i1` booleans.",
+        example: r#"; before
+!0 = !{i32 1, !"qir_major_version", i32 3}
+
+; after
+!0 = !{i32 1, !"qir_major_version", i32 2}"#,
+    },
+    Explanation {
+        code: codes::INVALID_ARRAY_BACKING,
+        title: "Array call without a fixed-size backing array",
+        explanation: "The qubit and result array runtime functions write to or read from caller \
+storage. That storage must be an `alloca [N x ptr]` whose length matches the length argument of \
+the call.",
+        example: r#"; before
+%arr = alloca ptr
+call void @__quantum__rt__qubit_array_allocate(i64 2, ptr %arr, ptr null)
+
+; after
+%arr = alloca [2 x ptr]
+call void @__quantum__rt__qubit_array_allocate(i64 2, ptr %arr, ptr null)"#,
+    },
+    Explanation {
+        code: codes::RESULT_ARRAY_TOO_LONG,
+        title: "Result array too long for output",
+        explanation: "`RESULT_ARRAY` output records store their length as a 32-bit integer, so \
+recorded result arrays may hold at most `i32::MAX` elements.",
+        example: r#"; before
+call void @__quantum__rt__result_array_record_output(i64 4294967296, ptr %arr, ptr @label)
+
+; after: record the results in smaller chunks"#,
+    },
+    Explanation {
+        code: codes::MISSING_CAPABILITY,
+        title: "Runtime function requires a capability flag",
+        explanation: "Dynamic qubit and result allocation, and the array variants of those \
+functions, are only available when the corresponding module flags are enabled: \
+`dynamic_qubit_management` for qubit allocation, `dynamic_result_management` for result \
+allocation, and additionally `arrays` for the `*_array_*` functions.",
+        example: r#"; before
+!2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+...
+%q = call ptr @__quantum__rt__qubit_allocate(ptr null)
+
+; after
+!2 = !{i32 1, !"dynamic_qubit_management", i1 true}"#,
+    },
+    Explanation {
+        code: codes::DYNAMIC_RESULT_OUTSIDE_ENTRY_BLOCK,
+        title: "Dynamic result allocated outside the entry block",
+        explanation: "Dynamically allocated results are lowered to stack slots, which must be \
+created once in the entry block of the entry point. Allocating results inside loops, branches \
+or helper functions is not supported.",
+        example: r#"; before
+loop:
+  %r = call ptr @__quantum__rt__result_allocate(ptr null)
+
+; after
+entry:
+  %r = call ptr @__quantum__rt__result_allocate(ptr null)
+  br label %loop"#,
+    },
+    Explanation {
+        code: codes::MISSING_WASM_EXPORT,
+        title: "WASM function not exported",
+        explanation: "Every external function carrying the `wasm` attribute is called through the \
+WASM module passed alongside the program, so the module must export a function of the same name.",
+        example: r#"; QIR
+declare i32 @add_one(i32) #1
+attributes #1 = { "wasm" }
+
+; WASM (text format)
+(func (export "add_one") (param i32) (result i32) ...)"#,
+    },
+    Explanation {
+        code: codes::WASM_ARITY_MISMATCH,
+        title: "WASM function arity mismatch",
+        explanation: "A `wasm` function is declared with a different number of parameters than the \
+WASM export of the same name takes. Declare exactly the parameters of the export.",
+        example: r#"; before, for (func (export "add") (param i32 i32) (result i32))
+declare i32 @add(i32) #1
+
+; after
+declare i32 @add(i32, i32) #1"#,
+    },
+    Explanation {
+        code: codes::WASM_SIGNATURE_MISMATCH,
+        title: "WASM function type mismatch",
+        explanation: "A parameter or result of a `wasm` function is declared with a type that does \
+not correspond to the WASM export: WASM `i32`, `i64`, `f32` and `f64` correspond to LLVM `i32`, \
+`i64`, `float` and `double`, and a function without results is declared `void`.",
+        example: r#"; before, for (func (export "scale") (param f64) (result f64))
+declare i64 @scale(i64) #1
+
+; after
+declare double @scale(double) #1"#,
+    },
+    Explanation {
+        code: codes::MULTI_CONTROLLED_ARITY_OVERFLOW,
+        title: "Multi-controlled gate uses more qubits than the program has",
+        explanation: "`__quantum__qis__mcx<N>__body` and `__quantum__qis__mcz<N>__body` take N \
+qubit operands, N-1 controls followed by the target. N may not exceed the entry point's \
+`required_num_qubits`, since the gate could not act on that many distinct qubits. Likewise, \
+the controls of a `__ctl` or `__ctladj` gate plus its target may not exceed \
+`required_num_qubits`.",
+        example: r#"; before ("required_num_qubits"="3")
+call void @__quantum__qis__mcx4__body(ptr null, ptr inttoptr (i64 1 to ptr), ptr inttoptr (i64 2 to ptr), ptr inttoptr (i64 3 to ptr))
+
+; after
+call void @__quantum__qis__mcx3__body(ptr null, ptr inttoptr (i64 1 to ptr), ptr inttoptr (i64 2 to ptr))"#,
+    },
+    Explanation {
+        code: codes::UNSUPPORTED_RUNTIME_ARRAY,
+        title: "Q# runtime array or tuple used outside a controlled gate",
+        explanation: "Q# passes the controls of `__ctl` and `__ctladj` gates in a runtime array \
+created by `__quantum__rt__array_create_1d`, and the angle and target of controlled rotations in a \
+tuple created by `__quantum__rt__tuple_create`. The Quantinuum runtime has neither, so qir-qis \
+lowers them to stack arrays. This needs a constant length and element size (8, a qubit \
+pointer), element pointers taken at constant indices, and no uses other than the controlled gate \
+calls and reference and alias count updates. Tuples need a constant size and may only be read, \
+written and passed to controlled rotations.",
+        example: r#"; before
+%n = call i64 @get_count()
+%controls = call ptr @__quantum__rt__array_create_1d(i32 8, i64 %n)
+
+; after
+%controls = call ptr @__quantum__rt__array_create_1d(i32 8, i64 2)"#,
+    },
+    Explanation {
+        code: codes::DATA_LAYOUT_PRESENT,
+        title: "Module sets a data layout",
+        explanation: "The compiler replaces the module's data layout with the one of the selected \
+target, so the layout in the input has no effect.",
+        example: r#"; remove the line
+target datalayout = "e-m:e-i64:64-n32:64-S128""#,
+    },
+    Explanation {
+        code: codes::TARGET_TRIPLE_PRESENT,
+        title: "Module sets a target triple",
+        explanation: "The compiler replaces the module's target triple with the one of the \
+selected target, so the triple in the input has no effect.",
+        example: r#"; remove the line
+target triple = "x86_64-unknown-linux-gnu""#,
+    },
+    Explanation {
+        code: codes::QSHARP_M_SYNONYM,
+        title: "Q# `m` measurement used",
+        explanation: "`__quantum__qis__m__body` is emitted by the Q# QDK as a synonym for \
+`__quantum__qis__mz__body` and is compiled as such. Prefer the standard name.",
+        example: r#"; before
+call void @__quantum__qis__m__body(ptr %q, ptr %r)
+
+; after
+call void @__quantum__qis__mz__body(ptr %q, ptr %r)"#,
+    },
+    Explanation {
+        code: codes::QSHARP_MRESETZ,
+        title: "Q# `mresetz` measurement used",
+        explanation: "`__quantum__qis__mresetz__body` is emitted by the Q# QDK and is compiled as a \
+measurement followed by a reset. Prefer spelling both operations out.",
+        example: r#"; before
+call void @__quantum__qis__mresetz__body(ptr %q, ptr %r)
+
+; after
+call void @__quantum__qis__mz__body(ptr %q, ptr %r)
+call void @__quantum__qis__reset__body(ptr %q)"#,
+    },
+    Explanation {
+        code: codes::NO_GLOBALS,
+        title: "Module has no globals",
+        explanation: "Output labels are stored as global string constants. A module without any \
+globals records all output unlabelled, which is usually a mistake in the producer.",
+        example: r#"@r0 = internal constant [3 x i8] c"r0\00"
+...
+call void @__quantum__rt__result_record_output(ptr null, ptr @r0)"#,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use super::{CATALOG, explain};
+    use crate::diagnostics::codes;
+
+    #[test]
+    fn test_catalog_is_sorted_and_unique() {
+        assert!(CATALOG.windows(2).all(|pair| pair[0].code < pair[1].code));
+    }
+
+    #[test]
+    fn test_explain_finds_entries_ignoring_case() {
+        let entry = explain("e021").expect("E021 should be documented");
+        assert_eq!(entry.code, codes::MISSING_CAPABILITY);
+        assert!(
+            entry
+                .to_string()
+                .starts_with("E021: Runtime function requires a capability flag\n")
+        );
+        assert!(explain("E999").is_none());
+    }
+}
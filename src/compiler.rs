@@ -0,0 +1,300 @@
+//! A compilation session reusing its setup across many programs.
+//!
+//! [`CompileOptions::compile`] creates a fresh LLVM context for every
+//! program and rebuilds everything the translation needs: the gate
+//! decompositions, the target machine and the WASM function tables. A
+//! [`Compiler`] builds them once for a given [`CompileOptions`] and shares
+//! them between every program it translates:
+//!
+//! ```no_run
+//! use inkwell::context::Context;
+//! use qir_qis::{CompileOptions, Compiler};
+//!
+//! # fn main() -> Result<(), qir_qis::Error> {
+//! # let programs: Vec<Vec<u8>> = Vec::new();
+//! let ctx = Context::create();
+//! let compiler = Compiler::new(&ctx, CompileOptions::new())?;
+//! for bc_bytes in &programs {
+//!     let output = compiler.compile(bc_bytes)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::targets::TargetMachine;
+use inkwell::values::InstructionValue;
+
+use crate::{
+    CompileOptions, CompileOutput, Error, LoweredModule, PassContext, Pipeline, SourceLocation,
+    ValidationReport, create_module_from_ir_text,
+    decompose::{Decompositions, build_decomposition_module},
+    opt::session_target_machine,
+    parse_borrowed_bitcode_module,
+    source_map::SourceMap,
+    utils::WasmSignature,
+};
+
+/// Translates many QIR programs with the same [`CompileOptions`].
+///
+/// Every program is parsed into the borrowed LLVM [`Context`], which is not
+/// thread-safe: use one `Compiler`, and one context, per thread. The context
+/// keeps the types and constants interned by each program, so a long-lived
+/// session grows with the variety of the programs it translates.
+#[derive(Debug)]
+pub struct Compiler<'ctx> {
+    ctx: &'ctx Context,
+    options: CompileOptions,
+    pipeline: Pipeline,
+    decompositions: Decompositions<'ctx>,
+    wasm_signatures: Option<BTreeMap<String, WasmSignature>>,
+    target_machine: Option<TargetMachine>,
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Prepare to translate programs in `ctx` with `options`, running the
+    /// default [`Pipeline`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Wasm`] if the WASM module cannot be parsed,
+    /// [`Error::Target`] if the target machine cannot be created, or
+    /// [`Error::Lowering`] if the decompositions cannot be built.
+    pub fn new(ctx: &'ctx Context, options: CompileOptions) -> Result<Self, Error> {
+        let decompositions = build_decomposition_module(ctx, options.decompositions())?;
+        let wasm_signatures = crate::get_wasm_signatures(options.wasm())?;
+        let target_machine =
+            session_target_machine(options.opt_level(), options.target(), options.emit())?;
+        Ok(Self {
+            ctx,
+            options,
+            pipeline: Pipeline::default(),
+            decompositions,
+            wasm_signatures,
+            target_machine,
+        })
+    }
+
+    /// Run `pipeline` instead of the default one.
+    #[must_use]
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// The options every program is translated with.
+    #[must_use]
+    pub const fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    /// The pipeline run over every program.
+    #[must_use]
+    pub const fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    /// Translate QIR bitcode to QIS, as [`CompileOptions::compile_with`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Parse`] if the bitcode cannot be parsed, the error of
+    /// the first pass that fails, or an [`Error::Target`] if the module
+    /// cannot be emitted.
+    pub fn compile(&self, bc_bytes: &[u8]) -> Result<CompileOutput, Error> {
+        let module = parse_borrowed_bitcode_module(self.ctx, bc_bytes, "bitcode")?;
+        self.compile_module(&module, None)
+    }
+
+    /// Translate QIR LLVM IR text to QIS, as
+    /// [`CompileOptions::compile_ll_with`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Parse`] if the text cannot be parsed, the error of
+    /// the first pass that fails, or an [`Error::Target`] if the module
+    /// cannot be emitted.
+    pub fn compile_ll(&self, ll_text: &str, source_name: &str) -> Result<CompileOutput, Error> {
+        let module = create_module_from_ir_text(self.ctx, ll_text, source_name)?;
+        let locations = SourceMap::new(source_name, ll_text).instruction_locations(&module);
+        self.compile_module(&module, Some(&locations))
+    }
+
+    /// Validate QIR bitcode against the WASM module and the registered gates
+    /// of the session, as [`CompileOptions::validate`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Parse`] if the bitcode cannot be parsed.
+    pub fn validate(&self, bc_bytes: &[u8]) -> Result<ValidationReport, Error> {
+        let module = parse_borrowed_bitcode_module(self.ctx, bc_bytes, "bitcode")?;
+        Ok(self.validate_module(&module))
+    }
+
+    /// Validate QIR LLVM IR text against the WASM module and the registered
+    /// gates of the session, as [`CompileOptions::validate_ll`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Parse`] if the text cannot be parsed.
+    pub fn validate_ll(&self, ll_text: &str, source_name: &str) -> Result<ValidationReport, Error> {
+        let module = create_module_from_ir_text(self.ctx, ll_text, source_name)?;
+        let mut report = self.validate_module(&module);
+        report.resolve_locations(&SourceMap::new(source_name, ll_text));
+        Ok(report)
+    }
+
+    /// Lower a QIR module of the session's context to QIS in place, as
+    /// [`CompileOptions::lower_module`].
+    ///
+    /// # Errors
+    /// Returns the error of the first pass that fails.
+    pub fn lower<'a>(&self, module: &'a Module<'ctx>) -> Result<LoweredModule<'a, 'ctx>, Error> {
+        let mut cx = self.pass_context(module, None);
+        self.pipeline.run(&mut cx)?;
+        Ok(LoweredModule {
+            module,
+            warnings: cx.warnings,
+            dumps: cx.dumps,
+        })
+    }
+
+    /// Validate a QIR module against the WASM module and the registered gates
+    /// of the session, as [`validate_qir_module`](crate::validate_qir_module).
+    #[must_use]
+    pub fn validate_module(&self, module: &Module<'_>) -> ValidationReport {
+        crate::validate_module(
+            module,
+            self.wasm_signatures.as_ref(),
+            Some(self.options.decompositions()),
+        )
+    }
+
+    fn compile_module(
+        &self,
+        module: &Module<'ctx>,
+        locations: Option<&HashMap<InstructionValue<'ctx>, SourceLocation>>,
+    ) -> Result<CompileOutput, Error> {
+        let cx = self.pass_context(module, locations);
+        crate::run_pipeline(cx, &self.pipeline, self.options.emit())
+    }
+
+    fn pass_context<'a>(
+        &'a self,
+        module: &'a Module<'ctx>,
+        locations: Option<&'a HashMap<InstructionValue<'ctx>, SourceLocation>>,
+    ) -> PassContext<'a, 'ctx> {
+        let mut cx = PassContext::new(
+            self.ctx,
+            module,
+            self.options.opt_level(),
+            self.options.target(),
+        );
+        cx.wasm_bytes = self.options.wasm();
+        cx.deny_warnings = self.options.deny_warnings();
+        cx.locations = locations;
+        cx.decompositions = Some(&self.decompositions);
+        cx.target_machine = self.target_machine.as_ref();
+        cx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use inkwell::context::Context;
+
+    use super::Compiler;
+    use crate::{CompileOptions, Emit, Pipeline, qir_ll_to_bc};
+
+    const FIXTURES: &[&str] = &[
+        "tests/data/base.ll",
+        "tests/data/adaptive.ll",
+        "tests/data/qir2_base.ll",
+        "tests/data/dynamic_qubit_alloc.ll",
+    ];
+
+    /// Declarations and definitions of `ir`, which do not depend on the order
+    /// in which functions were added to the module.
+    fn functions(ir: &[u8]) -> Vec<String> {
+        let mut functions: Vec<String> = String::from_utf8_lossy(ir)
+            .lines()
+            .filter(|line| line.starts_with("declare ") || line.starts_with("define "))
+            .map(str::to_owned)
+            .collect();
+        functions.sort();
+        functions
+    }
+
+    #[test]
+    fn test_session_matches_one_off_compilation() {
+        let options = CompileOptions::new().with_emit(Emit::LlvmIr);
+        let ctx = Context::create();
+        let compiler = Compiler::new(&ctx, options.clone()).expect("Failed to create compiler");
+        for path in FIXTURES {
+            let ll_text = std::fs::read_to_string(path).expect("Failed to read fixture");
+            let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert fixture");
+
+            let expected = options
+                .compile(&bc_bytes)
+                .expect("One-off compilation failed");
+            let first = compiler
+                .compile(&bc_bytes)
+                .expect("Session compilation failed");
+            let second = compiler
+                .compile(&bc_bytes)
+                .expect("Repeated compilation failed");
+            assert_eq!(
+                functions(&first.artifact),
+                functions(&expected.artifact),
+                "{path}"
+            );
+            assert_eq!(first.artifact, second.artifact, "{path}");
+
+            let from_text = compiler
+                .compile_ll(&ll_text, path)
+                .expect("Session compilation of text failed");
+            assert_eq!(
+                functions(&from_text.artifact),
+                functions(&expected.artifact),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_session_validates_and_runs_custom_pipelines() {
+        let ctx = Context::create();
+        let compiler = Compiler::new(&ctx, CompileOptions::new().with_emit(Emit::LlvmIr))
+            .expect("Failed to create compiler")
+            .with_pipeline({
+                let mut pipeline = Pipeline::default();
+                pipeline
+                    .stop_after("decompose")
+                    .expect("decompose is a stage");
+                pipeline
+            });
+        let ll_text =
+            std::fs::read_to_string("tests/data/base.ll").expect("Failed to read fixture");
+        let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert fixture");
+
+        let report = compiler.validate(&bc_bytes).expect("Validation failed");
+        assert!(!report.has_errors());
+        let output = compiler
+            .compile(&bc_bytes)
+            .expect("Session compilation failed");
+        let ir = String::from_utf8(output.artifact).expect("IR should be UTF-8");
+        assert!(ir.contains("@Entry_Point_Name"));
+        assert!(ir.contains("define linkonce_odr void @__quantum__qis__h__body"));
+        assert!(compiler.validate(b"not bitcode").is_err());
+
+        let module = crate::parse_bitcode_module(&ctx, &bc_bytes, "bitcode")
+            .expect("Failed to parse fixture");
+        assert_eq!(compiler.validate_module(&module), report);
+        let lowered = compiler.lower(&module).expect("Session lowering failed");
+        assert!(
+            lowered
+                .module
+                .get_function("__quantum__qis__h__body")
+                .is_some_and(|h| h.count_basic_blocks() > 0)
+        );
+    }
+}
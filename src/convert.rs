@@ -809,6 +809,54 @@ pub fn replace_rz_call<'a>(
     .map_err(|e| e.to_string())
 }
 
+/// Replaces a call to `__quantum__qis__zzmax__body` with a call to `___rzz`
+/// by π/2, the maximally entangling ZZ rotation.
+///
+/// # Errors
+/// Returns an error if the replacement fails.
+pub fn replace_zzmax_call<'a>(
+    ctx: &'a Context,
+    module: &Module<'a>,
+    old_call: InstructionValue<'a>,
+    dynamic_qubit_management: bool,
+) -> Result<(), String> {
+    replace_native_call(
+        ctx,
+        module,
+        old_call,
+        "___rzz",
+        &[
+            ctx.i64_type().into(), // qubit handle
+            ctx.i64_type().into(), // qubit handle
+            ctx.f64_type().into(), // angle
+        ],
+        |args, builder| {
+            let q1 = get_native_qubit_handle(
+                ctx,
+                module,
+                builder,
+                args[0].into_pointer_value(),
+                dynamic_qubit_management,
+                "qbit1",
+            )?;
+            let q2 = get_native_qubit_handle(
+                ctx,
+                module,
+                builder,
+                args[1].into_pointer_value(),
+                dynamic_qubit_management,
+                "qbit2",
+            )?;
+            let angle = ctx
+                .f64_type()
+                .const_float(std::f64::consts::FRAC_PI_2)
+                .as_basic_value_enum();
+            Ok(vec![q1, q2, angle])
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
 /// Replaces a call to `__quantum__qis__rzz__body` with a call to `___rzz`.
 ///
 /// # Errors
@@ -1314,7 +1362,7 @@ mod tests {
     use std::path::Path;
 
     use super::*;
-    use crate::{qir_ll_to_bc, qir_qis};
+    use crate::{CompileOptions, OptLevel, Target, qir_ll_to_bc};
 
     #[test]
     fn test_is_i8_array_type_true() {
@@ -2184,12 +2232,18 @@ entry:
         qir_ll_to_bc(&ll).expect("Failed to convert LLVM IR to bitcode")
     }
 
+    fn aarch64_o2() -> CompileOptions {
+        CompileOptions::new()
+            .with_opt_level(OptLevel::O2)
+            .with_target(Target::Aarch64)
+    }
+
     #[test]
     fn test_ir_fn_main_errors() {
         let ll_path = Path::new("tests/data/bad/ir_fn_main.ll");
         let qir_bytes = get_qir_bytes(ll_path);
 
-        assert!(qir_qis::qir_to_qis(qir_bytes.into(), 2, "aarch64", None).is_err());
+        assert!(aarch64_o2().compile(&qir_bytes).is_err());
     }
 
     #[test]
@@ -2197,8 +2251,8 @@ entry:
         let ll_path = Path::new("tests/data/bad/mz_to_creg_bit.ll");
         let qir_bytes = get_qir_bytes(ll_path);
 
-        assert!(qir_qis::validate_qir(qir_bytes.clone().into(), None).is_err());
-        assert!(qir_qis::qir_to_qis(qir_bytes.into(), 2, "aarch64", None).is_err());
+        assert!(crate::validate_qir(&qir_bytes, None).is_err());
+        assert!(aarch64_o2().compile(&qir_bytes).is_err());
     }
 
     #[test]
@@ -2300,7 +2354,7 @@ entry:
         let ll_path = Path::new("tests/data/bad/pytket_qir_12.ll");
         let qir_bytes = get_qir_bytes(ll_path);
 
-        assert!(qir_qis::qir_to_qis(qir_bytes.into(), 2, "aarch64", None).is_err());
+        assert!(aarch64_o2().compile(&qir_bytes).is_err());
     }
 
     #[test]
@@ -2308,7 +2362,7 @@ entry:
         let ll_path = Path::new("tests/data/bad/barrier_invalid.ll");
         let qir_bytes = get_qir_bytes(ll_path);
 
-        assert!(qir_qis::validate_qir(qir_bytes.into(), None).is_err());
+        assert!(crate::validate_qir(&qir_bytes, None).is_err());
     }
 
     #[test]
@@ -2370,6 +2424,11 @@ entry:
             #[case("tests/data/bad/inf_loop.ll")]
             // RNG and get shot number test with Adaptive-profile switch
             #[case("tests/data/ArithOps_switch.ll")]
+            // Gate decomposition tests
+            #[case("tests/data/two_qubit_gates.ll")]
+            #[case("tests/data/multi_controlled_gates.ll")]
+            #[case("tests/data/controlled_gates.ll")]
+            #[case("tests/data/qsharp_controlled_gates.ll")]
             #[trace]
             $item
         };
@@ -2382,7 +2441,7 @@ entry:
 
         let ll_path = Path::new(llpath);
         let qir_bytes = get_qir_bytes(ll_path);
-        let qis_bytes = qir_qis::qir_to_qis(qir_bytes.into(), 2, "aarch64", None).unwrap();
+        let qis_bytes = aarch64_o2().compile(&qir_bytes).unwrap().artifact;
 
         let context = Context::create();
         let qis_text = crate::parse_bitcode_module(&context, &qis_bytes, "qis_module")
@@ -2419,7 +2478,12 @@ entry:
         // Keep this as a pure conversion/parsing smoke test on Windows.
         // TargetMachine creation for optimized native codegen can be unstable
         // on some Windows LLVM environments and cause access violations.
-        let qis_bytes = qir_qis::qir_to_qis(qir_bytes.into(), 0, "native", None).unwrap();
+        let qis_bytes = CompileOptions::new()
+            .with_opt_level(OptLevel::O0)
+            .with_target(Target::Native)
+            .compile(&qir_bytes)
+            .unwrap()
+            .artifact;
 
         let context = Context::create();
         let parsed = crate::parse_bitcode_module(&context, &qis_bytes, "qis_module")
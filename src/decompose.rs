@@ -11,11 +11,23 @@
 //! ## Gate Decompositions
 //! See table at
 //! <https://github.com/quantinuum/qir-qis/blob/main/qtm-qir-reference.md#decompositions>
+//!
+//! Further `__quantum__qis__*` gates can be decomposed by registering them in
+//! a [`DecompositionRegistry`].
 
 use inkwell::module::{Linkage, Module};
-use inkwell::types::PointerType;
+use inkwell::types::{BasicMetadataTypeEnum, PointerType};
+use inkwell::values::{
+    BasicValue, CallSiteValue, FloatValue, InstructionOpcode, InstructionValue, Operand,
+    PointerValue,
+};
 use inkwell::{builder::Builder, context::Context, values::FunctionValue};
+use std::collections::BTreeMap;
 use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::Error;
 
 pub struct QirTypes<'ctx> {
     pub qubit_ptr_type: PointerType<'ctx>,
@@ -29,23 +41,540 @@ impl<'ctx> QirTypes<'ctx> {
     }
 }
 
-/// Struct to hold native QIR gates
-struct NativeGates<'ctx> {
+/// The native gates a decomposition is built from, emitting calls at the
+/// position of a builder.
+pub struct NativeGates<'a, 'ctx> {
+    context: &'ctx Context,
+    builder: &'a Builder<'ctx>,
     rxy: FunctionValue<'ctx>,
     rz: FunctionValue<'ctx>,
     rzz: FunctionValue<'ctx>,
 }
 
+impl<'a, 'ctx> NativeGates<'a, 'ctx> {
+    /// The context of the module the decomposition is built in.
+    #[must_use]
+    pub const fn context(&self) -> &'ctx Context {
+        self.context
+    }
+
+    /// The builder the calls are emitted with, e.g. to compute angles.
+    #[must_use]
+    pub const fn builder(&self) -> &'a Builder<'ctx> {
+        self.builder
+    }
+
+    /// A constant angle, in radians.
+    #[must_use]
+    pub fn angle(&self, radians: f64) -> FloatValue<'ctx> {
+        self.context.f64_type().const_float(radians)
+    }
+
+    /// Emit `rxy(theta, phi, qubit)`.
+    ///
+    /// # Errors
+    /// Returns an error if the call cannot be built.
+    pub fn rxy(
+        &self,
+        theta: FloatValue<'ctx>,
+        phi: FloatValue<'ctx>,
+        qubit: PointerValue<'ctx>,
+    ) -> Result<(), String> {
+        self.call(self.rxy, &[theta.into(), phi.into(), qubit.into()])
+    }
+
+    /// Emit `rz(theta, qubit)`.
+    ///
+    /// # Errors
+    /// Returns an error if the call cannot be built.
+    pub fn rz(&self, theta: FloatValue<'ctx>, qubit: PointerValue<'ctx>) -> Result<(), String> {
+        self.call(self.rz, &[theta.into(), qubit.into()])
+    }
+
+    /// Emit `rzz(theta, qubit1, qubit2)`.
+    ///
+    /// # Errors
+    /// Returns an error if the call cannot be built.
+    pub fn rzz(
+        &self,
+        theta: FloatValue<'ctx>,
+        qubit1: PointerValue<'ctx>,
+        qubit2: PointerValue<'ctx>,
+    ) -> Result<(), String> {
+        self.call(self.rzz, &[theta.into(), qubit1.into(), qubit2.into()])
+    }
+
+    /// Emit `factor * angle`, for angles only known at run time.
+    fn scaled(&self, angle: FloatValue<'ctx>, factor: f64) -> Result<FloatValue<'ctx>, String> {
+        self.builder
+            .build_float_mul(angle, self.angle(factor), "")
+            .map_err(|e| e.to_string())
+    }
+
+    fn call(
+        &self,
+        gate: FunctionValue<'ctx>,
+        args: &[inkwell::values::BasicMetadataValueEnum<'ctx>],
+    ) -> Result<(), String> {
+        self.builder
+            .build_call(gate, args, "")
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
 /// Adds QIR decompositions to the given module.
 /// # Errors
-/// Returns an error if the module verification fails.
-pub fn add_decompositions<'ctx>(ctx: &'ctx Context, module: &Module<'ctx>) -> Result<(), String> {
-    build_decompositions(ctx, module)?;
+/// Returns [`Error::Lowering`] if a decomposition cannot be built, or
+/// [`Error::Verifier`] if the module verification fails.
+pub fn add_decompositions<'ctx>(ctx: &'ctx Context, module: &Module<'ctx>) -> Result<(), Error> {
+    build_decompositions(ctx, module)
+        .map_err(|e| Error::Lowering(format!("Failed to add QIR decompositions: {e}")))?;
     crate::llvm_verify::verify_module(module, "Module verification failed")?;
 
     Ok(())
 }
 
+/// The QIR decompositions, and those of a [`DecompositionRegistry`], built
+/// once by [`build_decomposition_module`] to be linked into several QIR
+/// modules with [`link_decompositions`].
+///
+/// Each gate is kept in a module of its own, with the declarations of the
+/// native gates it calls, so that a QIR module only copies and links in the
+/// gates it uses.
+#[derive(Debug)]
+pub struct Decompositions<'ctx> {
+    gates: BTreeMap<String, Module<'ctx>>,
+}
+
+impl<'ctx> Decompositions<'ctx> {
+    /// The module defining the decomposition of `name`, if there is one.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Module<'ctx>> {
+        self.gates.get(name)
+    }
+}
+
+/// Builds the QIR decompositions, and those of `registry`, to be linked into
+/// several QIR modules with [`link_decompositions`].
+/// # Errors
+/// Returns [`Error::Lowering`] if a decomposition cannot be built, or
+/// [`Error::Verifier`] if the module verification fails.
+pub fn build_decomposition_module<'ctx>(
+    ctx: &'ctx Context,
+    registry: &DecompositionRegistry,
+) -> Result<Decompositions<'ctx>, Error> {
+    let decompositions = ctx.create_module("qir_qis_decompositions");
+    add_decompositions(ctx, &decompositions)?;
+    registry.add_to(ctx, &decompositions)?;
+    let names: Vec<String> = decompositions
+        .get_functions()
+        .filter(|function| function.count_basic_blocks() > 0)
+        .map(|function| function.get_name().to_string_lossy().into_owned())
+        .collect();
+    let gates = names
+        .into_iter()
+        .map(|name| {
+            let gate = decompositions.clone();
+            keep_only_definition(&gate, &name);
+            (name, gate)
+        })
+        .collect();
+    Ok(Decompositions { gates })
+}
+
+/// Delete the definitions in `module` other than `name` and those it calls.
+fn keep_only_definition(module: &Module<'_>, name: &str) {
+    // Decompositions only call native gates, but keep whatever a definition
+    // still uses, deleting the others until none is left unused.
+    loop {
+        let unused: Vec<FunctionValue<'_>> = module
+            .get_functions()
+            .filter(|function| {
+                function.count_basic_blocks() > 0
+                    && function.get_name().to_bytes() != name.as_bytes()
+                    && function
+                        .as_global_value()
+                        .as_pointer_value()
+                        .get_first_use()
+                        .is_none()
+            })
+            .collect();
+        if unused.is_empty() {
+            return;
+        }
+        for function in unused {
+            // SAFETY: the definition has no uses left to refer to it.
+            unsafe { function.delete() };
+        }
+    }
+}
+
+/// Links copies of the `decompositions` of the gates `module` declares into
+/// `module`, together with the native gates they call.
+///
+/// Gates the module defines itself are kept. The `mcx<n>` and `mcz<n>` gates
+/// depend on the arities the module declares, and the `__ctl` and `__ctladj`
+/// gates on the controls of each call, so they are decomposed in `module`
+/// directly.
+/// # Errors
+/// Returns [`Error::Lowering`] if linking or a decomposition fails, or
+/// [`Error::Verifier`] if the module verification fails.
+pub fn link_decompositions<'ctx>(
+    ctx: &'ctx Context,
+    module: &Module<'ctx>,
+    decompositions: &Decompositions<'ctx>,
+) -> Result<(), Error> {
+    let used: Vec<&Module<'ctx>> = module
+        .get_functions()
+        .filter(|function| function.count_basic_blocks() == 0)
+        .filter_map(|function| decompositions.get(function.get_name().to_str().ok()?))
+        .collect();
+    for gate in used {
+        // Match the module so that the linker does not warn about mismatches.
+        let gate = gate.clone();
+        gate.set_triple(&module.get_triple());
+        gate.set_data_layout(&module.get_data_layout());
+        module
+            .link_in_module(gate)
+            .map_err(|e| Error::Lowering(format!("Failed to add QIR decompositions: {e}")))?;
+    }
+    define_multi_controlled_gates(ctx, module)
+        .and_then(|()| decompose_controlled_calls(ctx, module))
+        .map_err(|e| Error::Lowering(format!("Failed to add QIR decompositions: {e}")))?;
+    crate::llvm_verify::verify_module(module, "Module verification failed")
+}
+
+/// Builds the body of a registered gate from the native gates, its `double`
+/// parameters and its qubits.
+pub type DecompositionFn = dyn for<'a, 'ctx> Fn(
+        &NativeGates<'a, 'ctx>,
+        &[FloatValue<'ctx>],
+        &[PointerValue<'ctx>],
+    ) -> Result<(), String>
+    + Send
+    + Sync;
+
+/// Names of the native gates registered decompositions may call.
+const NATIVE_GATES: [&str; 3] = [
+    "__quantum__qis__rxy__body",
+    "__quantum__qis__rz__body",
+    "__quantum__qis__rzz__body",
+];
+
+#[derive(Clone)]
+enum Decomposition {
+    /// Built by a closure, for a gate taking `params` angles then `qubits`
+    /// qubits.
+    Builder {
+        params: usize,
+        qubits: usize,
+        build: Arc<DecompositionFn>,
+    },
+    /// Defined by an LLVM IR snippet.
+    Ir(String),
+}
+
+impl PartialEq for Decomposition {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Builder {
+                    params,
+                    qubits,
+                    build,
+                },
+                Self::Builder {
+                    params: other_params,
+                    qubits: other_qubits,
+                    build: other_build,
+                },
+            ) => {
+                params == other_params && qubits == other_qubits && Arc::ptr_eq(build, other_build)
+            }
+            (Self::Ir(ll_text), Self::Ir(other_ll_text)) => ll_text == other_ll_text,
+            (Self::Builder { .. } | Self::Ir(_), _) => false,
+        }
+    }
+}
+
+impl Eq for Decomposition {}
+
+/// Decompositions of `__quantum__qis__*` gates beyond the built-in ones.
+///
+/// A registered gate is accepted by validation and defined in terms of the
+/// native `rxy`, `rz` and `rzz` gates before lowering, like the built-in
+/// decompositions. Pass the registry to
+/// [`CompileOptions::with_decompositions`](crate::CompileOptions::with_decompositions):
+///
+/// ```no_run
+/// use std::f64::consts::PI;
+///
+/// use qir_qis::{CompileOptions, DecompositionRegistry};
+///
+/// let mut registry = DecompositionRegistry::new();
+/// registry.register("__quantum__qis__sx__body", 0, 1, |gates, _, qubits| {
+///     gates.rxy(gates.angle(PI / 2.0), gates.angle(0.0), qubits[0])
+/// })?;
+/// registry.register_ir(
+///     "__quantum__qis__sy__body",
+///     "define void @__quantum__qis__sy__body(ptr %q) {
+///        call void @__quantum__qis__rxy__body(double 0x3FF921FB54442D18, double 0x3FF921FB54442D18, ptr %q)
+///        ret void
+///      }
+///      declare void @__quantum__qis__rxy__body(double, double, ptr)",
+/// )?;
+/// let options = CompileOptions::new().with_decompositions(registry);
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct DecompositionRegistry {
+    gates: BTreeMap<String, Decomposition>,
+}
+
+impl DecompositionRegistry {
+    /// A registry without any gate.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            gates: BTreeMap::new(),
+        }
+    }
+
+    /// Register `name`, a gate taking `params` `double` angles followed by
+    /// `qubits` qubits, whose body `build` emits with the native gates.
+    ///
+    /// # Errors
+    /// Returns an error if `name` is not a `__quantum__qis__` function, is
+    /// already supported or is already registered.
+    pub fn register<F>(
+        &mut self,
+        name: &str,
+        params: usize,
+        qubits: usize,
+        build: F,
+    ) -> Result<&mut Self, String>
+    where
+        F: for<'a, 'ctx> Fn(
+                &NativeGates<'a, 'ctx>,
+                &[FloatValue<'ctx>],
+                &[PointerValue<'ctx>],
+            ) -> Result<(), String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.check_name(name)?;
+        let build: Arc<DecompositionFn> = Arc::new(build);
+        self.gates.insert(
+            name.to_owned(),
+            Decomposition::Builder {
+                params,
+                qubits,
+                build,
+            },
+        );
+        Ok(self)
+    }
+
+    /// Register `name`, a gate defined by the LLVM IR `ll_text`.
+    ///
+    /// The IR must define `name` returning `void`, calling only the native
+    /// gates `__quantum__qis__rxy__body`, `__quantum__qis__rz__body` and
+    /// `__quantum__qis__rzz__body`, which it declares.
+    ///
+    /// # Errors
+    /// Returns an error if `name` cannot be registered, as for
+    /// [`register`](Self::register), or if `ll_text` does not define it as
+    /// required.
+    pub fn register_ir(&mut self, name: &str, ll_text: &str) -> Result<&mut Self, String> {
+        self.check_name(name)?;
+        check_ir_decomposition(name, ll_text)?;
+        self.gates
+            .insert(name.to_owned(), Decomposition::Ir(ll_text.to_owned()));
+        Ok(self)
+    }
+
+    /// Whether `name` is registered.
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.gates.contains_key(name)
+    }
+
+    /// Names of the registered gates, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.gates.keys().map(String::as_str)
+    }
+
+    /// Whether no gate is registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.gates.is_empty()
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), String> {
+        if !name.starts_with("__quantum__qis__") {
+            return Err(format!(
+                "Cannot register `{name}`: decomposed gates must be `__quantum__qis__` functions"
+            ));
+        }
+        if crate::aux::is_builtin_qis_function(name) {
+            return Err(format!("Cannot register `{name}`: it is already supported"));
+        }
+        if self.contains(name) {
+            return Err(format!(
+                "Cannot register `{name}`: it is already registered"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Define the registered gates in `module`, except those it defines
+    /// itself.
+    /// # Errors
+    /// Returns [`Error::Lowering`] if a decomposition cannot be built, or
+    /// [`Error::Verifier`] if the module verification fails.
+    pub(crate) fn add_to<'ctx>(
+        &self,
+        ctx: &'ctx Context,
+        module: &Module<'ctx>,
+    ) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let qir_types = QirTypes::new(ctx);
+        let builder = ctx.create_builder();
+        let native_gates = NativeGates {
+            context: ctx,
+            builder: &builder,
+            rxy: declare_rxy(ctx, module, &qir_types),
+            rz: declare_rz(ctx, module, &qir_types),
+            rzz: declare_rzz(ctx, module, &qir_types),
+        };
+        for (name, decomposition) in &self.gates {
+            if module
+                .get_function(name)
+                .is_some_and(|f| f.count_basic_blocks() > 0)
+            {
+                continue;
+            }
+            let added = match decomposition {
+                Decomposition::Builder {
+                    params,
+                    qubits,
+                    build,
+                } => define_registered_gate(
+                    &native_gates,
+                    module,
+                    &qir_types,
+                    name,
+                    (*params, *qubits),
+                    build.as_ref(),
+                ),
+                Decomposition::Ir(ll_text) => link_registered_gate(ctx, module, name, ll_text),
+            };
+            added.map_err(|e| {
+                Error::Lowering(format!("Failed to add decomposition of `{name}`: {e}"))
+            })?;
+        }
+        crate::llvm_verify::verify_module(module, "Module verification failed")
+    }
+}
+
+impl fmt::Debug for DecompositionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+/// Define `name` in `module` with the body built by `build`.
+fn define_registered_gate<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    name: &str,
+    (params, qubits): (usize, usize),
+    build: &DecompositionFn,
+) -> Result<(), String> {
+    let angle_names: Vec<String> = (0..params).map(|i| format!("angle{i}")).collect();
+    let qubit_names: Vec<String> = (0..qubits).map(|i| format!("qubit{i}")).collect();
+    define_gate(
+        native,
+        module,
+        qir_types,
+        name,
+        (
+            &angle_names.iter().map(String::as_str).collect::<Vec<_>>(),
+            &qubit_names.iter().map(String::as_str).collect::<Vec<_>>(),
+        ),
+        |angles, qubits| build(native, angles, qubits),
+    )
+}
+
+/// Link the definition of `name` in `ll_text` into `module`.
+fn link_registered_gate<'ctx>(
+    ctx: &'ctx Context,
+    module: &Module<'ctx>,
+    name: &str,
+    ll_text: &str,
+) -> Result<(), String> {
+    let snippet =
+        crate::create_module_from_ir_text(ctx, ll_text, name).map_err(|e| e.to_string())?;
+    snippet.set_triple(&module.get_triple());
+    snippet.set_data_layout(&module.get_data_layout());
+    // Linked with external linkage, since the linker skips `linkonce_odr`
+    // definitions that the module does not reference yet.
+    module.link_in_module(snippet).map_err(|e| e.to_string())?;
+    if let Some(gate) = module.get_function(name) {
+        gate.set_linkage(Linkage::LinkOnceODR);
+    }
+    Ok(())
+}
+
+/// Check that `ll_text` defines the decomposition of `name` as
+/// [`DecompositionRegistry::register_ir`] requires.
+fn check_ir_decomposition(name: &str, ll_text: &str) -> Result<(), String> {
+    let invalid = |reason: String| format!("Cannot register `{name}`: {reason}");
+    let ctx = Context::create();
+    let module = crate::create_module_from_ir_text(&ctx, ll_text, name)
+        .map_err(|e| invalid(e.to_string()))?;
+    crate::llvm_verify::verify_module(&module, "invalid LLVM IR")
+        .map_err(|e| invalid(e.to_string()))?;
+    let gate = module
+        .get_function(name)
+        .filter(|f| f.count_basic_blocks() > 0)
+        .ok_or_else(|| invalid(format!("the LLVM IR does not define `{name}`")))?;
+    if gate.get_type().get_return_type().is_some() {
+        return Err(invalid("it must return `void`".to_owned()));
+    }
+    for function in module.get_functions() {
+        if function != gate && function.count_basic_blocks() > 0 {
+            return Err(invalid(format!(
+                "the LLVM IR must only define `{name}`, not `{}`",
+                function.get_name().to_string_lossy()
+            )));
+        }
+    }
+    for instr in gate
+        .get_basic_blocks()
+        .into_iter()
+        .flat_map(inkwell::basic_block::BasicBlock::get_instructions)
+        .filter(|instr| instr.get_opcode() == InstructionOpcode::Call)
+    {
+        let callee = inkwell::values::CallSiteValue::try_from(instr)
+            .ok()
+            .and_then(|call| call.get_called_fn_value())
+            .map(|callee| callee.get_name().to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if !NATIVE_GATES.contains(&callee.as_str()) && !callee.starts_with("llvm.") {
+            return Err(invalid(format!(
+                "it may only call the native gates rxy, rz and rzz, not `{callee}`"
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Builds the QIR decompositions for various quantum gates.
 fn build_decompositions<'ctx>(context: &'ctx Context, module: &Module<'ctx>) -> Result<(), String> {
     let qir_types = QirTypes::new(context);
@@ -54,7 +583,13 @@ fn build_decompositions<'ctx>(context: &'ctx Context, module: &Module<'ctx>) ->
     let rxy = declare_rxy(context, module, &qir_types);
     let rz = declare_rz(context, module, &qir_types);
     let rzz = declare_rzz(context, module, &qir_types);
-    let native_gates = NativeGates { rxy, rz, rzz };
+    let native_gates = NativeGates {
+        context,
+        builder: &builder,
+        rxy,
+        rz,
+        rzz,
+    };
 
     // Single-qubit gates
     define_h_gate(context, module, &builder, &qir_types, &native_gates)?;
@@ -73,10 +608,24 @@ fn build_decompositions<'ctx>(context: &'ctx Context, module: &Module<'ctx>) ->
     define_cx_gate(context, module, &builder, &qir_types, &native_gates, "cx")?;
     // Legacy: Combine with above if we deprecate CNOT
     define_cx_gate(context, module, &builder, &qir_types, &native_gates, "cnot")?;
-
-    // Three-qubit gate
-    define_ccx_gate(context, module, &builder, &qir_types, &native_gates)?;
-    Ok(())
+    define_cy_gate(module, &qir_types, &native_gates)?;
+    define_ch_gate(module, &qir_types, &native_gates)?;
+    define_swap_gate(module, &qir_types, &native_gates)?;
+    define_crz_gate(module, &qir_types, &native_gates)?;
+    define_cphase_gate(module, &qir_types, &native_gates)?;
+    define_rxx_gate(module, &qir_types, &native_gates)?;
+    define_ryy_gate(module, &qir_types, &native_gates)?;
+    define_tk2_gate(module, &qir_types, &native_gates)?;
+
+    // Three-qubit gates
+    define_ccx_gate(module, &qir_types, &native_gates)?;
+    define_ccz_gate(module, &qir_types, &native_gates)?;
+    define_cswap_gate(module, &qir_types, &native_gates)?;
+
+    // Multi-controlled gates of the arities the module declares, and
+    // controlled gates with the controls its calls pass
+    define_multi_controlled_gates(context, module)?;
+    decompose_controlled_calls(context, module)
 }
 
 /// Declare the native QIR gate rxy
@@ -164,7 +713,7 @@ fn define_h_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let h = get_or_create_decomposition_function(
         context,
@@ -207,7 +756,7 @@ fn define_x_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let x = get_or_create_decomposition_function(
         context,
@@ -242,7 +791,7 @@ fn define_y_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let y = get_or_create_decomposition_function(
         context,
@@ -277,7 +826,7 @@ fn define_z_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let z = get_or_create_decomposition_function(
         context,
@@ -311,7 +860,7 @@ fn define_s_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let s = get_or_create_decomposition_function(
         context,
@@ -345,7 +894,7 @@ fn define_s_adj_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let s = get_or_create_decomposition_function(
         context,
@@ -379,7 +928,7 @@ fn define_t_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let t = get_or_create_decomposition_function(
         context,
@@ -413,7 +962,7 @@ fn define_t_adj_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let t = get_or_create_decomposition_function(
         context,
@@ -447,7 +996,7 @@ fn define_rx_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let rx = get_or_create_decomposition_function(
         context,
@@ -484,7 +1033,7 @@ fn define_ry_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let ry = get_or_create_decomposition_function(
         context,
@@ -525,7 +1074,7 @@ fn define_cz_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
     let cz = get_or_create_decomposition_function(
         context,
@@ -577,7 +1126,7 @@ fn define_cx_gate<'ctx>(
     module: &Module<'ctx>,
     builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
     fn_name: &str,
 ) -> Result<(), String> {
     let gate_symbol = format!("__quantum__qis__{fn_name}__body");
@@ -636,149 +1185,937 @@ fn define_cx_gate<'ctx>(
     Ok(())
 }
 
+/// Define `name`, taking the `double` parameters named `angles` followed by
+/// the qubits named `qubits`, with the body emitted by `body`, unless the
+/// module already defines it.
+fn define_gate<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    name: &str,
+    (angles, qubits): (&[&str], &[&str]),
+    body: impl FnOnce(&[FloatValue<'ctx>], &[PointerValue<'ctx>]) -> Result<(), String>,
+) -> Result<(), String> {
+    let context = native.context;
+    let param_types: Vec<BasicMetadataTypeEnum> = angles
+        .iter()
+        .map(|_| BasicMetadataTypeEnum::from(context.f64_type()))
+        .chain(qubits.iter().map(|_| qir_types.qubit_ptr_type.into()))
+        .collect();
+    let gate = get_or_create_decomposition_function(context, module, name, &param_types);
+    if gate.get_first_basic_block().is_some() {
+        return Ok(());
+    }
+    let params = gate.get_params();
+    if params.len() != param_types.len() {
+        return Err(format!(
+            "`{name}` is declared with {} parameters instead of {}",
+            params.len(),
+            param_types.len()
+        ));
+    }
+    let (angle_params, qubit_params) = params.split_at(angles.len());
+    let angle_values = angle_params
+        .iter()
+        .zip(angles)
+        .map(|(param, param_name)| {
+            param.set_name(param_name);
+            FloatValue::try_from(*param)
+                .map_err(|()| format!("`{name}` expects a `double` {param_name}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let qubit_values = qubit_params
+        .iter()
+        .zip(qubits)
+        .map(|(param, param_name)| {
+            param.set_name(param_name);
+            PointerValue::try_from(*param)
+                .map_err(|()| format!("`{name}` expects a qubit pointer {param_name}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let entry = context.append_basic_block(gate, "entry");
+    native.builder.position_at_end(entry);
+    body(&angle_values, &qubit_values)?;
+    native
+        .builder
+        .build_return(None)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Emit the CX decomposition of [`define_cx_gate`].
+fn emit_cx<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    control: PointerValue<'ctx>,
+    target: PointerValue<'ctx>,
+) -> Result<(), String> {
+    native.rxy(native.angle(PI / -2.0), native.angle(PI / 2.0), target)?;
+    native.rzz(native.angle(PI / 2.0), control, target)?;
+    native.rz(native.angle(PI / -2.0), control)?;
+    native.rxy(native.angle(PI / 2.0), native.angle(PI), target)?;
+    native.rz(native.angle(PI / -2.0), target)
+}
+
+/// Emit the CZ decomposition of [`define_cz_gate`].
+fn emit_cz<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    control: PointerValue<'ctx>,
+    target: PointerValue<'ctx>,
+) -> Result<(), String> {
+    native.rzz(native.angle(PI / 2.0), control, target)?;
+    native.rz(native.angle(PI / -2.0), target)?;
+    native.rz(native.angle(PI / -2.0), control)
+}
+
+/// Emit `exp(-i theta/2 X⊗X)`: `rzz` conjugated by `ry(π/2)` on both qubits.
+fn emit_rxx<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    theta: FloatValue<'ctx>,
+    qubit1: PointerValue<'ctx>,
+    qubit2: PointerValue<'ctx>,
+) -> Result<(), String> {
+    for qubit in [qubit1, qubit2] {
+        native.rxy(native.angle(PI / 2.0), native.angle(PI / 2.0), qubit)?;
+    }
+    native.rzz(theta, qubit1, qubit2)?;
+    for qubit in [qubit1, qubit2] {
+        native.rxy(native.angle(PI / -2.0), native.angle(PI / 2.0), qubit)?;
+    }
+    Ok(())
+}
+
+/// Emit `exp(-i theta/2 Y⊗Y)`: `rzz` conjugated by `rx(-π/2)` on both qubits.
+fn emit_ryy<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    theta: FloatValue<'ctx>,
+    qubit1: PointerValue<'ctx>,
+    qubit2: PointerValue<'ctx>,
+) -> Result<(), String> {
+    for qubit in [qubit1, qubit2] {
+        native.rxy(native.angle(PI / -2.0), native.angle(0.0), qubit)?;
+    }
+    native.rzz(theta, qubit1, qubit2)?;
+    for qubit in [qubit1, qubit2] {
+        native.rxy(native.angle(PI / 2.0), native.angle(0.0), qubit)?;
+    }
+    Ok(())
+}
+
+/// Define decomposition of CY gate using native gates: CX conjugated by S on
+/// the target.
+fn define_cy_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__cy__body",
+        (&[], &["control", "target"]),
+        |_, qubits| {
+            let [control, target] = qubits else {
+                return Err("CY gate takes two qubits".to_owned());
+            };
+            native.rz(native.angle(PI / -2.0), *target)?;
+            emit_cx(native, *control, *target)?;
+            native.rz(native.angle(PI / 2.0), *target)
+        },
+    )
+}
+
+/// Define decomposition of CH gate using native gates: CZ conjugated by
+/// `ry(π/4)` on the target.
+fn define_ch_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__ch__body",
+        (&[], &["control", "target"]),
+        |_, qubits| {
+            let [control, target] = qubits else {
+                return Err("CH gate takes two qubits".to_owned());
+            };
+            native.rxy(native.angle(PI / -4.0), native.angle(PI / 2.0), *target)?;
+            emit_cz(native, *control, *target)?;
+            native.rxy(native.angle(PI / 4.0), native.angle(PI / 2.0), *target)
+        },
+    )
+}
+
+/// Define decomposition of SWAP gate using native gates:
+/// `rxx(π/2) ryy(π/2) rzz(π/2)`, which equals SWAP up to a global phase.
+fn define_swap_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__swap__body",
+        (&[], &["qubit1", "qubit2"]),
+        |_, qubits| {
+            let [qubit1, qubit2] = qubits else {
+                return Err("SWAP gate takes two qubits".to_owned());
+            };
+            let half_pi = native.angle(PI / 2.0);
+            emit_rxx(native, half_pi, *qubit1, *qubit2)?;
+            emit_ryy(native, half_pi, *qubit1, *qubit2)?;
+            native.rzz(half_pi, *qubit1, *qubit2)
+        },
+    )
+}
+
+/// Define decomposition of the controlled RZ gate using native gates.
+fn define_crz_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__crz__body",
+        (&["theta"], &["control", "target"]),
+        |angles, qubits| {
+            let ([theta], [control, target]) = (angles, qubits) else {
+                return Err("CRZ gate takes an angle and two qubits".to_owned());
+            };
+            native.rz(native.scaled(*theta, 0.5)?, *target)?;
+            native.rzz(native.scaled(*theta, -0.5)?, *control, *target)
+        },
+    )
+}
+
+/// Define decomposition of the controlled phase gate using native gates.
+fn define_cphase_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__cphase__body",
+        (&["theta"], &["control", "target"]),
+        |angles, qubits| {
+            let ([theta], [control, target]) = (angles, qubits) else {
+                return Err("CPHASE gate takes an angle and two qubits".to_owned());
+            };
+            let half_theta = native.scaled(*theta, 0.5)?;
+            native.rz(half_theta, *control)?;
+            native.rz(half_theta, *target)?;
+            native.rzz(native.scaled(*theta, -0.5)?, *control, *target)
+        },
+    )
+}
+
+/// Define decomposition of RXX gate using native gates.
+fn define_rxx_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__rxx__body",
+        (&["theta"], &["qubit1", "qubit2"]),
+        |angles, qubits| {
+            let ([theta], [qubit1, qubit2]) = (angles, qubits) else {
+                return Err("RXX gate takes an angle and two qubits".to_owned());
+            };
+            emit_rxx(native, *theta, *qubit1, *qubit2)
+        },
+    )
+}
+
+/// Define decomposition of RYY gate using native gates.
+fn define_ryy_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__ryy__body",
+        (&["theta"], &["qubit1", "qubit2"]),
+        |angles, qubits| {
+            let ([theta], [qubit1, qubit2]) = (angles, qubits) else {
+                return Err("RYY gate takes an angle and two qubits".to_owned());
+            };
+            emit_ryy(native, *theta, *qubit1, *qubit2)
+        },
+    )
+}
+
+/// Define decomposition of pytket's TK2 gate using native gates:
+/// `exp(-i/2 (alpha X⊗X + beta Y⊗Y + gamma Z⊗Z))`, whose terms commute.
+fn define_tk2_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__tk2__body",
+        (&["alpha", "beta", "gamma"], &["qubit1", "qubit2"]),
+        |angles, qubits| {
+            let ([alpha, beta, gamma], [qubit1, qubit2]) = (angles, qubits) else {
+                return Err("TK2 gate takes three angles and two qubits".to_owned());
+            };
+            emit_rxx(native, *alpha, *qubit1, *qubit2)?;
+            emit_ryy(native, *beta, *qubit1, *qubit2)?;
+            native.rzz(*gamma, *qubit1, *qubit2)
+        },
+    )
+}
+
 /// Define decomposition of CCX gate using native gates
-#[allow(clippy::too_many_lines)]
 fn define_ccx_gate<'ctx>(
-    context: &'ctx Context,
     module: &Module<'ctx>,
-    builder: &Builder,
     qir_types: &QirTypes<'ctx>,
-    native: &NativeGates<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
 ) -> Result<(), String> {
-    let ccx = get_or_create_decomposition_function(
-        context,
+    define_gate(
+        native,
         module,
+        qir_types,
         "__quantum__qis__ccx__body",
-        &[
-            qir_types.qubit_ptr_type.into(),
-            qir_types.qubit_ptr_type.into(),
-            qir_types.qubit_ptr_type.into(),
-        ],
-    );
-    if ccx.get_first_basic_block().is_some() {
+        (&[], &["control1", "control2", "target"]),
+        |_, qubits| {
+            let [control1, control2, target] = qubits else {
+                return Err("CCX gate takes three qubits".to_owned());
+            };
+            emit_ccx(native, *control1, *control2, *target)
+        },
+    )
+}
+
+fn emit_ccx<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    control1: PointerValue<'ctx>,
+    control2: PointerValue<'ctx>,
+    target: PointerValue<'ctx>,
+) -> Result<(), String> {
+    native.rxy(native.angle(PI), native.angle(PI / -2.0), target)?;
+    native.rzz(native.angle(PI / 2.0), control2, target)?;
+    native.rxy(native.angle(PI / 4.0), native.angle(PI / 2.0), target)?;
+    native.rzz(native.angle(PI / 2.0), control1, target)?;
+    native.rxy(native.angle(PI / 4.0), native.angle(0.0), target)?;
+    native.rzz(native.angle(PI / 2.0), control2, target)?;
+    native.rxy(native.angle(PI / 4.0), native.angle(PI / -2.0), target)?;
+    native.rzz(native.angle(PI / 2.0), control1, target)?;
+    native.rxy(native.angle(PI), native.angle(PI / 4.0), control1)?;
+    native.rxy(native.angle(3.0 * PI / -4.0), native.angle(PI), target)?;
+    native.rzz(native.angle(PI / 4.0), control1, control2)?;
+    native.rz(native.angle(PI), target)?;
+    native.rxy(native.angle(PI), native.angle(PI / -4.0), control1)?;
+    native.rz(native.angle(3.0 * PI / -4.0), control2)?;
+    native.rz(native.angle(PI / 4.0), control1)
+}
+
+fn emit_h<'ctx>(native: &NativeGates<'_, 'ctx>, qubit: PointerValue<'ctx>) -> Result<(), String> {
+    native.rxy(native.angle(PI / 2.0), native.angle(PI / -2.0), qubit)?;
+    native.rz(native.angle(PI), qubit)
+}
+
+/// Define decomposition of CCZ gate as CCX conjugated by H on the target
+fn define_ccz_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__ccz__body",
+        (&[], &["control1", "control2", "target"]),
+        |_, qubits| {
+            let [control1, control2, target] = qubits else {
+                return Err("CCZ gate takes three qubits".to_owned());
+            };
+            emit_ccz(native, *control1, *control2, *target)
+        },
+    )
+}
+
+fn emit_ccz<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    control1: PointerValue<'ctx>,
+    control2: PointerValue<'ctx>,
+    target: PointerValue<'ctx>,
+) -> Result<(), String> {
+    emit_h(native, target)?;
+    emit_ccx(native, control1, control2, target)?;
+    emit_h(native, target)
+}
+
+/// Define decomposition of the Fredkin (controlled SWAP) gate as a CCX
+/// between two CXs
+fn define_cswap_gate<'ctx>(
+    module: &Module<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+    native: &NativeGates<'_, 'ctx>,
+) -> Result<(), String> {
+    define_gate(
+        native,
+        module,
+        qir_types,
+        "__quantum__qis__cswap__body",
+        (&[], &["control", "target1", "target2"]),
+        |_, qubits| {
+            let [control, target1, target2] = qubits else {
+                return Err("CSWAP gate takes three qubits".to_owned());
+            };
+            emit_cx(native, *target2, *target1)?;
+            emit_ccx(native, *control, *target1, *target2)?;
+            emit_cx(native, *target2, *target1)
+        },
+    )
+}
+
+/// Declare the native gates in `module`, to be built at the position of
+/// `builder`.
+fn declare_native_gates<'a, 'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &'a Builder<'ctx>,
+    qir_types: &QirTypes<'ctx>,
+) -> NativeGates<'a, 'ctx> {
+    NativeGates {
+        context,
+        builder,
+        rxy: declare_rxy(context, module, qir_types),
+        rz: declare_rz(context, module, qir_types),
+        rzz: declare_rzz(context, module, qir_types),
+    }
+}
+
+/// Define the `mcx<n>` and `mcz<n>` gates declared in `module`, decomposed
+/// for the number of qubits each one takes.
+fn define_multi_controlled_gates<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+) -> Result<(), String> {
+    let gates: Vec<(String, usize)> = module
+        .get_functions()
+        .filter_map(|function| {
+            let name = function.get_name().to_str().ok()?;
+            let arity = crate::aux::parse_multi_controlled_arity(name).ok()?;
+            Some((name.to_owned(), arity))
+        })
+        .collect();
+    if gates.is_empty() {
         return Ok(());
     }
-    let entry = context.append_basic_block(ccx, "entry");
-    builder.position_at_end(entry);
 
-    let control1 = ccx
-        .get_first_param()
-        .ok_or("CCX gate function missing first parameter")?
-        .into_pointer_value();
-    let control2 = ccx
-        .get_nth_param(1)
-        .ok_or("CCX gate function missing second parameter")?
-        .into_pointer_value();
-    let target = ccx
-        .get_nth_param(2)
-        .ok_or("CCX gate function missing third parameter")?
+    let qir_types = QirTypes::new(context);
+    let builder = context.create_builder();
+    let native = declare_native_gates(context, module, &builder, &qir_types);
+    for (name, arity) in gates {
+        let qubit_names: Vec<String> = (1..arity)
+            .map(|i| format!("control{i}"))
+            .chain(std::iter::once("target".to_owned()))
+            .collect();
+        let qubit_names: Vec<&str> = qubit_names.iter().map(String::as_str).collect();
+        define_gate(
+            &native,
+            module,
+            &qir_types,
+            &name,
+            (&[], &qubit_names),
+            |_, qubits| {
+                if name.starts_with("__quantum__qis__mcx") {
+                    emit_mcx(&native, qubits)
+                } else {
+                    emit_mcz(&native, qubits)
+                }
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Replace the calls to `__ctl` and `__ctladj` gates in `module` by native
+/// gates on their target and the qubits of their controls array.
+///
+/// The controls are passed as a pointer to a fixed-size `[N x ptr]` array, or
+/// as a Q# runtime array, followed by the arguments of the `__body` gate:
+/// their number is only known at each call, so the gates are decomposed in
+/// place instead of being defined once.
+fn decompose_controlled_calls<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+) -> Result<(), String> {
+    let calls: Vec<(InstructionValue<'ctx>, FunctionValue<'ctx>, u32)> = module
+        .get_functions()
+        .flat_map(FunctionValue::get_basic_blocks)
+        .flat_map(inkwell::basic_block::BasicBlock::get_instructions)
+        .filter_map(|instr| {
+            let call = CallSiteValue::try_from(instr).ok()?;
+            let callee = call.get_called_fn_value()?;
+            let name = callee.get_name().to_str().ok()?;
+            // Keep gates the module defines itself, like the other decompositions.
+            (crate::aux::parse_controlled_gate(name).is_ok() && callee.count_basic_blocks() == 0)
+                .then_some((instr, callee, call.count_arguments()))
+        })
+        .collect();
+    if calls.is_empty() {
+        return Ok(());
+    }
+    lower_runtime_controls(context, module)?;
+
+    let qir_types = QirTypes::new(context);
+    let builder = context.create_builder();
+    let native = declare_native_gates(context, module, &builder, &qir_types);
+    let mut callees = Vec::new();
+    for (instr, callee, num_args) in calls {
+        let name = callee.get_name().to_string_lossy().into_owned();
+        let (gate, adjoint) = crate::aux::parse_controlled_gate(&name)?;
+        let args = (0..num_args)
+            .filter_map(|idx| instr.get_operand(idx).and_then(Operand::value))
+            .collect::<Vec<_>>();
+        let [controls, angles @ .., target] = args.as_slice() else {
+            return Err(format!("`{name}` takes controls and a target qubit"));
+        };
+        let controls = PointerValue::try_from(*controls)
+            .map_err(|()| format!("`{name}` expects an array of control qubits"))?;
+        let target = PointerValue::try_from(*target)
+            .map_err(|()| format!("`{name}` expects a target qubit"))?;
+        let angles = angles
+            .iter()
+            .map(|angle| {
+                FloatValue::try_from(*angle)
+                    .map_err(|()| format!("`{name}` expects `double` angles"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        builder.position_before(&instr);
+        // Q# passes the angle of a rotation and its target as a tuple.
+        let (angles, target) = if angles.is_empty() && ROTATION_GATES.contains(&gate) {
+            let (theta, target) = load_rotation_tuple(context, &builder, target, &name)?;
+            (vec![theta], target)
+        } else {
+            (angles, target)
+        };
+        let controls = load_controls(context, &builder, controls, &name)?;
+        emit_controlled_gate(&native, (gate, adjoint), &angles, &controls, target)
+            .map_err(|e| format!("Failed to decompose `{name}`: {e}"))?;
+        instr.erase_from_basic_block();
+        if !callees.contains(&callee) {
+            callees.push(callee);
+        }
+    }
+    for callee in callees {
+        if callee
+            .as_global_value()
+            .as_pointer_value()
+            .get_first_use()
+            .is_none()
+        {
+            // SAFETY: the declaration has no uses left to refer to it.
+            unsafe { callee.delete() };
+        }
+    }
+    Ok(())
+}
+
+/// Replace the Q# runtime arrays of controls and tuples of rotation arguments
+/// in `module` by stack arrays, dropping their reference and alias count
+/// updates.
+fn lower_runtime_controls<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+) -> Result<(), String> {
+    let creates: Vec<(InstructionValue<'ctx>, bool)> = module
+        .get_functions()
+        .flat_map(FunctionValue::get_basic_blocks)
+        .flat_map(inkwell::basic_block::BasicBlock::get_instructions)
+        .filter_map(|instr| {
+            let callee = CallSiteValue::try_from(instr).ok()?.get_called_fn_value()?;
+            match callee.get_name().to_bytes() {
+                b"__quantum__rt__array_create_1d" => Some((instr, true)),
+                b"__quantum__rt__tuple_create" => Some((instr, false)),
+                _ => None,
+            }
+        })
+        .collect();
+    if creates.is_empty() {
+        return Ok(());
+    }
+
+    let builder = context.create_builder();
+    let ptr_type = context.ptr_type(inkwell::AddressSpace::default());
+    let i64_type = context.i64_type();
+    for (create, is_array) in creates {
+        let entry = create
+            .get_parent()
+            .and_then(|block| block.get_parent())
+            .and_then(FunctionValue::get_first_basic_block)
+            .ok_or("Runtime array outside a function")?;
+        match entry.get_first_instruction() {
+            Some(first) => builder.position_before(&first),
+            None => builder.position_at_end(entry),
+        }
+        let (array_type, name) = if is_array {
+            let len = crate::aux::get_runtime_array_len(create)?;
+            let len = u32::try_from(len).map_err(|e| format!("Too many controls: {e}"))?;
+            (ptr_type.array_type(len), "controls")
+        } else {
+            let size = crate::aux::get_runtime_tuple_size(create)?;
+            let size = u32::try_from(size).map_err(|e| format!("Tuple too large: {e}"))?;
+            (context.i8_type().array_type(size), "tuple")
+        };
+        let alloca = builder
+            .build_alloca(array_type, name)
+            .map_err(|e| format!("Failed to allocate the {name} on the stack: {e}"))?;
+        let alloca_instr = alloca
+            .as_instruction_value()
+            .ok_or("Stack allocation is not an instruction")?;
+        alloca_instr
+            .set_alignment(8)
+            .map_err(|e| format!("Failed to align the {name}: {e}"))?;
+
+        for user in crate::aux::instruction_users(create).into_iter().flatten() {
+            let Some(callee) = CallSiteValue::try_from(user)
+                .ok()
+                .and_then(CallSiteValue::get_called_fn_value)
+            else {
+                continue;
+            };
+            match callee.get_name().to_bytes() {
+                b"__quantum__rt__array_get_element_ptr_1d" => {
+                    let index = user
+                        .get_operand(1)
+                        .and_then(Operand::value)
+                        .ok_or("Malformed `__quantum__rt__array_get_element_ptr_1d` call")?
+                        .into_int_value();
+                    builder.position_before(&user);
+                    // SAFETY: validation checked that `index` is a constant
+                    // within the array.
+                    let element = unsafe {
+                        builder.build_in_bounds_gep(
+                            array_type,
+                            alloca,
+                            &[i64_type.const_zero(), index],
+                            "control_ptr",
+                        )
+                    }
+                    .map_err(|e| format!("Failed to build GEP for control qubit: {e}"))?;
+                    let element = element
+                        .as_instruction_value()
+                        .ok_or("Control GEP is not an instruction")?;
+                    user.replace_all_uses_with(&element);
+                    user.erase_from_basic_block();
+                }
+                b"__quantum__rt__array_update_reference_count"
+                | b"__quantum__rt__array_update_alias_count"
+                | b"__quantum__rt__tuple_update_reference_count"
+                | b"__quantum__rt__tuple_update_alias_count" => user.erase_from_basic_block(),
+                _ => {}
+            }
+        }
+        create.replace_all_uses_with(&alloca_instr);
+        create.erase_from_basic_block();
+    }
+
+    for name in crate::aux::QSHARP_CONTROL_RT_FNS {
+        if let Some(function) = module.get_function(name)
+            && function
+                .as_global_value()
+                .as_pointer_value()
+                .get_first_use()
+                .is_none()
+        {
+            // SAFETY: the declaration has no uses left to refer to it.
+            unsafe { function.delete() };
+        }
+    }
+    Ok(())
+}
+
+/// Controlled gates taking an angle, which Q# passes in a tuple with the
+/// target.
+const ROTATION_GATES: [&str; 3] = ["rx", "ry", "rz"];
+
+/// Load the angle and the target qubit of the `{ double, %Qubit* }` tuple
+/// `tuple` of a call to `name`.
+fn load_rotation_tuple<'ctx>(
+    context: &'ctx Context,
+    builder: &Builder<'ctx>,
+    tuple: PointerValue<'ctx>,
+    name: &str,
+) -> Result<(FloatValue<'ctx>, PointerValue<'ctx>), String> {
+    let f64_type = context.f64_type();
+    let qubit_type = context.ptr_type(inkwell::AddressSpace::default());
+    let tuple_type = context.struct_type(&[f64_type.into(), qubit_type.into()], false);
+    let theta = builder
+        .build_struct_gep(tuple_type, tuple, 0, "theta_ptr")
+        .and_then(|theta| builder.build_load(f64_type, theta, "theta"))
+        .map_err(|e| format!("Failed to load the angle of `{name}`: {e}"))?
+        .into_float_value();
+    let target = builder
+        .build_struct_gep(tuple_type, tuple, 1, "target_ptr")
+        .and_then(|target| builder.build_load(qubit_type, target, "target"))
+        .map_err(|e| format!("Failed to load the target of `{name}`: {e}"))?
         .into_pointer_value();
+    Ok((theta, target))
+}
 
-    let pi = context.f64_type().const_float(PI);
-    let half_pi = context.f64_type().const_float(PI / 2.0);
-    let neg_half_pi = context.f64_type().const_float(PI / -2.0);
-    let quarter_pi = context.f64_type().const_float(PI / 4.0);
-    let neg_quarter_pi = context.f64_type().const_float(PI / -4.0);
-    let neg_three_quarter_pi = context.f64_type().const_float(3.0 * PI / -4.0);
-    let zero = context.f64_type().const_zero();
+/// Load the qubits of the controls array `controls` of a call to `name`.
+fn load_controls<'ctx>(
+    context: &'ctx Context,
+    builder: &Builder<'ctx>,
+    controls: PointerValue<'ctx>,
+    name: &str,
+) -> Result<Vec<PointerValue<'ctx>>, String> {
+    let num_controls = crate::aux::get_fixed_pointer_array_len(controls, name)?;
+    let qubit_type = context.ptr_type(inkwell::AddressSpace::default());
+    let array_type = qubit_type
+        .array_type(u32::try_from(num_controls).map_err(|e| format!("Too many controls: {e}"))?);
+    let i64_type = context.i64_type();
+    (0..num_controls)
+        .map(|idx| {
+            // SAFETY: `idx` is within the fixed-size backing array of the controls.
+            let element = unsafe {
+                builder.build_in_bounds_gep(
+                    array_type,
+                    controls,
+                    &[i64_type.const_zero(), i64_type.const_int(idx, false)],
+                    "control_ptr",
+                )
+            }
+            .map_err(|e| format!("Failed to build GEP for control qubit: {e}"))?;
+            builder
+                .build_load(qubit_type, element, "control")
+                .map(inkwell::values::BasicValueEnum::into_pointer_value)
+                .map_err(|e| format!("Failed to load control qubit: {e}"))
+        })
+        .collect()
+}
 
-    // rxy(π, -π/2, target)
-    let _ = builder.build_call(
-        native.rxy,
-        &[pi.into(), neg_half_pi.into(), target.into()],
-        "",
-    );
-    // rzz(π/2, control2, target)
-    let _ = builder.build_call(
-        native.rzz,
-        &[half_pi.into(), control2.into(), target.into()],
-        "",
-    );
-    // rxy(π/4, π/2, target)
-    let _ = builder.build_call(
-        native.rxy,
-        &[quarter_pi.into(), half_pi.into(), target.into()],
-        "",
-    );
-    // rzz(π/2, control1, target)
-    let _ = builder.build_call(
-        native.rzz,
-        &[half_pi.into(), control1.into(), target.into()],
-        "",
-    );
-    // rxy(π/4, 0, target)
-    let _ = builder.build_call(
-        native.rxy,
-        &[quarter_pi.into(), zero.into(), target.into()],
-        "",
-    );
-    // rzz(π/2, control2, target)
-    let _ = builder.build_call(
-        native.rzz,
-        &[half_pi.into(), control2.into(), target.into()],
-        "",
-    );
-    // rxy(π/4, -π/2, target)
-    let _ = builder.build_call(
-        native.rxy,
-        &[quarter_pi.into(), neg_half_pi.into(), target.into()],
-        "",
-    );
-    // rzz(π/2, control1, target)
-    let _ = builder.build_call(
-        native.rzz,
-        &[half_pi.into(), control1.into(), target.into()],
-        "",
-    );
-    // rxy(π, π/4, control1)
-    let _ = builder.build_call(
-        native.rxy,
-        &[pi.into(), quarter_pi.into(), control1.into()],
-        "",
-    );
-    // rxy(-3π/4, π, target)
-    let _ = builder.build_call(
-        native.rxy,
-        &[neg_three_quarter_pi.into(), pi.into(), target.into()],
-        "",
-    );
-    // rzz(π/4, control1, control2)
-    let _ = builder.build_call(
-        native.rzz,
-        &[quarter_pi.into(), control1.into(), control2.into()],
-        "",
-    );
-    // rz(π, target)
-    let _ = builder.build_call(native.rz, &[pi.into(), target.into()], "");
-    // rxy(π, -π/4, control1)
-    let _ = builder.build_call(
-        native.rxy,
-        &[pi.into(), neg_quarter_pi.into(), control1.into()],
-        "",
-    );
-    // rz(-3π/4, control2)
-    let _ = builder.build_call(
-        native.rz,
-        &[neg_three_quarter_pi.into(), control2.into()],
-        "",
-    );
-    // rz(π/4, control1)
-    let _ = builder.build_call(native.rz, &[quarter_pi.into(), control1.into()], "");
+/// Emit `gate` (or its adjoint) on `target` controlled by all of `controls`,
+/// including the relative phase the gate has over its `SU(2)` part.
+fn emit_controlled_gate<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    (gate, adjoint): (&str, bool),
+    angles: &[FloatValue<'ctx>],
+    controls: &[PointerValue<'ctx>],
+    target: PointerValue<'ctx>,
+) -> Result<(), String> {
+    let qubits: Vec<PointerValue<'ctx>> = controls
+        .iter()
+        .copied()
+        .chain(std::iter::once(target))
+        .collect();
+    let sign = if adjoint { -1.0 } else { 1.0 };
+    match (gate, angles) {
+        ("x", []) => emit_mcx(native, &qubits),
+        ("y", []) => {
+            native.rz(native.angle(PI / -2.0), target)?;
+            emit_mcx(native, &qubits)?;
+            native.rz(native.angle(PI / 2.0), target)
+        }
+        ("z", []) => emit_mcz(native, &qubits),
+        ("h", []) => {
+            native.rxy(native.angle(PI / -4.0), native.angle(PI / 2.0), target)?;
+            emit_mcz(native, &qubits)?;
+            native.rxy(native.angle(PI / 4.0), native.angle(PI / 2.0), target)
+        }
+        ("s", []) => emit_controlled_phase(native, native.angle(sign * PI / 2.0), &qubits),
+        ("t", []) => emit_controlled_phase(native, native.angle(sign * PI / 4.0), &qubits),
+        ("rx", [theta]) => {
+            emit_h(native, target)?;
+            emit_controlled_rz(native, native.scaled(*theta, sign)?, controls, target)?;
+            emit_h(native, target)
+        }
+        ("ry", [theta]) => {
+            native.rxy(native.angle(PI / 2.0), native.angle(0.0), target)?;
+            emit_controlled_rz(native, native.scaled(*theta, sign)?, controls, target)?;
+            native.rxy(native.angle(PI / -2.0), native.angle(0.0), target)
+        }
+        ("rz", [theta]) => {
+            emit_controlled_rz(native, native.scaled(*theta, sign)?, controls, target)
+        }
+        _ => Err(format!(
+            "controlled `{gate}` does not take {} angles",
+            angles.len()
+        )),
+    }
+}
 
-    let _ = builder.build_return(None);
+/// Emit an X gate on the last of `qubits` controlled by all the others.
+fn emit_mcx<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    qubits: &[PointerValue<'ctx>],
+) -> Result<(), String> {
+    match qubits {
+        [] => Err("Multi-controlled X gate takes at least one qubit".to_owned()),
+        [target] => native.rxy(native.angle(PI), native.angle(0.0), *target),
+        [control, target] => emit_cx(native, *control, *target),
+        [control1, control2, target] => emit_ccx(native, *control1, *control2, *target),
+        [.., target] => {
+            emit_h(native, *target)?;
+            emit_mcz(native, qubits)?;
+            emit_h(native, *target)
+        }
+    }
+}
+
+/// Emit a Z gate on all of `qubits`, i.e. a phase of -1 on the state in
+/// which all of them are 1.
+fn emit_mcz<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    qubits: &[PointerValue<'ctx>],
+) -> Result<(), String> {
+    match qubits {
+        [] => Err("Multi-controlled Z gate takes at least one qubit".to_owned()),
+        [target] => native.rz(native.angle(PI), *target),
+        [control, target] => emit_cz(native, *control, *target),
+        [control1, control2, target] => emit_ccz(native, *control1, *control2, *target),
+        _ => emit_controlled_phase(native, native.angle(PI), qubits),
+    }
+}
+
+/// Emit a phase of `phase` on the state in which all of `qubits` are 1,
+/// without ancillas.
+///
+/// Up to a global phase, the phase φ·x₁⋯xₙ is an rz(φ) on the last qubit
+/// controlled by the others, followed by a phase φ/2·x₁⋯xₙ₋₁ on those, and so
+/// on down to a single qubit. With the controlled rz of
+/// [`emit_controlled_rz`], this takes O(n²) CX gates.
+fn emit_controlled_phase<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    phase: FloatValue<'ctx>,
+    qubits: &[PointerValue<'ctx>],
+) -> Result<(), String> {
+    let mut phase = phase;
+    let mut qubits = qubits;
+    while let [controls @ .., target] = qubits {
+        emit_controlled_rz(native, phase, controls, *target)?;
+        phase = native.scaled(phase, 0.5)?;
+        qubits = controls;
+    }
     Ok(())
 }
 
+/// Emit rz(`theta`) on `target` controlled by all of `controls`, without
+/// ancillas.
+///
+/// The controls are split in two halves, and with a = rz(θ/4) this is
+/// a·X₁·a†·X₂·a·X₁·a†·X₂, where Xᵢ is an X on the target controlled by the
+/// i-th half: as X·a†·X = a, the target turns by a⁴ = rz(θ) when both halves
+/// are set, and the rotations cancel otherwise. Each Xᵢ borrows the qubits of
+/// the other half as ancillas, for O(n) CX gates in total.
+fn emit_controlled_rz<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    theta: FloatValue<'ctx>,
+    controls: &[PointerValue<'ctx>],
+    target: PointerValue<'ctx>,
+) -> Result<(), String> {
+    match controls {
+        [] => native.rz(theta, target),
+        [control] => {
+            native.rz(native.scaled(theta, 0.5)?, target)?;
+            native.rzz(native.scaled(theta, -0.5)?, *control, target)
+        }
+        _ => {
+            let (first, second) = controls.split_at(controls.len().div_ceil(2));
+            for _ in 0..2 {
+                native.rz(native.scaled(theta, 0.25)?, target)?;
+                emit_mcx_borrowing(native, first, target, second)?;
+                native.rz(native.scaled(theta, -0.25)?, target)?;
+                emit_mcx_borrowing(native, second, target, first)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Emit an X gate on `target` controlled by all of `controls`, borrowing
+/// `borrowed` as ancillas in any state, which are left as they were.
+///
+/// With k > 2 controls, this is the chain of 4(k - 2) Toffoli gates of
+/// Barenco et al., "Elementary gates for quantum computation" (lemma 7.2),
+/// which borrows k - 2 ancillas.
+fn emit_mcx_borrowing<'ctx>(
+    native: &NativeGates<'_, 'ctx>,
+    controls: &[PointerValue<'ctx>],
+    target: PointerValue<'ctx>,
+    borrowed: &[PointerValue<'ctx>],
+) -> Result<(), String> {
+    let (first, second, middle, last) = match controls {
+        [] => return native.rxy(native.angle(PI), native.angle(0.0), target),
+        [control] => return emit_cx(native, *control, target),
+        [control1, control2] => return emit_ccx(native, *control1, *control2, target),
+        [first, second, middle @ .., last] => (*first, *second, middle, *last),
+    };
+    // The i-th ancilla is flipped by the AND of the first i + 1 controls, by a
+    // Toffoli gate from the previous ancilla and the next control.
+    let Some(ancillas @ [first_ancilla, ..]) = borrowed.get(..=middle.len()) else {
+        return Err("Too few qubits to borrow for a multi-controlled X gate".to_owned());
+    };
+    let last_ancilla = ancillas[middle.len()];
+    let steps: Vec<_> = middle
+        .iter()
+        .zip(ancillas)
+        .zip(&ancillas[1..])
+        .map(|((control, ancilla), next)| (*control, *ancilla, *next))
+        .collect();
+    let ladder = || -> Result<(), String> {
+        for &(control, ancilla, next) in steps.iter().rev() {
+            emit_ccx(native, control, ancilla, next)?;
+        }
+        emit_ccx(native, first, second, *first_ancilla)?;
+        for &(control, ancilla, next) in &steps {
+            emit_ccx(native, control, ancilla, next)?;
+        }
+        Ok(())
+    };
+    // The target is flipped twice by the last control and ancilla, before
+    // and after the ancilla is flipped by the AND of the other controls,
+    // then the ancillas are restored.
+    emit_ccx(native, last, last_ancilla, target)?;
+    ladder()?;
+    emit_ccx(native, last, last_ancilla, target)?;
+    ladder()
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::expect_used)]
 
-    use super::add_decompositions;
-    #[cfg(not(windows))]
+    use super::{
+        DecompositionRegistry, NativeGates, add_decompositions, build_decomposition_module,
+        link_decompositions,
+    };
+    use inkwell::AddressSpace;
     use inkwell::context::Context;
+    use inkwell::module::{Linkage, Module};
+    use inkwell::types::BasicMetadataTypeEnum;
     #[cfg(not(windows))]
-    use inkwell::values::{AnyValue, CallSiteValue, Operand};
-    #[cfg(not(windows))]
+    use inkwell::values::AnyValue;
+    use inkwell::values::{
+        BasicValueEnum, CallSiteValue, FloatValue, InstructionOpcode, Operand, PointerValue,
+    };
     use std::f64::consts::PI;
 
     #[cfg(not(windows))]
@@ -901,6 +2238,398 @@ mod tests {
         assert!(!ry.is_empty());
     }
 
+    const SY_LL: &str = r"
+        define void @__quantum__qis__sy__body(ptr %q) {
+          call void @__quantum__qis__rxy__body(double 0x3FF921FB54442D18, double 0x3FF921FB54442D18, ptr %q)
+          ret void
+        }
+        declare void @__quantum__qis__rxy__body(double, double, ptr)
+    ";
+
+    fn sample_registry() -> DecompositionRegistry {
+        let mut registry = DecompositionRegistry::new();
+        registry
+            .register("__quantum__qis__xx__body", 1, 2, |gates, angles, qubits| {
+                let theta = angles.first().ok_or("missing angle")?;
+                let (q1, q2) = (
+                    qubits.first().ok_or("missing qubit")?,
+                    qubits.get(1).ok_or("missing qubit")?,
+                );
+                let half_pi = gates.angle(std::f64::consts::FRAC_PI_2);
+                gates.rxy(half_pi, gates.angle(0.0), *q1)?;
+                gates.rzz(*theta, *q1, *q2)
+            })
+            .expect("xx should register")
+            .register_ir("__quantum__qis__sy__body", SY_LL)
+            .expect("sy should register");
+        registry
+    }
+
+    #[test]
+    fn test_registered_gates_are_defined_with_native_gates() {
+        let registry = sample_registry();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["__quantum__qis__sy__body", "__quantum__qis__xx__body"]
+        );
+
+        let context = inkwell::context::Context::create();
+        let decompositions =
+            build_decomposition_module(&context, &registry).expect("decompositions should build");
+        for name in registry.names() {
+            let module = decompositions.get(name).expect("gate should be built");
+            let gate = module.get_function(name).expect("gate should be defined");
+            assert!(gate.count_basic_blocks() > 0, "{name}");
+            assert_eq!(gate.get_linkage(), Linkage::LinkOnceODR, "{name}");
+            // Each gate is kept on its own, with the native gates it calls.
+            assert_eq!(
+                module
+                    .get_functions()
+                    .filter(|function| function.count_basic_blocks() > 0)
+                    .count(),
+                1,
+                "{name}"
+            );
+        }
+        let module = decompositions
+            .get("__quantum__qis__xx__body")
+            .expect("xx should be built");
+        assert_eq!(
+            module
+                .get_function("__quantum__qis__xx__body")
+                .expect("xx should be defined")
+                .count_params(),
+            3
+        );
+
+        // Adding them again keeps the existing definitions.
+        registry
+            .add_to(&context, module)
+            .expect("decompositions should be added again");
+    }
+
+    #[test]
+    fn test_registry_rejects_invalid_gates() {
+        let mut registry = sample_registry();
+        let before = registry.clone();
+        let noop = |_: &NativeGates<'_, '_>,
+                    _: &[FloatValue<'_>],
+                    _: &[PointerValue<'_>]|
+         -> Result<(), String> { Ok(()) };
+        for (name, reason) in [
+            ("my_gate", "must be `__quantum__qis__` functions"),
+            ("__quantum__qis__h__body", "already supported"),
+            ("__quantum__qis__barrier3__body", "already supported"),
+            ("__quantum__qis__xx__body", "already registered"),
+        ] {
+            let err = registry
+                .register(name, 0, 1, noop)
+                .expect_err("gate should be rejected");
+            assert!(err.contains(reason), "{name}: {err}");
+        }
+
+        for (ll_text, reason) in [
+            ("not IR", "Failed to create module"),
+            (
+                "declare void @__quantum__qis__sz__body(ptr)",
+                "does not define `__quantum__qis__sz__body`",
+            ),
+            (
+                "define i1 @__quantum__qis__sz__body(ptr %q) {\n  ret i1 0\n}",
+                "must return `void`",
+            ),
+            (
+                "define void @__quantum__qis__sz__body(ptr %q) {\n  call void @helper(ptr %q)\n  ret void\n}\ndefine void @helper(ptr %q) {\n  ret void\n}",
+                "must only define",
+            ),
+            (
+                "define void @__quantum__qis__sz__body(ptr %q) {\n  call void @__quantum__qis__h__body(ptr %q)\n  ret void\n}\ndeclare void @__quantum__qis__h__body(ptr)",
+                "not `__quantum__qis__h__body`",
+            ),
+        ] {
+            let err = registry
+                .register_ir("__quantum__qis__sz__body", ll_text)
+                .expect_err("IR should be rejected");
+            assert!(err.contains(reason), "{ll_text}: {err}");
+        }
+        assert!(!registry.contains("__quantum__qis__sz__body"));
+        assert_eq!(registry, before);
+    }
+
+    /// `angle` as a multiple of π, e.g. `-3π/4`, or its value.
+    fn angle_text(angle: f64) -> String {
+        if angle.abs() < 1e-12 {
+            return "0".to_owned();
+        }
+        for (denominator, suffix) in [(1.0, ""), (2.0, "/2"), (4.0, "/4"), (8.0, "/8")] {
+            let numerator = angle / PI * denominator;
+            if (numerator - numerator.round()).abs() < 1e-9 {
+                let sign = if numerator < 0.0 { "-" } else { "" };
+                let numerator = format!("{:.0}", numerator.abs());
+                let numerator = if numerator == "1" { "" } else { &numerator };
+                return format!("{sign}{numerator}π{suffix}");
+            }
+        }
+        angle.to_string()
+    }
+
+    /// An argument of a native gate call: a constant angle, a scaled angle
+    /// parameter or a qubit parameter.
+    fn operand_text(value: BasicValueEnum<'_>) -> String {
+        if let Ok(angle) = FloatValue::try_from(value) {
+            if let Some((constant, _)) = angle.get_constant() {
+                return angle_text(constant);
+            }
+            if let Some(instr) = angle.as_instruction()
+                && instr.get_opcode() == InstructionOpcode::FMul
+                && let (Some(Operand::Value(param)), Some(Operand::Value(factor))) =
+                    (instr.get_operand(0), instr.get_operand(1))
+                && let Ok(factor) = FloatValue::try_from(factor)
+                && let Some((factor, _)) = factor.get_constant()
+            {
+                let param = operand_text(param);
+                return if (factor - 0.5).abs() < 1e-12 {
+                    format!("{param}/2")
+                } else if (factor + 0.5).abs() < 1e-12 {
+                    format!("-{param}/2")
+                } else {
+                    format!("{}*{param}", angle_text(factor))
+                };
+            }
+        }
+        value.get_name().to_string_lossy().into_owned()
+    }
+
+    /// The native gate calls of the decomposition of `fn_name`, one per line,
+    /// e.g. `rzz(π/2, control, target)`.
+    fn native_sequence(module: &Module<'_>, fn_name: &str) -> String {
+        let function = module
+            .get_function(fn_name)
+            .expect("decomposition function should exist");
+        let short_name = |name: &str| {
+            name.trim_start_matches("__quantum__qis__")
+                .trim_end_matches("__body")
+                .to_owned()
+        };
+        let params = function
+            .get_params()
+            .into_iter()
+            .map(operand_text)
+            .collect::<Vec<_>>();
+        let mut lines = vec![format!("{}({}):", short_name(fn_name), params.join(", "))];
+        for instr in function
+            .get_basic_blocks()
+            .into_iter()
+            .flat_map(inkwell::basic_block::BasicBlock::get_instructions)
+        {
+            let Ok(call) = CallSiteValue::try_from(instr) else {
+                continue;
+            };
+            let callee = call
+                .get_called_fn_value()
+                .expect("decompositions only make direct calls");
+            let args = (0..call.count_arguments())
+                .filter_map(|idx| match instr.get_operand(idx) {
+                    Some(Operand::Value(value)) => Some(operand_text(value)),
+                    Some(Operand::Block(_)) | None => None,
+                })
+                .collect::<Vec<_>>();
+            lines.push(format!(
+                "  {}({})",
+                short_name(&callee.get_name().to_string_lossy()),
+                args.join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Declare `name` in `module` as a gate on `num_qubits` qubits.
+    fn declare_qubit_gate(module: &Module<'_>, name: &str, num_qubits: usize) {
+        let context = module.get_context();
+        let qubit = BasicMetadataTypeEnum::from(context.ptr_type(AddressSpace::default()));
+        let fn_type = context.void_type().fn_type(&vec![qubit; num_qubits], false);
+        module.add_function(name, fn_type, None);
+    }
+
+    #[test]
+    fn test_only_used_decompositions_are_linked() {
+        let context = Context::create();
+        let decompositions = build_decomposition_module(&context, &DecompositionRegistry::new())
+            .expect("decompositions should build");
+        let module = crate::create_module_from_ir_text(
+            &context,
+            r"
+declare void @__quantum__qis__h__body(ptr)
+declare void @__quantum__qis__cx__body(ptr, ptr)
+
+define void @__quantum__qis__x__body(ptr %qubit) {
+entry:
+  ret void
+}
+
+define void @main(ptr %control, ptr %target) {
+entry:
+  call void @__quantum__qis__h__body(ptr %control)
+  call void @__quantum__qis__cx__body(ptr %control, ptr %target)
+  call void @__quantum__qis__x__body(ptr %target)
+  ret void
+}
+",
+            "used",
+        )
+        .expect("module should parse");
+        link_decompositions(&context, &module, &decompositions)
+            .expect("decompositions should link");
+
+        for name in ["__quantum__qis__h__body", "__quantum__qis__cx__body"] {
+            assert_eq!(
+                native_sequence(&module, name),
+                native_sequence(
+                    decompositions.get(name).expect("gate should be built"),
+                    name
+                )
+            );
+        }
+        // The module's own definition is kept, and unused gates are left out.
+        assert_eq!(
+            native_sequence(&module, "__quantum__qis__x__body"),
+            "x(qubit):"
+        );
+        assert!(module.get_function("__quantum__qis__ccx__body").is_none());
+        assert!(module.get_function("__quantum__qis__y__body").is_none());
+    }
+
+    #[test]
+    fn test_multi_controlled_gates_are_defined_for_declared_arities() {
+        let context = Context::create();
+        let decompositions = build_decomposition_module(&context, &DecompositionRegistry::new())
+            .expect("decompositions should build");
+        assert!(decompositions.get("__quantum__qis__mcx2__body").is_none());
+
+        let module = context.create_module("multi_controlled");
+        for (name, num_qubits) in [
+            ("__quantum__qis__mcx1__body", 1),
+            ("__quantum__qis__mcx3__body", 3),
+            ("__quantum__qis__mcx5__body", 5),
+            ("__quantum__qis__mcz2__body", 2),
+            ("__quantum__qis__mcz8__body", 8),
+            ("__quantum__qis__mcz16__body", 16),
+        ] {
+            declare_qubit_gate(&module, name, num_qubits);
+        }
+        link_decompositions(&context, &module, &decompositions)
+            .expect("decompositions should link");
+
+        let native_calls = |name: &str, gate: &str| {
+            native_sequence(&module, name)
+                .lines()
+                .filter(|line| line.starts_with(&format!("  {gate}(")))
+                .count()
+        };
+        assert_eq!(native_calls("__quantum__qis__mcx1__body", "rxy"), 1);
+        assert_eq!(native_calls("__quantum__qis__mcz2__body", "rzz"), 1);
+        assert_eq!(
+            native_sequence(&module, "__quantum__qis__mcx3__body")
+                .lines()
+                .skip(1)
+                .collect::<Vec<_>>(),
+            native_sequence(
+                decompositions
+                    .get("__quantum__qis__ccx__body")
+                    .expect("ccx should be built"),
+                "__quantum__qis__ccx__body"
+            )
+            .lines()
+            .skip(1)
+            .collect::<Vec<_>>()
+        );
+        // An rz on each qubit controlled by the ones before it: 1 ZZ rotation
+        // with one control, then four multi-controlled X gates, here CX or CCX
+        assert_eq!(
+            native_calls("__quantum__qis__mcx5__body", "rzz"),
+            1 + 4 + 2 * (5 + 1) + 4 * 5
+        );
+        assert!(
+            native_sequence(&module, "__quantum__qis__mcx5__body").starts_with(
+                "mcx5(control1, control2, control3, control4, target):\n  rxy(π/2, -π/2, target)"
+            )
+        );
+        // The number of gates grows quadratically with the number of qubits
+        assert_eq!(native_calls("__quantum__qis__mcz8__body", "rzz"), 287);
+        assert_eq!(native_calls("__quantum__qis__mcz16__body", "rzz"), 2687);
+    }
+
+    #[test]
+    fn test_controlled_gate_calls_are_decomposed_in_place() {
+        let context = Context::create();
+        let decompositions = build_decomposition_module(&context, &DecompositionRegistry::new())
+            .expect("decompositions should build");
+        let module = crate::create_module_from_ir_text(
+            &context,
+            r"
+declare void @__quantum__qis__rz__ctl(ptr, double, ptr)
+declare void @__quantum__qis__x__ctladj(ptr, ptr)
+
+define void @main(ptr %target) {
+entry:
+  %controls = alloca [2 x ptr], align 8
+  call void @__quantum__qis__rz__ctl(ptr %controls, double 5.000000e-01, ptr %target)
+  call void @__quantum__qis__x__ctladj(ptr %controls, ptr %target)
+  ret void
+}
+",
+            "controlled",
+        )
+        .expect("module should parse");
+        link_decompositions(&context, &module, &decompositions)
+            .expect("decompositions should link");
+
+        assert!(module.get_function("__quantum__qis__rz__ctl").is_none());
+        assert!(module.get_function("__quantum__qis__x__ctladj").is_none());
+        let sequence = native_sequence(&module, "main");
+        let native_calls = |gate: &str| {
+            sequence
+                .lines()
+                .filter(|line| line.starts_with(&format!("  {gate}(")))
+                .count()
+        };
+        // Two CX gates into the target from each of the two controls, then the
+        // ZZ rotations of a CCX
+        assert_eq!(native_calls("rzz"), 4 + 5);
+        assert!(sequence.contains("  rz(0.125, target)"), "{sequence}");
+    }
+
+    #[test]
+    fn test_snapshot_decompositions() {
+        const GATES: &[&str] = &[
+            "__quantum__qis__cy__body",
+            "__quantum__qis__ch__body",
+            "__quantum__qis__swap__body",
+            "__quantum__qis__crz__body",
+            "__quantum__qis__cphase__body",
+            "__quantum__qis__rxx__body",
+            "__quantum__qis__ryy__body",
+            "__quantum__qis__tk2__body",
+            "__quantum__qis__ccz__body",
+            "__quantum__qis__cswap__body",
+            "__quantum__qis__mcz4__body",
+        ];
+        let context = Context::create();
+        let module = context.create_module("decompose_test");
+        declare_qubit_gate(&module, "__quantum__qis__mcz4__body", 4);
+        add_decompositions(&context, &module).expect("decompositions should build");
+        let decompositions = GATES
+            .iter()
+            .map(|gate| native_sequence(&module, gate))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let mut settings = insta::Settings::clone_current();
+        settings.set_prepend_module_to_snapshot(false);
+        settings.set_snapshot_path("../tests/snaps");
+        settings.bind(|| insta::assert_snapshot!("decompositions", decompositions));
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_add_decompositions_windows_smoke() {
@@ -0,0 +1,399 @@
+//! Structured validation diagnostics.
+//!
+//! Every `aux::validate_*` pass reports problems as [`Diagnostic`]s carrying a
+//! stable [`codes`] identifier, so that callers can group and count failures
+//! without matching on message text.
+
+use std::fmt;
+
+use inkwell::basic_block::BasicBlock;
+use inkwell::values::{AnyValue, FunctionValue, InstructionValue};
+
+use crate::catalog::{Explanation, explain};
+use crate::source_map::SourceMap;
+use crate::{Error, decode_llvm_c_string};
+
+/// Stable diagnostic codes.
+///
+/// Codes are never reused or renumbered once released; new checks get new codes.
+/// Each code is documented in the [`catalog`](crate::catalog). `C` codes
+/// identify the [`Error`](crate::Error) variants that are not tied to a
+/// single check.
+pub mod codes {
+    /// The input is not valid LLVM IR text or bitcode.
+    pub const PARSE_FAILED: &str = "C001";
+    /// The LLVM verifier rejected a module.
+    pub const VERIFIER_FAILED: &str = "C002";
+    /// The program failed validation; each violation has its own code.
+    pub const VALIDATION_FAILED: &str = "C003";
+    /// Lowering QIR to QIS failed.
+    pub const LOWERING_FAILED: &str = "C004";
+    /// A pipeline stage name does not match any stage.
+    pub const UNKNOWN_STAGE: &str = "C005";
+    /// The target machine could not be created or the optimizer failed.
+    pub const TARGET_FAILED: &str = "C006";
+    /// The supplied WASM module cannot be parsed.
+    pub const INVALID_WASM_MODULE: &str = "C007";
+    /// A bundle cannot be read.
+    pub const INVALID_BUNDLE: &str = "C008";
+    /// A compilation option has an invalid value.
+    pub const INVALID_OPTION: &str = "C009";
+
+    /// No function carries the `entry_point` attribute.
+    pub const MISSING_ENTRY_POINT: &str = "E001";
+    /// The entry function has no basic blocks.
+    pub const EMPTY_ENTRY_POINT: &str = "E002";
+    /// A required entry point attribute is missing.
+    pub const MISSING_ENTRY_ATTRIBUTE: &str = "E003";
+    /// `required_num_qubits` is zero.
+    pub const ZERO_REQUIRED_QUBITS: &str = "E004";
+    /// The input defines a function reserved for `qir_qis.*` internal helpers.
+    pub const RESERVED_HELPER_NAME: &str = "E005";
+    /// A `barrier<N>` call uses more qubits than `required_num_qubits`.
+    pub const BARRIER_ARITY_OVERFLOW: &str = "E006";
+    /// A `__quantum__qis__*` function is not supported.
+    pub const UNSUPPORTED_QIS_FUNCTION: &str = "E007";
+    /// A `__quantum__rt__*` function is not supported.
+    pub const UNSUPPORTED_RT_FUNCTION: &str = "E008";
+    /// A supported `__quantum__rt__*` function is declared with the wrong signature.
+    pub const MALFORMED_RT_DECLARATION: &str = "E009";
+    /// A `___*` Quantinuum function is not supported.
+    pub const UNSUPPORTED_QTM_FUNCTION: &str = "E010";
+    /// An IR-defined function is called `main`.
+    pub const IR_FUNCTION_NAMED_MAIN: &str = "E011";
+    /// An IR-defined function returns a pointer.
+    pub const IR_FUNCTION_RETURNS_POINTER: &str = "E012";
+    /// The `required_num_results` attribute is not a valid integer.
+    pub const INVALID_REQUIRED_NUM_RESULTS: &str = "E013";
+    /// A result operand could not be interpreted as a static result index.
+    pub const MALFORMED_RESULT_OPERAND: &str = "E014";
+    /// A static result index is not below `required_num_results`.
+    pub const RESULT_INDEX_OUT_OF_RANGE: &str = "E015";
+    /// A required module flag is missing.
+    pub const MISSING_MODULE_FLAG: &str = "E016";
+    /// A module flag is present but malformed.
+    pub const MALFORMED_MODULE_FLAG: &str = "E017";
+    /// A module flag has an unsupported value.
+    pub const UNSUPPORTED_MODULE_FLAG_VALUE: &str = "E018";
+    /// An array runtime call is not backed by a matching fixed-size `[N x ptr]` array.
+    pub const INVALID_ARRAY_BACKING: &str = "E019";
+    /// A `RESULT_ARRAY` output is longer than `i32::MAX`.
+    pub const RESULT_ARRAY_TOO_LONG: &str = "E020";
+    /// A runtime function is used without enabling the capability it requires.
+    pub const MISSING_CAPABILITY: &str = "E021";
+    /// Dynamic results are allocated outside the entry block.
+    pub const DYNAMIC_RESULT_OUTSIDE_ENTRY_BLOCK: &str = "E022";
+    /// A `wasm` function is not exported by the supplied WASM module.
+    pub const MISSING_WASM_EXPORT: &str = "E023";
+    /// A `wasm` function is declared with a different number of parameters than its export.
+    pub const WASM_ARITY_MISMATCH: &str = "E024";
+    /// A `wasm` function parameter or result type does not match its export.
+    pub const WASM_SIGNATURE_MISMATCH: &str = "E025";
+    /// An `mcx<N>`, `mcz<N>` or controlled gate call uses more qubits than
+    /// `required_num_qubits`.
+    pub const MULTI_CONTROLLED_ARITY_OVERFLOW: &str = "E026";
+    /// A Q# runtime array or tuple is used other than as the controls or
+    /// arguments of a controlled gate.
+    pub const UNSUPPORTED_RUNTIME_ARRAY: &str = "E027";
+
+    /// The module specifies a data layout, which is replaced during compilation.
+    pub const DATA_LAYOUT_PRESENT: &str = "W001";
+    /// The module specifies a target triple, which is replaced during compilation.
+    pub const TARGET_TRIPLE_PRESENT: &str = "W002";
+    /// The Q# QDK `__quantum__qis__m__body` synonym for `mz` is used.
+    pub const QSHARP_M_SYNONYM: &str = "W003";
+    /// The Q# QDK `__quantum__qis__mresetz__body` operation is used.
+    pub const QSHARP_MRESETZ: &str = "W004";
+    /// The module defines no globals, so no output labels are available.
+    pub const NO_GLOBALS: &str = "W005";
+}
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Severity {
+    /// The program cannot be compiled.
+    Error,
+    /// The program can be compiled, but something looks suspicious.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        })
+    }
+}
+
+/// Position of a diagnostic in an `.ll` source file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    /// File name as passed by the caller.
+    pub file: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number.
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// Index of an instruction within its function, used to map diagnostics back
+/// to source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IrPosition {
+    pub block: usize,
+    pub instruction: usize,
+}
+
+/// A single problem found while validating a QIR module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Stable identifier of the check that produced this diagnostic, see [`codes`].
+    pub code: &'static str,
+    /// Severity of the diagnostic.
+    pub severity: Severity,
+    /// Name of the offending function, if any.
+    pub function: Option<String>,
+    /// Name of the offending basic block, if any and if the block is named.
+    pub block: Option<String>,
+    /// Printed LLVM IR of the offending instruction, if any.
+    pub instruction: Option<String>,
+    /// Human-readable description of the problem.
+    pub message: String,
+    /// Position in the `.ll` source, when validating from LLVM IR text.
+    pub location: Option<SourceLocation>,
+    position: Option<IrPosition>,
+}
+
+impl Diagnostic {
+    /// Create an error diagnostic that is not attached to any IR location.
+    #[must_use]
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity: Severity::Error,
+            function: None,
+            block: None,
+            instruction: None,
+            message: message.into(),
+            location: None,
+            position: None,
+        }
+    }
+
+    /// Create a warning diagnostic that is not attached to any IR location.
+    #[must_use]
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, message)
+        }
+    }
+
+    /// Long-form explanation of this diagnostic's code, if documented.
+    #[must_use]
+    pub fn explanation(&self) -> Option<&'static Explanation> {
+        explain(self.code)
+    }
+
+    /// Attach the diagnostic to `function`.
+    #[must_use]
+    pub(crate) fn in_function(mut self, function: FunctionValue<'_>) -> Self {
+        self.function = function.get_name().to_str().ok().map(ToOwned::to_owned);
+        self
+    }
+
+    /// Attach the diagnostic to `instr` and its enclosing block and function.
+    #[must_use]
+    pub(crate) fn at_instruction(mut self, instr: InstructionValue<'_>) -> Self {
+        if let Some(bb) = instr.get_parent() {
+            self.block = block_name(bb);
+            if let Some(function) = bb.get_parent() {
+                self = self.in_function(function);
+                self.position = function
+                    .get_basic_block_iter()
+                    .position(|candidate| candidate == bb)
+                    .zip(
+                        bb.get_instructions()
+                            .position(|candidate| candidate == instr),
+                    )
+                    .map(|(block, instruction)| IrPosition { block, instruction });
+            }
+        }
+        self.instruction = Some(instr.print_to_string().to_string().trim().to_owned());
+        self
+    }
+
+    /// Fill in [`Diagnostic::location`] from `source_map`.
+    pub(crate) fn resolve_location(&mut self, source_map: &SourceMap) {
+        if let Some(function) = &self.function {
+            self.location = source_map.resolve(function, self.position);
+        }
+    }
+}
+
+fn block_name(bb: BasicBlock<'_>) -> Option<String> {
+    decode_llvm_c_string(bb.get_name())
+        .filter(|name| !name.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{location}: ")?;
+        }
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        if let Some(function) = &self.function {
+            write!(f, "\n  in function `{function}`")?;
+            if let Some(block) = &self.block {
+                write!(f, ", block `{block}`")?;
+            }
+        }
+        if let Some(instruction) = &self.instruction {
+            write!(f, "\n  at `{instruction}`")?;
+        }
+        if self.explanation().is_some() {
+            write!(
+                f,
+                "\n  = help: run `qir-qis explain {}` for more information",
+                self.code
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of validating a QIR module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Every diagnostic produced by the validation passes, in pass order.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if any diagnostic has [`Severity::Error`].
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// Diagnostics with [`Severity::Error`].
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Diagnostics with [`Severity::Warning`].
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+
+    /// Promote every warning to an error, for pipelines that treat warnings
+    /// as failures.
+    pub fn deny_warnings(&mut self) {
+        for diagnostic in &mut self.diagnostics {
+            diagnostic.severity = Severity::Error;
+        }
+    }
+
+    /// Turn the report into an [`Error::Validation`] if it contains errors, or
+    /// any warnings when `deny_warnings` is set.
+    ///
+    /// # Errors
+    /// Returns [`Error::Validation`] carrying the (promoted) report.
+    pub fn into_result(mut self, deny_warnings: bool) -> Result<Self, Error> {
+        if deny_warnings {
+            self.deny_warnings();
+        }
+        if self.has_errors() {
+            return Err(Error::Validation(self));
+        }
+        Ok(self)
+    }
+
+    pub(crate) fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub(crate) fn resolve_locations(&mut self, source_map: &SourceMap) {
+        for diagnostic in &mut self.diagnostics {
+            diagnostic.resolve_location(source_map);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use super::{Diagnostic, Severity, ValidationReport, codes};
+
+    #[test]
+    fn test_report_separates_errors_and_warnings() {
+        let mut report = ValidationReport::default();
+        assert!(!report.has_errors());
+
+        report.push(Diagnostic::warning(codes::TARGET_TRIPLE_PRESENT, "warning"));
+        assert!(!report.has_errors());
+        assert_eq!(report.warnings().count(), 1);
+
+        report.push(Diagnostic::error(
+            codes::UNSUPPORTED_QIS_FUNCTION,
+            "Unsupported QIR QIS function: __quantum__qis__foo__body",
+        ));
+        assert!(report.has_errors());
+        assert_eq!(
+            report.errors().map(|d| d.code).collect::<Vec<_>>(),
+            vec![codes::UNSUPPORTED_QIS_FUNCTION]
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_display_includes_location() {
+        let diagnostic = Diagnostic {
+            function: Some("main".to_string()),
+            block: Some("entry".to_string()),
+            instruction: Some("call void @foo()".to_string()),
+            ..Diagnostic::error(codes::UNSUPPORTED_QIS_FUNCTION, "bad call")
+        };
+        assert_eq!(
+            diagnostic.to_string(),
+            "error[E007]: bad call\n  in function `main`, block `entry`\n  at `call void @foo()`\n  = help: run `qir-qis explain E007` for more information"
+        );
+    }
+
+    #[test]
+    fn test_into_result_respects_deny_warnings() {
+        let report = ValidationReport {
+            diagnostics: vec![Diagnostic::warning(
+                codes::DATA_LAYOUT_PRESENT,
+                "QIR module has a data layout",
+            )],
+        };
+        let allowed = report
+            .clone()
+            .into_result(false)
+            .expect("warnings alone should not fail");
+        assert_eq!(allowed.warnings().count(), 1);
+
+        let err = report
+            .into_result(true)
+            .expect_err("denied warnings should fail");
+        assert!(matches!(
+            &err,
+            crate::Error::Validation(denied)
+                if denied.diagnostics.iter().all(|d| d.severity == Severity::Error)
+        ));
+        assert_eq!(err.to_string(), "QIR module has a data layout");
+    }
+}
@@ -0,0 +1,282 @@
+//! Error type returned by the public QIR-QIS API.
+
+use std::fmt;
+
+use crate::{StageDump, ValidationReport, diagnostics::codes};
+
+/// Errors produced while parsing, validating or compiling QIR.
+///
+/// The `Display` implementation renders the same human-readable message that
+/// earlier releases returned as a plain `String`. Every variant has a stable
+/// [`code`](Error::code) documented in the [`catalog`](crate::catalog).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input could not be parsed as LLVM IR text or bitcode.
+    Parse(String),
+    /// The LLVM verifier rejected a module.
+    Verifier {
+        /// The compilation stage at which verification ran.
+        stage: String,
+        /// The verifier output.
+        message: String,
+    },
+    /// The QIR program violates one or more QIR or QIS constraints.
+    ///
+    /// The report contains at least one diagnostic with
+    /// [`Severity::Error`](crate::Severity::Error).
+    Validation(ValidationReport),
+    /// Lowering QIR to QIS failed.
+    Lowering(String),
+    /// The module has no function with the `entry_point` attribute.
+    MissingEntryFunction,
+    /// A [`Pipeline`](crate::Pipeline) has no stage with the given name.
+    UnknownStage {
+        /// The requested stage.
+        stage: String,
+        /// The stages of the pipeline, in order.
+        expected: Vec<String>,
+    },
+    /// Creating the target machine or running the optimization pipeline failed.
+    Target(String),
+    /// The supplied WASM module could not be parsed.
+    Wasm(String),
+    /// A bundle could not be read.
+    Bundle(String),
+    /// A compilation option has an invalid value, such as an unknown
+    /// optimization level or target architecture.
+    InvalidOption(String),
+    /// A pass of a [`Pipeline`](crate::Pipeline) failed after the IR had been
+    /// recorded after earlier stages.
+    ///
+    /// Behaves as the error of the failing pass, which
+    /// [`Error::split_dumps`] separates from the recorded IR.
+    Stage {
+        /// The name of the failing pass.
+        stage: String,
+        /// The error of the failing pass.
+        error: Box<Error>,
+        /// IR recorded after the stages that ran before the failure.
+        dumps: Vec<StageDump>,
+    },
+}
+
+impl Error {
+    /// Short, stable identifier of the error variant.
+    ///
+    /// This is the value exposed as `kind` on the Python exceptions.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Parse(_) => "parse",
+            Self::Verifier { .. } => "verifier",
+            Self::Validation(_) => "validation",
+            Self::Lowering(_) | Self::MissingEntryFunction => "lowering",
+            Self::Target(_) => "target",
+            Self::Wasm(_) => "wasm",
+            Self::Bundle(_) => "bundle",
+            Self::InvalidOption(_) | Self::UnknownStage { .. } => "option",
+            Self::Stage { error, .. } => error.kind(),
+        }
+    }
+
+    /// Stable code of the error, explained in the [`catalog`](crate::catalog).
+    ///
+    /// Validation errors have a code of their own; the code of each
+    /// violation is on its [`Diagnostic`](crate::Diagnostic).
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Parse(_) => codes::PARSE_FAILED,
+            Self::Verifier { .. } => codes::VERIFIER_FAILED,
+            Self::Validation(_) => codes::VALIDATION_FAILED,
+            Self::Lowering(_) => codes::LOWERING_FAILED,
+            Self::MissingEntryFunction => codes::MISSING_ENTRY_POINT,
+            Self::UnknownStage { .. } => codes::UNKNOWN_STAGE,
+            Self::Target(_) => codes::TARGET_FAILED,
+            Self::Wasm(_) => codes::INVALID_WASM_MODULE,
+            Self::Bundle(_) => codes::INVALID_BUNDLE,
+            Self::InvalidOption(_) => codes::INVALID_OPTION,
+            Self::Stage { error, .. } => error.code(),
+        }
+    }
+
+    /// Individual error messages carried by this error.
+    ///
+    /// Validation errors report one message per violation; every other
+    /// variant reports a single message.
+    #[must_use]
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            Self::Validation(report) => report
+                .errors()
+                .map(|diagnostic| diagnostic.message.clone())
+                .collect(),
+            Self::Stage { error, .. } => error.messages(),
+            Self::Parse(_)
+            | Self::Verifier { .. }
+            | Self::Lowering(_)
+            | Self::MissingEntryFunction
+            | Self::UnknownStage { .. }
+            | Self::Target(_)
+            | Self::Wasm(_)
+            | Self::Bundle(_)
+            | Self::InvalidOption(_) => vec![self.to_string()],
+        }
+    }
+
+    /// The error of the failing pass, and the IR recorded after the stages
+    /// before it, which is only non-empty for [`Error::Stage`].
+    #[must_use]
+    pub fn split_dumps(self) -> (Self, Vec<StageDump>) {
+        match self {
+            Self::Stage { error, dumps, .. } => (*error, dumps),
+            error @ (Self::Parse(_)
+            | Self::Verifier { .. }
+            | Self::Validation(_)
+            | Self::Lowering(_)
+            | Self::MissingEntryFunction
+            | Self::UnknownStage { .. }
+            | Self::Target(_)
+            | Self::Wasm(_)
+            | Self::Bundle(_)
+            | Self::InvalidOption(_)) => (error, Vec::new()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(message)
+            | Self::Lowering(message)
+            | Self::Target(message)
+            | Self::Wasm(message)
+            | Self::Bundle(message)
+            | Self::InvalidOption(message) => f.write_str(message),
+            Self::MissingEntryFunction => {
+                f.write_str("Failed to find entry function in QIR module: No entry function found")
+            }
+            Self::UnknownStage { stage, expected } => write!(
+                f,
+                "Unknown pipeline stage `{stage}` (expected one of: {})",
+                expected.join(", ")
+            ),
+            Self::Verifier { stage, message } => write!(f, "{stage}: {message}"),
+            Self::Validation(_) => f.write_str(&self.messages().join("; ")),
+            Self::Stage { error, .. } => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use crate::{Diagnostic, ValidationReport, diagnostics::codes, explain};
+
+    fn report(messages: &[&str]) -> ValidationReport {
+        ValidationReport {
+            diagnostics: messages
+                .iter()
+                .map(|message| Diagnostic::error(codes::UNSUPPORTED_QIS_FUNCTION, *message))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_display_matches_legacy_string_messages() {
+        let validation = Error::Validation(report(&[
+            "Missing required attribute: `qir_profiles`",
+            "Unsupported QIR QIS function: __quantum__qis__foo__body",
+        ]));
+        assert_eq!(
+            validation.to_string(),
+            "Missing required attribute: `qir_profiles`; Unsupported QIR QIS function: __quantum__qis__foo__body"
+        );
+
+        let verifier = Error::Verifier {
+            stage: "LLVM module verification failed".to_string(),
+            message: "broken module".to_string(),
+        };
+        assert_eq!(
+            verifier.to_string(),
+            "LLVM module verification failed: broken module"
+        );
+        assert_eq!(
+            Error::InvalidOption("Invalid target architecture: riscv".to_string()).to_string(),
+            "Invalid target architecture: riscv"
+        );
+    }
+
+    #[test]
+    fn test_messages_and_kind_expose_variant_information() {
+        let validation = Error::Validation(report(&["a", "b"]));
+        assert_eq!(validation.kind(), "validation");
+        assert_eq!(validation.messages(), vec!["a", "b"]);
+
+        let wasm = Error::Wasm("Failed to parse WASM: bad magic".to_string());
+        assert_eq!(wasm.kind(), "wasm");
+        assert_eq!(wasm.messages(), vec!["Failed to parse WASM: bad magic"]);
+    }
+
+    #[test]
+    fn test_typed_variants_keep_legacy_kinds_and_messages() {
+        assert_eq!(Error::MissingEntryFunction.kind(), "lowering");
+        assert_eq!(
+            Error::MissingEntryFunction.to_string(),
+            "Failed to find entry function in QIR module: No entry function found"
+        );
+
+        let stage = Error::UnknownStage {
+            stage: "peephole".to_string(),
+            expected: vec!["decompose".to_string(), "optimize".to_string()],
+        };
+        assert_eq!(stage.kind(), "option");
+        assert_eq!(
+            stage.to_string(),
+            "Unknown pipeline stage `peephole` (expected one of: decompose, optimize)"
+        );
+    }
+
+    #[test]
+    fn test_every_code_is_documented() {
+        let errors = [
+            Error::Parse(String::new()),
+            Error::Verifier {
+                stage: String::new(),
+                message: String::new(),
+            },
+            Error::Validation(report(&["a"])),
+            Error::Lowering(String::new()),
+            Error::MissingEntryFunction,
+            Error::UnknownStage {
+                stage: String::new(),
+                expected: Vec::new(),
+            },
+            Error::Target(String::new()),
+            Error::Wasm(String::new()),
+            Error::Bundle(String::new()),
+            Error::InvalidOption(String::new()),
+        ];
+        for error in &errors {
+            assert!(
+                explain(error.code()).is_some(),
+                "{} is undocumented",
+                error.code()
+            );
+        }
+        assert_eq!(
+            Error::MissingEntryFunction.code(),
+            codes::MISSING_ENTRY_POINT
+        );
+
+        let stage = Error::Stage {
+            stage: "fail".to_string(),
+            error: Box::new(Error::Target("boom".to_string())),
+            dumps: Vec::new(),
+        };
+        assert_eq!(stage.code(), codes::TARGET_FAILED);
+    }
+}
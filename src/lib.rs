@@ -44,12 +44,35 @@ use pyo3::prelude::*;
 #[cfg(feature = "python")]
 use pyo3_stub_gen::define_stub_info_gatherer;
 
+pub mod bundle;
+pub mod catalog;
+pub mod compiler;
 pub mod convert;
 mod decompose;
+pub mod diagnostics;
+mod error;
 mod llvm_verify;
 pub mod opt;
+pub mod options;
+pub mod pipeline;
+mod source_map;
 mod utils;
 
+use std::collections::HashMap;
+
+use source_map::SourceMap;
+
+pub use aux::CapabilityFlags;
+pub use bundle::Bundle;
+pub use catalog::{Explanation, explain};
+pub use compiler::Compiler;
+pub use decompose::{DecompositionFn, DecompositionRegistry, NativeGates};
+pub use diagnostics::{Diagnostic, Severity, SourceLocation, ValidationReport};
+pub use error::Error;
+pub use opt::Emit;
+pub use options::{CompileOptions, OptLevel, Target};
+pub use pipeline::{Pass, PassContext, Pipeline, StageDump};
+
 #[cfg(windows)]
 pub const DEFAULT_OPT_LEVEL: u32 = 0;
 #[cfg(not(windows))]
@@ -71,10 +94,12 @@ mod aux {
             create_reset_call, get_index, get_or_create_function, get_required_num_qubits,
             get_required_num_qubits_strict, get_required_num_results, get_result_vars,
             get_string_label, handle_tuple_or_array_output, parse_gep, record_classical_output,
-            replace_rxy_call, replace_rz_call, replace_rzz_call,
+            replace_rxy_call, replace_rz_call, replace_rzz_call, replace_zzmax_call,
         },
         decode_llvm_bytes,
-        utils::extract_operands,
+        decompose::DecompositionRegistry,
+        diagnostics::{Diagnostic, ValidationReport, codes},
+        utils::{WasmSignature, WasmType, extract_operands},
     };
 
     use inkwell::{
@@ -85,12 +110,12 @@ mod aux {
         module::{Linkage, Module},
         types::{ArrayType, BasicMetadataTypeEnum, BasicTypeEnum, FunctionType},
         values::{
-            AnyValue, BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallSiteValue,
-            FunctionValue, InstructionOpcode, PointerValue,
+            AnyValue, AnyValueEnum, BasicMetadataValueEnum, BasicValue, BasicValueEnum,
+            CallSiteValue, FunctionValue, InstructionOpcode, InstructionValue, PointerValue,
         },
     };
 
-    static ALLOWED_QIS_FNS: [&str; 23] = [
+    static ALLOWED_QIS_FNS: [&str; 36] = [
         // Native gates
         "__quantum__qis__rxy__body",
         "__quantum__qis__rz__body",
@@ -101,8 +126,11 @@ mod aux {
         // mz + reset
         "__quantum__qis__mresetz__body",
         // Synonyms for native gates
-        "__quantum__qis__u1q__body", // rxy
-        "__quantum__qis__m__body",   // mz
+        "__quantum__qis__u1q__body",     // rxy
+        "__quantum__qis__phasedx__body", // rxy
+        "__quantum__qis__zzphase__body", // rzz
+        "__quantum__qis__zzmax__body",   // rzz(π/2)
+        "__quantum__qis__m__body",       // mz
         // Decomposed to native gates
         "__quantum__qis__h__body",
         "__quantum__qis__x__body",
@@ -117,8 +145,19 @@ mod aux {
         "__quantum__qis__cz__body",
         "__quantum__qis__cx__body",
         "__quantum__qis__cnot__body",
+        "__quantum__qis__cy__body",
+        "__quantum__qis__ch__body",
+        "__quantum__qis__swap__body",
+        "__quantum__qis__crz__body",
+        "__quantum__qis__cphase__body",
+        "__quantum__qis__rxx__body",
+        "__quantum__qis__ryy__body",
+        "__quantum__qis__tk2__body",
         "__quantum__qis__ccx__body",
-        // Note: barrier instructions with arbitrary arity are validated separately
+        "__quantum__qis__ccz__body",
+        "__quantum__qis__cswap__body",
+        // Note: barrier, mcx and mcz instructions with arbitrary arity are
+        // validated separately
     ];
 
     static BASE_ALLOWED_RT_FNS: [&str; 8] = [
@@ -132,13 +171,43 @@ mod aux {
         "__quantum__rt__int_record_output",
     ];
 
-    #[derive(Clone, Copy, Debug, Default)]
+    /// Q# runtime functions building the `%Array*` of controls and the
+    /// `%Tuple*` of arguments of controlled gates, which are lowered to stack
+    /// arrays.
+    pub static QSHARP_CONTROL_RT_FNS: [&str; 7] = [
+        "__quantum__rt__array_create_1d",
+        "__quantum__rt__array_get_element_ptr_1d",
+        "__quantum__rt__array_update_reference_count",
+        "__quantum__rt__array_update_alias_count",
+        "__quantum__rt__tuple_create",
+        "__quantum__rt__tuple_update_reference_count",
+        "__quantum__rt__tuple_update_alias_count",
+    ];
+
+    /// Optional QIR capabilities enabled through module flags.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[non_exhaustive]
     pub struct CapabilityFlags {
+        /// Qubits may be allocated and released at runtime.
         pub dynamic_qubit_management: bool,
+        /// Results may be allocated and released at runtime.
         pub dynamic_result_management: bool,
+        /// The qubit and result array runtime functions may be used.
         pub arrays: bool,
     }
 
+    impl CapabilityFlags {
+        /// Each capability with its module flag name.
+        #[must_use]
+        pub const fn entries(self) -> [(&'static str, bool); 3] {
+            [
+                ("dynamic_qubit_management", self.dynamic_qubit_management),
+                ("dynamic_result_management", self.dynamic_result_management),
+                ("arrays", self.arrays),
+            ]
+        }
+    }
+
     fn is_capability_gated_rt_function(fn_name: &str) -> bool {
         matches!(
             fn_name,
@@ -248,32 +317,51 @@ mod aux {
     ];
 
     #[cfg(not(windows))]
-    pub fn validate_module_layout_and_triple(module: &Module) {
+    pub fn validate_module_layout_and_triple(module: &Module, report: &mut ValidationReport) {
         let datalayout = module.get_data_layout();
         let triple = module.get_triple();
 
         if !datalayout.as_str().is_empty() {
-            log::warn!("QIR module has a data layout: {:?}", datalayout.as_str());
+            report.push(Diagnostic::warning(
+                codes::DATA_LAYOUT_PRESENT,
+                format!("QIR module has a data layout: {:?}", datalayout.as_str()),
+            ));
         }
         if !triple.as_str().is_empty() {
-            log::warn!("QIR module has a target triple: {:?}", triple.as_str());
+            report.push(Diagnostic::warning(
+                codes::TARGET_TRIPLE_PRESENT,
+                format!("QIR module has a target triple: {:?}", triple.as_str()),
+            ));
         }
     }
 
     #[cfg(windows)]
-    pub const fn validate_module_layout_and_triple(_module: &Module) {
+    pub const fn validate_module_layout_and_triple(
+        _module: &Module,
+        _report: &mut ValidationReport,
+    ) {
         // Best-effort warning path only. Avoid unstable getter APIs on Windows,
         // where these calls have been unreliable in CI; re-checking locally on
         // Windows Arm64 on March 23, 2026 reproduced STATUS_ACCESS_VIOLATION.
     }
 
+    /// Whether `fn_name` is a QIS function supported without a
+    /// [`DecompositionRegistry`](crate::DecompositionRegistry).
+    pub fn is_builtin_qis_function(fn_name: &str) -> bool {
+        ALLOWED_QIS_FNS.contains(&fn_name)
+            || parse_barrier_arity(fn_name).is_ok()
+            || parse_multi_controlled_arity(fn_name).is_ok()
+            || parse_controlled_gate(fn_name).is_ok()
+    }
+
     pub fn validate_functions(
         module: &Module,
         entry_fn: FunctionValue,
-        _wasm_fns: &BTreeMap<String, u64>,
-        errors: &mut Vec<String>,
+        wasm_fns: Option<&BTreeMap<String, WasmSignature>>,
+        registry: Option<&DecompositionRegistry>,
+        report: &mut ValidationReport,
     ) {
-        // Extract required_num_qubits for barrier validation
+        // Extract required_num_qubits for barrier and multi-controlled gate validation
         let required_num_qubits = get_required_num_qubits(entry_fn);
 
         for fun in module.get_functions() {
@@ -283,9 +371,13 @@ mod aux {
             }
             let fn_name = fun.get_name().to_str().unwrap_or("");
             if fn_name.starts_with("qir_qis.") {
-                errors.push(format!(
-                    "Input QIR must not define internal helper function: {fn_name}"
-                ));
+                report.push(
+                    Diagnostic::error(
+                        codes::RESERVED_HELPER_NAME,
+                        format!("Input QIR must not define internal helper function: {fn_name}"),
+                    )
+                    .in_function(fun),
+                );
                 continue;
             }
             if fn_name.starts_with("__quantum__qis__") {
@@ -299,9 +391,15 @@ mod aux {
                             && let Ok(arity_u32) = u32::try_from(arity)
                             && arity_u32 > max_qubits
                         {
-                            errors.push(format!(
-                "Barrier arity {arity} exceeds module's required_num_qubits ({max_qubits})"
-            ));
+                            report.push(
+                                Diagnostic::error(
+                                    codes::BARRIER_ARITY_OVERFLOW,
+                                    format!(
+                                        "Barrier arity {arity} exceeds module's required_num_qubits ({max_qubits})"
+                                    ),
+                                )
+                                .in_function(fun),
+                            );
                         }
                         true
                     })
@@ -309,24 +407,73 @@ mod aux {
                     false
                 };
 
-                if !is_barrier && !ALLOWED_QIS_FNS.contains(&fn_name) {
-                    errors.push(format!("Unsupported QIR QIS function: {fn_name}"));
+                // Check for multi-controlled gates with arbitrary arity (mcx1, mcz2, ...)
+                let is_multi_controlled =
+                    parse_multi_controlled_arity(fn_name).is_ok_and(|arity| {
+                        // The gate acts on `arity` distinct qubits
+                        if let Some(max_qubits) = required_num_qubits
+                            && let Ok(arity_u32) = u32::try_from(arity)
+                            && arity_u32 > max_qubits
+                        {
+                            report.push(
+                                Diagnostic::error(
+                                    codes::MULTI_CONTROLLED_ARITY_OVERFLOW,
+                                    format!(
+                                        "Multi-controlled gate arity {arity} exceeds module's required_num_qubits ({max_qubits})"
+                                    ),
+                                )
+                                .in_function(fun),
+                            );
+                        }
+                        true
+                    });
+
+                if !is_barrier
+                    && !is_multi_controlled
+                    && parse_controlled_gate(fn_name).is_err()
+                    && !ALLOWED_QIS_FNS.contains(&fn_name)
+                    && !registry.is_some_and(|registry| registry.contains(fn_name))
+                {
+                    report.push(
+                        Diagnostic::error(
+                            codes::UNSUPPORTED_QIS_FUNCTION,
+                            format!("Unsupported QIR QIS function: {fn_name}"),
+                        )
+                        .in_function(fun),
+                    );
                 }
                 continue;
             } else if fn_name.starts_with("__quantum__rt__") {
+                // Q# runtime arrays and tuples are checked at each call by
+                // `validate_runtime_control_arrays`.
                 if !BASE_ALLOWED_RT_FNS.contains(&fn_name)
                     && !is_capability_gated_rt_function(fn_name)
+                    && !QSHARP_CONTROL_RT_FNS.contains(&fn_name)
                 {
-                    errors.push(format!("Unsupported QIR RT function: {fn_name}"));
+                    report.push(
+                        Diagnostic::error(
+                            codes::UNSUPPORTED_RT_FUNCTION,
+                            format!("Unsupported QIR RT function: {fn_name}"),
+                        )
+                        .in_function(fun),
+                    );
                 } else if is_capability_gated_rt_function(fn_name)
                     && let Err(err) = validate_dynamic_rt_signature(fn_name, fun.get_type())
                 {
-                    errors.push(err);
+                    report.push(
+                        Diagnostic::error(codes::MALFORMED_RT_DECLARATION, err).in_function(fun),
+                    );
                 }
                 continue;
             } else if fn_name.starts_with("___") {
                 if !ALLOWED_QTM_FNS.contains(&fn_name) {
-                    errors.push(format!("Unsupported Qtm QIS function: {fn_name}"));
+                    report.push(
+                        Diagnostic::error(
+                            codes::UNSUPPORTED_QTM_FUNCTION,
+                            format!("Unsupported Qtm QIS function: {fn_name}"),
+                        )
+                        .in_function(fun),
+                    );
                 }
                 continue;
             }
@@ -335,7 +482,13 @@ mod aux {
                 // IR defined functions
                 // TODO: allowed only if "ir_functions" is true
                 if fn_name == "main" {
-                    errors.push("IR defined function cannot be called `main`".to_string());
+                    report.push(
+                        Diagnostic::error(
+                            codes::IR_FUNCTION_NAMED_MAIN,
+                            "IR defined function cannot be called `main`",
+                        )
+                        .in_function(fun),
+                    );
                 }
                 // See whether a function returns a pointer type
                 if fun
@@ -343,21 +496,126 @@ mod aux {
                     .get_return_type()
                     .is_some_and(BasicTypeEnum::is_pointer_type)
                 {
-                    errors.push(format!("Function `{fn_name}` cannot return a pointer type"));
+                    report.push(
+                        Diagnostic::error(
+                            codes::IR_FUNCTION_RETURNS_POINTER,
+                            format!("Function `{fn_name}` cannot return a pointer type"),
+                        )
+                        .in_function(fun),
+                    );
                 }
                 continue;
             }
 
+            if let Some(wasm_fns) = wasm_fns
+                && fun
+                    .get_string_attribute(AttributeLoc::Function, "wasm")
+                    .is_some()
+            {
+                validate_wasm_declaration(fun, fn_name, wasm_fns, report);
+                continue;
+            }
+
             log::debug!(
                 "External function `{fn_name}` found, leaving as-is for downstream processing"
             );
         }
     }
 
+    /// Check a `wasm` function declaration against the WASM export of the same name.
+    fn validate_wasm_declaration(
+        fun: FunctionValue,
+        fn_name: &str,
+        wasm_fns: &BTreeMap<String, WasmSignature>,
+        report: &mut ValidationReport,
+    ) {
+        let Some(signature) = wasm_fns.get(fn_name) else {
+            report.push(
+                Diagnostic::error(
+                    codes::MISSING_WASM_EXPORT,
+                    format!("WASM function `{fn_name}` is not exported by the WASM module"),
+                )
+                .in_function(fun),
+            );
+            return;
+        };
+
+        let fn_type = fun.get_type();
+        let params = fn_type.get_param_types();
+        if params.len() != signature.params.len() {
+            report.push(
+                Diagnostic::error(
+                    codes::WASM_ARITY_MISMATCH,
+                    format!(
+                        "WASM function `{fn_name}` takes {} parameters, but is declared with {}",
+                        signature.params.len(),
+                        params.len()
+                    ),
+                )
+                .in_function(fun),
+            );
+            return;
+        }
+        let mismatch = |message: String| {
+            Diagnostic::error(codes::WASM_SIGNATURE_MISMATCH, message).in_function(fun)
+        };
+        for (index, (param, expected)) in params.iter().zip(&signature.params).enumerate() {
+            if wasm_type_of(*param) != Some(*expected) {
+                report.push(mismatch(format!(
+                    "Parameter {index} of WASM function `{fn_name}` is {expected} in the WASM module, but `{}` in the declaration",
+                    param.print_to_string().to_string()
+                )));
+            }
+        }
+
+        let declared = fn_type.get_return_type();
+        match (signature.results.as_slice(), declared) {
+            ([], None) => {}
+            ([expected], Some(declared)) => {
+                if wasm_type_of(declared.into()) != Some(*expected) {
+                    report.push(mismatch(format!(
+                        "WASM function `{fn_name}` returns {expected} in the WASM module, but `{}` in the declaration",
+                        declared.print_to_string().to_string()
+                    )));
+                }
+            }
+            ([], Some(declared)) => report.push(mismatch(format!(
+                "WASM function `{fn_name}` returns nothing in the WASM module, but `{}` in the declaration",
+                declared.print_to_string().to_string()
+            ))),
+            ([expected], None) => report.push(mismatch(format!(
+                "WASM function `{fn_name}` returns {expected} in the WASM module, but is declared `void`"
+            ))),
+            (results, _) => report.push(mismatch(format!(
+                "WASM function `{fn_name}` returns {} values, which a QIR declaration cannot express",
+                results.len()
+            ))),
+        }
+    }
+
+    /// The WASM value type an LLVM parameter or return type is passed as, if any.
+    fn wasm_type_of(ty: BasicMetadataTypeEnum) -> Option<WasmType> {
+        if let BasicMetadataTypeEnum::IntType(int) = ty {
+            match int.get_bit_width() {
+                32 => Some(WasmType::I32),
+                64 => Some(WasmType::I64),
+                _ => None,
+            }
+        } else if let BasicMetadataTypeEnum::FloatType(float) = ty {
+            match float.get_bit_width() {
+                32 => Some(WasmType::F32),
+                64 => Some(WasmType::F64),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
     pub fn validate_result_slot_usage(
         module: &Module,
         entry_fn: FunctionValue,
-        errors: &mut Vec<String>,
+        report: &mut ValidationReport,
     ) {
         if entry_fn
             .get_string_attribute(AttributeLoc::Function, "required_num_results")
@@ -369,7 +627,10 @@ mod aux {
         let required_num_results = match get_required_num_results(entry_fn) {
             Ok(required_num_results) => required_num_results,
             Err(err) => {
-                errors.push(err);
+                report.push(
+                    Diagnostic::error(codes::INVALID_REQUIRED_NUM_RESULTS, err)
+                        .in_function(entry_fn),
+                );
                 return;
             }
         };
@@ -398,64 +659,75 @@ mod aux {
                         _ => continue,
                     };
 
+                    let malformed_operand = |message: String| {
+                        Diagnostic::error(codes::MALFORMED_RESULT_OPERAND, message)
+                            .at_instruction(instr)
+                    };
                     let call_args = match extract_operands(&instr) {
                         Ok(args) => args,
                         Err(err) => {
-                            errors.push(format!("Failed to inspect `{fn_name}` call: {err}"));
+                            report.push(malformed_operand(format!(
+                                "Failed to inspect `{fn_name}` call: {err}"
+                            )));
                             continue;
                         }
                     };
                     let Some(result_arg) = call_args.get(result_operand_index).copied() else {
-                        errors.push(format!("Call to `{fn_name}` is missing a result operand"));
+                        report.push(malformed_operand(format!(
+                            "Call to `{fn_name}` is missing a result operand"
+                        )));
                         continue;
                     };
 
                     let BasicValueEnum::PointerValue(result_ptr) = result_arg else {
-                        errors.push(format!(
+                        report.push(malformed_operand(format!(
                             "Call to `{fn_name}` has a non-pointer result operand"
-                        ));
+                        )));
                         continue;
                     };
 
                     let result_idx = match get_index(result_ptr) {
                         Ok(idx) => idx,
                         Err(err) => {
-                            errors.push(format!(
+                            report.push(malformed_operand(format!(
                                 "Failed to inspect result operand for `{fn_name}`: {err}"
-                            ));
+                            )));
                             continue;
                         }
                     };
                     if let Err(err) = checked_result_index(result_idx, required_num_results) {
-                        errors.push(err);
+                        report.push(
+                            Diagnostic::error(codes::RESULT_INDEX_OUT_OF_RANGE, err)
+                                .at_instruction(instr),
+                        );
                     }
                 }
             }
         }
     }
 
-    pub fn validate_module_flags(module: &Module, errors: &mut Vec<String>) {
+    pub fn validate_module_flags(module: &Module, report: &mut ValidationReport) {
         let module_flags = collect_module_flags(module);
         validate_exact_module_flag(
             &module_flags,
             "qir_major_version",
             &["i32 1", "i32 2"],
-            errors,
+            report,
         );
-        validate_exact_module_flag(&module_flags, "qir_minor_version", &["i32 0"], errors);
+        validate_exact_module_flag(&module_flags, "qir_minor_version", &["i32 0"], report);
         validate_exact_module_flag(
             &module_flags,
             "dynamic_qubit_management",
             &["i1 false", "i1 true"],
-            errors,
+            report,
         );
         validate_exact_module_flag(
             &module_flags,
             "dynamic_result_management",
             &["i1 false", "i1 true"],
-            errors,
+            report,
         );
-        validate_optional_module_flag(&module_flags, "arrays", &["i1 false", "i1 true"], errors);
+        validate_optional_module_flag(&module_flags, "arrays", &["i1 false", "i1 true"], report);
     }
 
     pub struct ModuleFlags {
@@ -551,11 +823,14 @@ mod aux {
         module_flags: &ModuleFlags,
         flag_name: &str,
         expected_values: &[&str],
-        errors: &mut Vec<String>,
+        report: &mut ValidationReport,
     ) {
         let Some(actual_values) = module_flags.get(flag_name) else {
             if module_flags.is_malformed(flag_name) {
-                errors.push(format!("Missing or unsupported module flag: {flag_name}"));
+                report.push(Diagnostic::error(
+                    codes::MALFORMED_MODULE_FLAG,
+                    format!("Missing or unsupported module flag: {flag_name}"),
+                ));
             }
             return;
         };
@@ -572,20 +847,29 @@ mod aux {
         } else {
             format!("one of {}", expected_values.join(", "))
         };
-        errors.push(format!("Unsupported {flag_name}: expected {expected}"));
+        report.push(Diagnostic::error(
+            codes::UNSUPPORTED_MODULE_FLAG_VALUE,
+            format!("Unsupported {flag_name}: expected {expected}"),
+        ));
     }
     fn validate_exact_module_flag(
         module_flags: &ModuleFlags,
         flag_name: &str,
         expected_values: &[&str],
-        errors: &mut Vec<String>,
+        report: &mut ValidationReport,
     ) {
         let Some(actual_values) = module_flags.get(flag_name) else {
             if module_flags.is_malformed(flag_name) {
-                errors.push(format!("Missing or unsupported module flag: {flag_name}"));
+                report.push(Diagnostic::error(
+                    codes::MALFORMED_MODULE_FLAG,
+                    format!("Missing or unsupported module flag: {flag_name}"),
+                ));
                 return;
             }
-            errors.push(format!("Missing required module flag: {flag_name}"));
+            report.push(Diagnostic::error(
+                codes::MISSING_MODULE_FLAG,
+                format!("Missing required module flag: {flag_name}"),
+            ));
             return;
         };
 
@@ -601,10 +885,13 @@ mod aux {
         } else {
             format!("one of {}", expected_values.join(", "))
         };
-        errors.push(format!("Unsupported {flag_name}: expected {expected}"));
+        report.push(Diagnostic::error(
+            codes::UNSUPPORTED_MODULE_FLAG_VALUE,
+            format!("Unsupported {flag_name}: expected {expected}"),
+        ));
     }
 
-    fn get_fixed_pointer_array_len(
+    pub fn get_fixed_pointer_array_len(
         array_ptr: PointerValue<'_>,
         opname: &str,
     ) -> Result<u64, String> {
@@ -665,7 +952,10 @@ mod aux {
         Err(array_backing_error())
     }
 
-    pub fn validate_dynamic_array_allocation_backing(module: &Module, errors: &mut Vec<String>) {
+    pub fn validate_dynamic_array_allocation_backing(
+        module: &Module,
+        report: &mut ValidationReport,
+    ) {
         for fun in module.get_functions() {
             for bb in fun.get_basic_blocks() {
                 for instr in bb.get_instructions() {
@@ -691,17 +981,23 @@ mod aux {
                         continue;
                     }
 
+                    let invalid_backing = |message: String| {
+                        Diagnostic::error(codes::INVALID_ARRAY_BACKING, message)
+                            .at_instruction(instr)
+                    };
                     let call_args: Vec<BasicValueEnum> = match extract_operands(&instr) {
                         Ok(args) => args,
                         Err(err) => {
-                            errors.push(format!("Failed to inspect {fn_name} operands: {err}"));
+                            report.push(invalid_backing(format!(
+                                "Failed to inspect {fn_name} operands: {err}"
+                            )));
                             continue;
                         }
                     };
                     let requested_len = match extract_const_len(call_args[0], fn_name) {
                         Ok(len) => len,
                         Err(err) => {
-                            errors.push(err);
+                            report.push(invalid_backing(err));
                             continue;
                         }
                     };
@@ -711,21 +1007,296 @@ mod aux {
                     ) {
                         Ok(len) => len,
                         Err(err) => {
-                            errors.push(err);
+                            report.push(invalid_backing(err));
                             continue;
                         }
                     };
                     if requested_len != backing_len {
-                        errors.push(format!(
+                        report.push(invalid_backing(format!(
                             "{fn_name} requires a fixed-size backing array whose requested length {requested_len} does not match backing array length {backing_len}"
-                        ));
+                        )));
                     }
                     if fn_name == "__quantum__rt__result_array_record_output"
                         && requested_len > i32::MAX as u64
                     {
-                        errors.push(format!(
-                            "{fn_name} requires an array length that fits in i32 for RESULT_ARRAY output"
-                        ));
+                        report.push(
+                            Diagnostic::error(
+                                codes::RESULT_ARRAY_TOO_LONG,
+                                format!(
+                                    "{fn_name} requires an array length that fits in i32 for RESULT_ARRAY output"
+                                ),
+                            )
+                            .at_instruction(instr),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check that the controls of every `__ctl` and `__ctladj` gate call are a
+    /// fixed-size qubit array, which together with the target fits in the
+    /// entry function's `required_num_qubits`.
+    pub fn validate_controlled_gate_controls(
+        module: &Module,
+        entry_fn: FunctionValue,
+        report: &mut ValidationReport,
+    ) {
+        let required_num_qubits = get_required_num_qubits(entry_fn);
+        for fun in module.get_functions() {
+            for bb in fun.get_basic_blocks() {
+                for instr in bb.get_instructions() {
+                    let Ok(call) = CallSiteValue::try_from(instr) else {
+                        continue;
+                    };
+                    let Some(callee) = call.get_called_fn_value() else {
+                        continue;
+                    };
+                    let callee_global = callee.as_global_value();
+                    let callee_name = callee_global.get_name();
+                    let Some(fn_name) = callee_name.to_str().ok() else {
+                        continue;
+                    };
+                    if parse_controlled_gate(fn_name).is_err() {
+                        continue;
+                    }
+
+                    let controls = instr
+                        .get_operand(0)
+                        .and_then(inkwell::values::Operand::value)
+                        .and_then(|controls| PointerValue::try_from(controls).ok());
+                    let num_controls = match controls {
+                        Some(controls) => match get_runtime_array(controls) {
+                            Some(create) => match get_runtime_array_len(create) {
+                                Ok(len) => Ok(len),
+                                // Reported by `validate_runtime_control_arrays`.
+                                Err(_) => continue,
+                            },
+                            None => get_fixed_pointer_array_len(controls, fn_name),
+                        },
+                        None => Err(format!("{fn_name} requires an array of control qubits")),
+                    };
+                    let num_controls = match num_controls {
+                        Ok(len) => len,
+                        Err(err) => {
+                            report.push(
+                                Diagnostic::error(codes::INVALID_ARRAY_BACKING, err)
+                                    .at_instruction(instr),
+                            );
+                            continue;
+                        }
+                    };
+                    // The gate acts on its controls and target
+                    if let Some(max_qubits) = required_num_qubits
+                        && num_controls >= u64::from(max_qubits)
+                    {
+                        report.push(
+                            Diagnostic::error(
+                                codes::MULTI_CONTROLLED_ARITY_OVERFLOW,
+                                format!(
+                                    "{fn_name} with {num_controls} controls exceeds module's required_num_qubits ({max_qubits})"
+                                ),
+                            )
+                            .at_instruction(instr),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The name of the function called by `instr`, if it is a direct call.
+    fn called_fn_name(instr: InstructionValue<'_>) -> Option<String> {
+        let callee = CallSiteValue::try_from(instr).ok()?.get_called_fn_value()?;
+        Some(callee.get_name().to_str().ok()?.to_owned())
+    }
+
+    /// The instructions using the result of `instr`, once per use, or `None`
+    /// for a user that is not an instruction.
+    pub fn instruction_users(instr: InstructionValue<'_>) -> Vec<Option<InstructionValue<'_>>> {
+        std::iter::successors(instr.get_first_use(), |use_| use_.get_next_use())
+            .map(|use_| {
+                let user = use_.get_user();
+                if let AnyValueEnum::InstructionValue(user) = user {
+                    Some(user)
+                } else {
+                    BasicValueEnum::try_from(user)
+                        .ok()
+                        .and_then(|user| user.as_instruction_value())
+                }
+            })
+            .collect()
+    }
+
+    /// Whether operand `idx` of `instr` is the result of `value`.
+    fn is_operand(instr: InstructionValue<'_>, idx: u32, value: InstructionValue<'_>) -> bool {
+        instr
+            .get_operand(idx)
+            .and_then(inkwell::values::Operand::value)
+            .and_then(|operand| operand.as_instruction_value())
+            == Some(value)
+    }
+
+    /// The constant integer operand `idx` of `instr`.
+    fn constant_operand(instr: InstructionValue<'_>, idx: u32) -> Option<u64> {
+        inkwell::values::IntValue::try_from(instr.get_operand(idx)?.value()?)
+            .ok()?
+            .get_zero_extended_constant()
+    }
+
+    /// The `__quantum__rt__array_create_1d` call creating `array`, if any.
+    pub fn get_runtime_array(array: PointerValue<'_>) -> Option<InstructionValue<'_>> {
+        let create = array.as_instruction_value()?;
+        (called_fn_name(create)? == "__quantum__rt__array_create_1d").then_some(create)
+    }
+
+    /// The length of the Q# runtime array created by the
+    /// `__quantum__rt__array_create_1d` call `create`, which is lowered to a
+    /// `[N x ptr]` stack array.
+    ///
+    /// The array must have a constant number of qubit pointers, only be
+    /// indexed at constant indices and only be passed as the controls of
+    /// controlled gates, besides updating its reference and alias counts.
+    pub fn get_runtime_array_len(create: InstructionValue<'_>) -> Result<u64, String> {
+        let error = |reason: String| {
+            format!(
+                "__quantum__rt__array_create_1d arrays are only supported as the controls of controlled gates: {reason}"
+            )
+        };
+        if constant_operand(create, 0) != Some(8) {
+            return Err(error(
+                "the elements must be 8-byte qubit pointers".to_owned(),
+            ));
+        }
+        let len = constant_operand(create, 1)
+            .ok_or_else(|| error("the length must be a constant".to_owned()))?;
+        for user in instruction_users(create) {
+            let Some(user) = user else {
+                return Err(error("the array is used outside an instruction".to_owned()));
+            };
+            let name = called_fn_name(user).unwrap_or_default();
+            let supported = match name.as_str() {
+                "__quantum__rt__array_get_element_ptr_1d" => {
+                    is_operand(user, 0, create)
+                        && constant_operand(user, 1).is_some_and(|idx| idx < len)
+                }
+                "__quantum__rt__array_update_reference_count"
+                | "__quantum__rt__array_update_alias_count" => true,
+                name if parse_controlled_gate(name).is_ok() => {
+                    is_operand(user, 0, create)
+                        && (1..user.get_num_operands()).all(|idx| !is_operand(user, idx, create))
+                }
+                _ => false,
+            };
+            if !supported {
+                return Err(error(format!(
+                    "unsupported use `{}`",
+                    user.print_to_string().to_string().trim()
+                )));
+            }
+        }
+        Ok(len)
+    }
+
+    /// The size of the Q# runtime tuple created by the
+    /// `__quantum__rt__tuple_create` call `create`, which is lowered to a
+    /// stack allocation.
+    ///
+    /// The tuple must have a constant size and only be written, read and
+    /// passed as the `{ double, %Qubit* }` arguments of controlled rotations,
+    /// besides updating its reference and alias counts.
+    pub fn get_runtime_tuple_size(create: InstructionValue<'_>) -> Result<u64, String> {
+        let error = |reason: String| {
+            format!(
+                "__quantum__rt__tuple_create tuples are only supported as the arguments of controlled rotations: {reason}"
+            )
+        };
+        let size = constant_operand(create, 0)
+            .ok_or_else(|| error("the size must be a constant".to_owned()))?;
+        let mut pointers = vec![create];
+        while let Some(pointer) = pointers.pop() {
+            for user in instruction_users(pointer) {
+                let Some(user) = user else {
+                    return Err(error("the tuple is used outside an instruction".to_owned()));
+                };
+                let opcode = user.get_opcode();
+                let supported = if matches!(
+                    opcode,
+                    InstructionOpcode::BitCast | InstructionOpcode::GetElementPtr
+                ) {
+                    pointers.push(user);
+                    true
+                } else if opcode == InstructionOpcode::Load {
+                    true
+                } else if opcode == InstructionOpcode::Store {
+                    // Stores into the tuple, but not of its address.
+                    !is_operand(user, 0, pointer)
+                } else if opcode == InstructionOpcode::Call {
+                    let name = called_fn_name(user).unwrap_or_default();
+                    match name.as_str() {
+                        "__quantum__rt__tuple_update_reference_count"
+                        | "__quantum__rt__tuple_update_alias_count" => true,
+                        name => {
+                            parse_controlled_gate(name)
+                                .is_ok_and(|(gate, _)| ["rx", "ry", "rz"].contains(&gate))
+                                && user.get_num_operands() == 3
+                                && is_operand(user, 1, pointer)
+                                && !is_operand(user, 0, pointer)
+                        }
+                    }
+                } else {
+                    false
+                };
+                if !supported {
+                    return Err(error(format!(
+                        "unsupported use `{}`",
+                        user.print_to_string().to_string().trim()
+                    )));
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    /// Check that the Q# runtime arrays and tuples of the module only hold
+    /// the controls and arguments of controlled gates, which qir-qis lowers to
+    /// stack arrays.
+    pub fn validate_runtime_control_arrays(module: &Module, report: &mut ValidationReport) {
+        for fun in module.get_functions() {
+            for bb in fun.get_basic_blocks() {
+                for instr in bb.get_instructions() {
+                    let Some(fn_name) = called_fn_name(instr) else {
+                        continue;
+                    };
+                    let result = match fn_name.as_str() {
+                        "__quantum__rt__array_create_1d" => get_runtime_array_len(instr).map(drop),
+                        "__quantum__rt__tuple_create" => get_runtime_tuple_size(instr).map(drop),
+                        // The uses of a created array or tuple are checked with it.
+                        name if QSHARP_CONTROL_RT_FNS.contains(&name) => {
+                            let created_by = if name.starts_with("__quantum__rt__array_") {
+                                "__quantum__rt__array_create_1d"
+                            } else {
+                                "__quantum__rt__tuple_create"
+                            };
+                            let operand = instr
+                                .get_operand(0)
+                                .and_then(inkwell::values::Operand::value)
+                                .and_then(|operand| operand.as_instruction_value());
+                            if operand.and_then(called_fn_name).as_deref() == Some(created_by) {
+                                Ok(())
+                            } else {
+                                Err(format!(
+                                    "{fn_name} is only supported on the result of {created_by}"
+                                ))
+                            }
+                        }
+                        _ => continue,
+                    };
+                    if let Err(err) = result {
+                        report.push(
+                            Diagnostic::error(codes::UNSUPPORTED_RUNTIME_ARRAY, err)
+                                .at_instruction(instr),
+                        );
                     }
                 }
             }
@@ -735,7 +1306,7 @@ mod aux {
     pub fn validate_capability_usage(
         module: &Module,
         flags: CapabilityFlags,
-        errors: &mut Vec<String>,
+        report: &mut ValidationReport,
     ) {
         for fun in module.get_functions() {
             for bb in fun.get_basic_blocks() {
@@ -752,40 +1323,52 @@ mod aux {
                         continue;
                     };
 
-                    match fn_name {
+                    let message = match fn_name {
                         "__quantum__rt__qubit_array_allocate"
                         | "__quantum__rt__qubit_array_release"
                             if !flags.arrays || !flags.dynamic_qubit_management =>
                         {
-                            errors.push(format!(
+                            format!(
                                 "{fn_name} requires both `arrays=true` and `dynamic_qubit_management=true`"
-                            ));
+                            )
                         }
                         "__quantum__rt__result_array_allocate"
                         | "__quantum__rt__result_array_release"
                         | "__quantum__rt__result_array_record_output"
                             if !flags.arrays || !flags.dynamic_result_management =>
                         {
-                            errors.push(format!(
+                            format!(
                                 "{fn_name} requires both `arrays=true` and `dynamic_result_management=true`"
-                            ));
+                            )
+                        }
+                        // Q# runtime arrays of controls are lowered to the stack.
+                        name if parse_controlled_gate(name).is_ok()
+                            && (!flags.arrays || !flags.dynamic_qubit_management)
+                            && instr
+                                .get_operand(0)
+                                .and_then(inkwell::values::Operand::value)
+                                .and_then(|controls| PointerValue::try_from(controls).ok())
+                                .is_none_or(|controls| get_runtime_array(controls).is_none()) =>
+                        {
+                            format!(
+                                "{fn_name} requires both `arrays=true` and `dynamic_qubit_management=true`"
+                            )
                         }
                         "__quantum__rt__qubit_allocate" | "__quantum__rt__qubit_release"
                             if !flags.dynamic_qubit_management =>
                         {
-                            errors.push(format!(
-                                "{fn_name} requires `dynamic_qubit_management=true`"
-                            ));
+                            format!("{fn_name} requires `dynamic_qubit_management=true`")
                         }
                         "__quantum__rt__result_allocate" | "__quantum__rt__result_release"
                             if !flags.dynamic_result_management =>
                         {
-                            errors.push(format!(
-                                "{fn_name} requires `dynamic_result_management=true`"
-                            ));
+                            format!("{fn_name} requires `dynamic_result_management=true`")
                         }
-                        _ => {}
-                    }
+                        _ => continue,
+                    };
+                    report.push(
+                        Diagnostic::error(codes::MISSING_CAPABILITY, message).at_instruction(instr),
+                    );
                 }
             }
         }
@@ -794,7 +1377,7 @@ mod aux {
     pub fn validate_dynamic_result_allocation_placement(
         module: &Module,
         entry_fn: FunctionValue,
-        errors: &mut Vec<String>,
+        report: &mut ValidationReport,
     ) {
         for fun in module.get_functions() {
             let allowed_block = if fun == entry_fn {
@@ -821,9 +1404,15 @@ mod aux {
                         "__quantum__rt__result_allocate" | "__quantum__rt__result_array_allocate"
                     ) && Some(bb) != allowed_block
                     {
-                        errors.push(format!(
-                            "{fn_name} is only supported in the entry block because dynamic result slots are lowered to stack storage"
-                        ));
+                        report.push(
+                            Diagnostic::error(
+                                codes::DYNAMIC_RESULT_OUTSIDE_ENTRY_BLOCK,
+                                format!(
+                                    "{fn_name} is only supported in the entry block because dynamic result slots are lowered to stack storage"
+                                ),
+                            )
+                            .at_instruction(instr),
+                        );
                     }
                 }
             }
@@ -839,9 +1428,6 @@ mod aux {
         module: *const Module<'ctx>,
         instr: inkwell::values::InstructionValue<'ctx>,
         fn_name: String,
-        // Reserved for downstream passthrough compatibility.
-        #[allow(dead_code)]
-        wasm_fns: *const BTreeMap<String, u64>,
         qubit_array: Option<PointerValue<'ctx>>,
         qubit_array_type: Option<ArrayType<'ctx>>,
         capability_flags: CapabilityFlags,
@@ -849,19 +1435,51 @@ mod aux {
         result_ssa: *mut Vec<Option<(BasicValueEnum<'ctx>, Option<BasicValueEnum<'ctx>>)>>,
     }
 
+    /// A lowering error, together with the instruction that was being lowered.
+    pub struct LoweringFailure<'ctx> {
+        pub message: String,
+        pub instr: Option<inkwell::values::InstructionValue<'ctx>>,
+    }
+
+    impl From<String> for LoweringFailure<'_> {
+        fn from(message: String) -> Self {
+            Self {
+                message,
+                instr: None,
+            }
+        }
+    }
+
+    /// A lowering warning, together with the instruction it refers to.
+    ///
+    /// The instruction may have been erased by the time the warning is
+    /// reported; it is only used as a key into the source location map.
+    pub struct LoweringWarning<'ctx> {
+        pub diagnostic: Diagnostic,
+        pub instr: Option<inkwell::values::InstructionValue<'ctx>>,
+    }
+
     /// Primary translation loop over the entry function for translation to QIS.
     pub fn process_entry_function<'ctx>(
         ctx: &'ctx Context,
         module: &Module<'ctx>,
         entry_fn: FunctionValue<'ctx>,
-        wasm_fns: &BTreeMap<String, u64>,
         qubit_array: Option<PointerValue<'ctx>>,
         capability_flags: CapabilityFlags,
-    ) -> Result<(), String> {
+        warnings: &mut Vec<LoweringWarning<'ctx>>,
+    ) -> Result<(), LoweringFailure<'ctx>> {
         let mut global_mapping = convert_globals(ctx, module)?;
 
-        if global_mapping.is_empty() {
-            log::warn!("No globals found in QIR module");
+        // The qubit array adds its own `e_qalloc_fail` message, which is not
+        // one of the program's globals.
+        if global_mapping.keys().all(|name| name == "e_qalloc_fail") {
+            warnings.push(LoweringWarning {
+                diagnostic: Diagnostic::warning(
+                    codes::NO_GLOBALS,
+                    "No globals found in QIR module",
+                ),
+                instr: None,
+            });
         }
         let mut result_ssa = if capability_flags.dynamic_result_management {
             Vec::new()
@@ -892,19 +1510,27 @@ mod aux {
                         .ok()
                         .map(ToOwned::to_owned)
                 }) {
+                    if let Some((code, message)) = lowering_warning(&fn_name) {
+                        warnings.push(LoweringWarning {
+                            diagnostic: Diagnostic::warning(code, message).at_instruction(instr),
+                            instr: Some(instr),
+                        });
+                    }
                     let args = ProcessCallArgs {
                         ctx,
                         module,
                         instr,
                         fn_name,
-                        wasm_fns: std::ptr::from_ref(wasm_fns),
                         qubit_array,
                         qubit_array_type,
                         capability_flags,
                         global_mapping: &raw mut global_mapping,
                         result_ssa: &raw mut result_ssa,
                     };
-                    process_call_instruction(args)?;
+                    process_call_instruction(args).map_err(|message| LoweringFailure {
+                        message,
+                        instr: Some(instr),
+                    })?;
                 }
             }
         }
@@ -912,6 +1538,21 @@ mod aux {
         Ok(())
     }
 
+    /// Warning raised when lowering a call to `fn_name`, if any.
+    fn lowering_warning(fn_name: &str) -> Option<(&'static str, &'static str)> {
+        match fn_name {
+            "__quantum__qis__m__body" => Some((
+                codes::QSHARP_M_SYNONYM,
+                "`__quantum__qis__m__body` is from Q# QDK, synonym for `__quantum__qis__mz__body`",
+            )),
+            "__quantum__qis__mresetz__body" => Some((
+                codes::QSHARP_MRESETZ,
+                "`__quantum__qis__mresetz__body` is from Q# QDK",
+            )),
+            _ => None,
+        }
+    }
+
     fn process_call_instruction(mut args: ProcessCallArgs<'_>) -> Result<(), String> {
         let call = CallSiteValue::try_from(args.instr)
             .map_err(|()| "Instruction is not a call site".to_string())?;
@@ -993,9 +1634,10 @@ mod aux {
                     args.capability_flags.dynamic_qubit_management,
                 )?;
             }
-            "__quantum__qis__u1q__body" => {
+            "__quantum__qis__u1q__body" | "__quantum__qis__phasedx__body" => {
                 log::info!(
-                    "`__quantum__qis__u1q__body` used, synonym for `__quantum__qis__rxy__body`"
+                    "`{}` used, synonym for `__quantum__qis__rxy__body`",
+                    args.fn_name
                 );
                 replace_rxy_call(
                     args.ctx,
@@ -1004,6 +1646,25 @@ mod aux {
                     args.capability_flags.dynamic_qubit_management,
                 )?;
             }
+            "__quantum__qis__zzphase__body" => {
+                log::info!(
+                    "`__quantum__qis__zzphase__body` used, synonym for `__quantum__qis__rzz__body`"
+                );
+                replace_rzz_call(
+                    args.ctx,
+                    module_ref(args),
+                    args.instr,
+                    args.capability_flags.dynamic_qubit_management,
+                )?;
+            }
+            "__quantum__qis__zzmax__body" => {
+                replace_zzmax_call(
+                    args.ctx,
+                    module_ref(args),
+                    args.instr,
+                    args.capability_flags.dynamic_qubit_management,
+                )?;
+            }
             "__quantum__qis__mz__body"
             | "__quantum__qis__m__body"
             | "__quantum__qis__mresetz__body" => {
@@ -2538,11 +3199,6 @@ mod aux {
         result_ssa: *mut (),
     ) -> Result<(), String> {
         let module = unsafe { &*module.cast::<Module<'ctx>>() };
-        if fn_name == "__quantum__qis__m__body" {
-            log::warn!(
-                "`__quantum__qis__m__body` is from Q# QDK, synonym for `__quantum__qis__mz__body`"
-            );
-        }
         let builder = ctx.create_builder();
         builder.position_before(instr);
 
@@ -2596,7 +3252,6 @@ mod aux {
         }
 
         if fn_name == "__quantum__qis__mresetz__body" {
-            log::warn!("`__quantum__qis__mresetz__body` is from Q# QDK");
             // Create ___reset call
             create_reset_call(ctx, module, &builder, q_handle);
         }
@@ -2750,6 +3405,35 @@ mod aux {
             .ok_or_else(|| format!("Invalid barrier function name: {fn_name}"))
     }
 
+    /// Number of qubits of a `__quantum__qis__mcx<n>__body` or
+    /// `__quantum__qis__mcz<n>__body` gate: n-1 controls and a target.
+    pub fn parse_multi_controlled_arity(fn_name: &str) -> Result<usize, String> {
+        fn_name
+            .strip_prefix("__quantum__qis__mcx")
+            .or_else(|| fn_name.strip_prefix("__quantum__qis__mcz"))
+            .and_then(|s| s.strip_suffix("__body"))
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("Invalid multi-controlled gate name: {fn_name}"))
+    }
+
+    /// Gates of the de facto gate set with `__ctl` and `__ctladj` variants.
+    const CONTROLLED_GATES: [&str; 9] = ["h", "x", "y", "z", "s", "t", "rx", "ry", "rz"];
+
+    /// Gate of a `__quantum__qis__<gate>__ctl` or `__quantum__qis__<gate>__ctladj`
+    /// function, and whether it is the adjoint variant.
+    pub fn parse_controlled_gate(fn_name: &str) -> Result<(&str, bool), String> {
+        fn_name
+            .strip_prefix("__quantum__qis__")
+            .and_then(|name| {
+                name.strip_suffix("__ctl")
+                    .map(|gate| (gate, false))
+                    .or_else(|| name.strip_suffix("__ctladj").map(|gate| (gate, true)))
+            })
+            .filter(|(gate, _)| CONTROLLED_GATES.contains(gate))
+            .ok_or_else(|| format!("Invalid controlled gate name: {fn_name}"))
+    }
+
     #[allow(clippy::too_many_lines)]
     fn handle_barrier_call(args: &ProcessCallArgs<'_>) -> Result<(), String> {
         let ProcessCallArgs {
@@ -3196,14 +3880,42 @@ fn create_memory_buffer_from_bytes(
     unsafe { Ok(inkwell::memory_buffer::MemoryBuffer::new(memory_buffer)) }
 }
 
-pub(crate) fn create_module_from_ir_text<'ctx>(
+/// Like [`create_memory_buffer_from_bytes`], without copying `bytes`: the
+/// buffer borrows them for its lifetime.
+fn borrow_memory_buffer_from_bytes<'a>(
+    bytes: &'a [u8],
+    name: &str,
+) -> Result<inkwell::memory_buffer::MemoryBuffer<'a>, String> {
+    use llvm_sys::core::LLVMCreateMemoryBufferWithMemoryRange;
+
+    let name = std::ffi::CString::new(name)
+        .map_err(|_| "Memory buffer name contains interior NUL byte".to_string())?;
+    let memory_buffer = unsafe {
+        LLVMCreateMemoryBufferWithMemoryRange(bytes.as_ptr().cast(), bytes.len(), name.as_ptr(), 0)
+    };
+    if memory_buffer.is_null() {
+        return Err(
+            "LLVM failed to create memory buffer from bytes: received null memory buffer pointer"
+                .to_string(),
+        );
+    }
+
+    unsafe { Ok(inkwell::memory_buffer::MemoryBuffer::new(memory_buffer)) }
+}
+
+/// Parse QIR LLVM IR text into a module of `ctx` named `name`.
+///
+/// # Errors
+/// Returns [`Error::Parse`] if the text cannot be parsed.
+pub fn create_module_from_ir_text<'ctx>(
     ctx: &'ctx inkwell::context::Context,
     ll_text: &str,
     name: &str,
-) -> Result<inkwell::module::Module<'ctx>, String> {
-    let memory_buffer = create_memory_buffer_from_bytes(ll_text.as_bytes(), name)?;
+) -> Result<inkwell::module::Module<'ctx>, Error> {
+    let memory_buffer =
+        create_memory_buffer_from_bytes(ll_text.as_bytes(), name).map_err(Error::Parse)?;
     ctx.create_module_from_ir(memory_buffer)
-        .map_err(|e| format!("Failed to create module from LLVM IR: {e}"))
+        .map_err(|e| Error::Parse(format!("Failed to create module from LLVM IR: {e}")))
 }
 
 fn memory_buffer_to_owned_bytes(
@@ -3217,149 +3929,207 @@ fn memory_buffer_to_owned_bytes(
     }
 }
 
-pub(crate) fn parse_bitcode_module<'ctx>(
+/// Parse QIR bitcode into a module of `ctx` named `name`.
+///
+/// # Errors
+/// Returns [`Error::Parse`] if the bitcode cannot be parsed.
+pub fn parse_bitcode_module<'ctx>(
     ctx: &'ctx inkwell::context::Context,
     bitcode: &[u8],
     name: &str,
-) -> Result<inkwell::module::Module<'ctx>, String> {
-    let memory_buffer = create_memory_buffer_from_bytes(bitcode, name)?;
+) -> Result<inkwell::module::Module<'ctx>, Error> {
+    let memory_buffer = create_memory_buffer_from_bytes(bitcode, name).map_err(Error::Parse)?;
     inkwell::module::Module::parse_bitcode_from_buffer(&memory_buffer, ctx)
-        .map_err(|e| format!("Failed to parse bitcode: {e}"))
+        .map_err(|e| Error::Parse(format!("Failed to parse bitcode: {e}")))
 }
 
-/// Core QIR to QIS translation logic.
-///
-/// # Arguments
-/// - `bc_bytes` - The QIR bytes to translate.
-/// - `opt_level` - The optimization level to use (0-3). Platform defaults are
+/// Like [`parse_bitcode_module`], reading `bitcode` in place instead of
+/// copying it into LLVM first.
+pub(crate) fn parse_borrowed_bitcode_module<'ctx>(
+    ctx: &'ctx inkwell::context::Context,
+    bitcode: &[u8],
+    name: &str,
+) -> Result<inkwell::module::Module<'ctx>, Error> {
+    let memory_buffer = borrow_memory_buffer_from_bytes(bitcode, name).map_err(Error::Parse)?;
+    inkwell::module::Module::parse_bitcode_from_buffer(&memory_buffer, ctx)
+        .map_err(|e| Error::Parse(format!("Failed to parse bitcode: {e}")))
+}
+
+/// Core QIR to QIS translation logic.
+///
+/// # Arguments
+/// - `bc_bytes` - The QIR bytes to translate.
+/// - `opt_level` - The optimization level to use (0-3). Platform defaults are
 ///   exposed via [`DEFAULT_OPT_LEVEL`].
 /// - `target` - Target architecture ("aarch64", "x86-64", "native"). Platform
 ///   defaults are exposed via [`DEFAULT_TARGET`].
 /// - `wasm_bytes` - Optional WASM bytes for Wasm codegen.
 ///
 /// # Errors
-/// Returns an error string if the translation fails.
+/// Returns [`Error::InvalidOption`] for an invalid `opt_level` or `target`,
+/// or an [`Error`] describing the stage at which the translation failed.
+#[deprecated(note = "build a `CompileOptions` and call `CompileOptions::compile` instead")]
 pub fn qir_to_qis(
     bc_bytes: &[u8],
     opt_level: u32,
     target: &str,
-    _wasm_bytes: Option<&[u8]>,
-) -> Result<Vec<u8>, String> {
-    use crate::{
-        aux::{get_capability_flags, process_entry_function},
-        convert::{
-            add_qmain_wrapper, create_qubit_array, find_entry_function, free_all_qubits,
-            get_string_attrs, process_ir_defined_q_fns, prune_unused_ir_qis_helpers,
-        },
-        decompose::add_decompositions,
-        opt::optimize,
-        utils::add_generator_metadata,
-    };
-    use inkwell::{attributes::AttributeLoc, context::Context};
-    use std::{collections::BTreeMap, env};
-
-    let ctx = Context::create();
-    let module = parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
-    crate::llvm_verify::verify_module(&module, "LLVM module verification failed after parse")?;
-
-    add_decompositions(&ctx, &module)
-        .map_err(|e| format!("Failed to add QIR decompositions: {e}"))?;
-    let entry_fn = find_entry_function(&module)
-        .map_err(|e| format!("Failed to find entry function in QIR module: {e}"))?;
-
-    let entry_fn_name = entry_fn
-        .get_name()
-        .to_str()
-        .map_err(|e| format!("Invalid UTF-8 in entry function name: {e}"))?;
-    let capability_flags = get_capability_flags(&module);
-
-    log::trace!("Entry function: {entry_fn_name}");
-    let new_name = format!("___user_qir_{entry_fn_name}");
-    entry_fn.as_global_value().set_name(&new_name);
-    log::debug!("Renamed entry function to: {new_name}");
-    let qubit_array = if capability_flags.dynamic_qubit_management {
-        None
-    } else {
-        Some(create_qubit_array(&ctx, &module, entry_fn)?)
+    wasm_bytes: Option<&[u8]>,
+) -> Result<Vec<u8>, Error> {
+    let options = CompileOptions::new()
+        .with_opt_level(OptLevel::try_from(opt_level).map_err(Error::InvalidOption)?)
+        .with_target(target.parse::<Target>().map_err(Error::InvalidOption)?);
+    let options = match wasm_bytes {
+        Some(wasm_bytes) => options.with_wasm(wasm_bytes),
+        None => options,
     };
+    options.compile(bc_bytes).map(|output| output.artifact)
+}
 
-    let wasm_fns: BTreeMap<String, u64> = BTreeMap::new();
-    process_entry_function(
-        &ctx,
-        &module,
-        entry_fn,
-        &wasm_fns,
-        qubit_array,
-        capability_flags,
-    )?;
-
-    // Handle IR defined functions that take qubits
-    process_ir_defined_q_fns(
-        &ctx,
-        &module,
-        entry_fn,
-        capability_flags.dynamic_qubit_management,
-    )?;
-
-    if let Some(qubit_array) = qubit_array {
-        free_all_qubits(&ctx, &module, entry_fn, qubit_array)?;
-    }
-
-    // Add qmain wrapper that calls setup, entry function, and teardown
-    let _ = add_qmain_wrapper(&ctx, &module, entry_fn);
-
-    crate::llvm_verify::verify_module(&module, "LLVM module verification failed")?;
-
-    // Clean up the translated module
-    for attr in get_string_attrs(entry_fn) {
-        let kind = decode_string_attribute_kind(attr)?;
-        entry_fn.remove_string_attribute(AttributeLoc::Function, &kind);
-    }
+/// Result of a successful QIR to QIS translation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOutput {
+    /// The translated program, in the requested [`Emit`] format.
+    pub artifact: Vec<u8>,
+    /// Warnings raised while lowering the program.
+    pub warnings: Vec<Diagnostic>,
+    /// IR recorded after the stages marked with [`Pipeline::print_after`].
+    pub dumps: Vec<StageDump>,
+}
 
-    // TODO: remove global module metadata
-    // seems inkwell doesn't support this yet
+/// A QIR module lowered to QIS in place by [`CompileOptions::lower_module`].
+#[derive(Debug)]
+pub struct LoweredModule<'a, 'ctx> {
+    /// The lowered module, which [`opt::emit`] serializes.
+    pub module: &'a inkwell::module::Module<'ctx>,
+    /// Warnings raised while lowering the program.
+    pub warnings: Vec<Diagnostic>,
+    /// IR recorded after the stages marked with [`Pipeline::print_after`].
+    pub dumps: Vec<StageDump>,
+}
 
-    // Add metadata to the module
-    let md_string = ctx.metadata_string("mainlib");
-    let md_node = ctx.metadata_node(&[md_string.into()]);
-    module
-        .add_global_metadata("name", &md_node)
-        .map_err(|e| format!("Failed to add global metadata: {e}"))?;
-    add_generator_metadata(&ctx, &module, "gen_name", env!("CARGO_PKG_NAME"))?;
-    add_generator_metadata(&ctx, &module, "gen_version", env!("CARGO_PKG_VERSION"))?;
+/// Translate many QIR bitcode programs concurrently on `jobs` threads.
+///
+/// Every program is translated as by [`CompileOptions::compile`], in an LLVM
+/// context of its own, so the translations share no LLVM state; the global
+/// LLVM target registry is initialized once, before its first use. `jobs`
+/// defaults to the available parallelism. The results are in the order of
+/// `programs`.
+///
+/// All the translation functions of this crate may likewise be called from
+/// several threads at once.
+#[must_use]
+pub fn compile_many<B: AsRef<[u8]> + Sync>(
+    programs: &[B],
+    options: &CompileOptions,
+    jobs: Option<std::num::NonZeroUsize>,
+) -> Vec<Result<CompileOutput, Error>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let jobs = jobs
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, std::num::NonZeroUsize::get)
+        .min(programs.len());
+    let next = AtomicUsize::new(0);
+    let mut results = Vec::with_capacity(programs.len());
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(program) = programs.get(index) else {
+                            break;
+                        };
+                        done.push((index, options.compile(program.as_ref())));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for worker in workers {
+            match worker.join() {
+                Ok(done) => results.extend(done),
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
 
-    optimize(&module, opt_level, target)?;
-    prune_unused_ir_qis_helpers(&module);
+/// Translate `module` with `options` in a one-off session.
+fn compile_module<'ctx>(
+    ctx: &'ctx inkwell::context::Context,
+    module: &inkwell::module::Module<'ctx>,
+    pipeline: &Pipeline,
+    options: &CompileOptions,
+    locations: &HashMap<inkwell::values::InstructionValue<'ctx>, SourceLocation>,
+) -> Result<CompileOutput, Error> {
+    let mut cx = PassContext::new(ctx, module, options.opt_level(), options.target());
+    cx.wasm_bytes = options.wasm();
+    cx.deny_warnings = options.deny_warnings();
+    cx.locations = Some(locations);
+    cx.registry = Some(options.decompositions());
+    run_pipeline(cx, pipeline, options.emit())
+}
 
-    Ok(memory_buffer_to_owned_bytes(
-        &module.write_bitcode_to_memory(),
-    ))
+/// Run `pipeline` over the module of `cx`, then emit it.
+fn run_pipeline(
+    mut cx: PassContext<'_, '_>,
+    pipeline: &Pipeline,
+    emit: Emit,
+) -> Result<CompileOutput, Error> {
+    pipeline.run(&mut cx)?;
+
+    Ok(CompileOutput {
+        artifact: crate::opt::emit_with(
+            cx.module,
+            emit,
+            cx.opt_level,
+            cx.target,
+            cx.target_machine,
+        )?,
+        warnings: cx.warnings,
+        dumps: cx.dumps,
+    })
 }
 
 /// Extract WASM function mapping from the given WASM bytes.
 ///
 /// # Errors
-/// Returns an error string if parsing fails.
+/// Returns [`Error::Wasm`] if parsing fails.
 #[cfg(feature = "wasm")]
 pub fn get_wasm_functions(
     wasm_bytes: Option<&[u8]>,
-) -> Result<std::collections::BTreeMap<String, u64>, String> {
+) -> Result<std::collections::BTreeMap<String, u64>, Error> {
     use crate::utils::parse_wasm_functions;
     use std::collections::BTreeMap;
 
     let mut wasm_fns: BTreeMap<String, u64> = BTreeMap::new();
     if let Some(bytes) = wasm_bytes {
-        wasm_fns = parse_wasm_functions(bytes)?;
+        wasm_fns = parse_wasm_functions(bytes).map_err(Error::Wasm)?;
         log::debug!("WASM function map: {wasm_fns:?}");
     }
     Ok(wasm_fns)
 }
 
+/// Parse the export signatures of the WASM module, if one is supplied.
+#[cfg(feature = "wasm")]
+fn get_wasm_signatures(
+    wasm_bytes: Option<&[u8]>,
+) -> Result<Option<std::collections::BTreeMap<String, utils::WasmSignature>>, Error> {
+    wasm_bytes
+        .map(|bytes| utils::parse_wasm_signatures(bytes).map_err(Error::Wasm))
+        .transpose()
+}
+
+/// Without the `wasm` feature, WASM modules are not inspected.
 #[cfg(not(feature = "wasm"))]
-fn get_wasm_functions(
+const fn get_wasm_signatures(
     _wasm_bytes: Option<&[u8]>,
-) -> Result<std::collections::BTreeMap<String, u64>, String> {
-    Ok(std::collections::BTreeMap::new())
+) -> Result<Option<std::collections::BTreeMap<String, utils::WasmSignature>>, Error> {
+    Ok(None)
 }
 
 /// Validate the given QIR bitcode.
@@ -3369,28 +4139,129 @@ fn get_wasm_functions(
 /// - `wasm_bytes` - Optional WASM bytes to validate against.
 ///
 /// # Errors
-/// Returns an error string if validation fails.
-pub fn validate_qir(bc_bytes: &[u8], wasm_bytes: Option<&[u8]>) -> Result<(), String> {
+/// Returns [`Error::Validation`] listing every violation found, or
+/// [`Error::Parse`] / [`Error::Wasm`] if the inputs cannot be parsed.
+pub fn validate_qir(bc_bytes: &[u8], wasm_bytes: Option<&[u8]>) -> Result<(), Error> {
+    let report = validate_qir_report(bc_bytes, wasm_bytes)?;
+    if report.has_errors() {
+        return Err(Error::Validation(report));
+    }
+    log::info!("QIR validation passed");
+    Ok(())
+}
+
+/// Validate the given QIR bitcode and return every diagnostic found.
+///
+/// Unlike [`validate_qir`], an invalid program is not an error here: the
+/// returned [`ValidationReport`] lists each problem with a stable code, a
+/// severity and the offending function, block and instruction.
+///
+/// # Arguments
+/// - `bc_bytes` - The QIR bytes to validate.
+/// - `wasm_bytes` - Optional WASM bytes to validate against.
+///
+/// # Errors
+/// Returns [`Error::Parse`] / [`Error::Wasm`] if the inputs cannot be parsed.
+pub fn validate_qir_report(
+    bc_bytes: &[u8],
+    wasm_bytes: Option<&[u8]>,
+) -> Result<ValidationReport, Error> {
+    let ctx = inkwell::context::Context::create();
+    let module = parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
+    validate_qir_module(&module, wasm_bytes)
+}
+
+/// Validate QIR LLVM IR text and return every diagnostic found.
+///
+/// Behaves like [`validate_qir_report`], additionally filling in
+/// [`Diagnostic::location`] with the `source_name:line:column` of the
+/// offending instruction or function in `ll_text`.
+///
+/// # Arguments
+/// - `ll_text` - The QIR LLVM IR text to validate.
+/// - `source_name` - File name used when reporting source positions.
+/// - `wasm_bytes` - Optional WASM bytes to validate against.
+///
+/// # Errors
+/// Returns [`Error::Parse`] / [`Error::Wasm`] if the inputs cannot be parsed.
+pub fn validate_qir_ll_report(
+    ll_text: &str,
+    source_name: &str,
+    wasm_bytes: Option<&[u8]>,
+) -> Result<ValidationReport, Error> {
+    let ctx = inkwell::context::Context::create();
+    let module = create_module_from_ir_text(&ctx, ll_text, source_name)?;
+    validate_qir_ll_module(&module, ll_text, source_name, wasm_bytes)
+}
+
+/// Validate a QIR module parsed from `ll_text` in place, as
+/// [`validate_qir_module`], filling in [`Diagnostic::location`] as
+/// [`validate_qir_ll_report`] does.
+///
+/// # Errors
+/// Returns [`Error::Wasm`] if `wasm_bytes` cannot be parsed.
+pub fn validate_qir_ll_module(
+    module: &inkwell::module::Module<'_>,
+    ll_text: &str,
+    source_name: &str,
+    wasm_bytes: Option<&[u8]>,
+) -> Result<ValidationReport, Error> {
+    let mut report = validate_qir_module(module, wasm_bytes)?;
+    report.resolve_locations(&SourceMap::new(source_name, ll_text));
+    Ok(report)
+}
+
+/// Validate a QIR module in place and return every diagnostic found.
+///
+/// Behaves like [`validate_qir_report`] on a module a frontend already
+/// holds, without serializing and re-parsing it. Gates registered in a
+/// [`DecompositionRegistry`] are accepted by
+/// [`CompileOptions::validate_module`] instead.
+///
+/// # Errors
+/// Returns [`Error::Wasm`] if `wasm_bytes` cannot be parsed.
+pub fn validate_qir_module(
+    module: &inkwell::module::Module<'_>,
+    wasm_bytes: Option<&[u8]>,
+) -> Result<ValidationReport, Error> {
+    Ok(validate_module(
+        module,
+        get_wasm_signatures(wasm_bytes)?.as_ref(),
+        None,
+    ))
+}
+
+fn validate_module(
+    module: &inkwell::module::Module<'_>,
+    wasm_signatures: Option<&std::collections::BTreeMap<String, utils::WasmSignature>>,
+    registry: Option<&DecompositionRegistry>,
+) -> ValidationReport {
     use crate::{
         aux::{
-            get_capability_flags, validate_capability_usage,
+            get_capability_flags, validate_capability_usage, validate_controlled_gate_controls,
             validate_dynamic_array_allocation_backing,
             validate_dynamic_result_allocation_placement, validate_functions,
             validate_module_flags, validate_module_layout_and_triple, validate_result_slot_usage,
+            validate_runtime_control_arrays,
         },
         convert::{ENTRY_ATTRIBUTE_KEYS, find_entry_function},
+        diagnostics::codes,
     };
-    use inkwell::{attributes::AttributeLoc, context::Context};
+    use inkwell::attributes::AttributeLoc;
 
-    let ctx = Context::create();
-    let module = parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
-    let mut errors = Vec::new();
+    let mut report = ValidationReport::default();
 
-    let capability_flags = get_capability_flags(&module);
-    validate_module_layout_and_triple(&module);
-    let entry_fn = if let Ok(entry_fn) = find_entry_function(&module) {
+    let capability_flags = get_capability_flags(module);
+    validate_module_layout_and_triple(module, &mut report);
+    let entry_fn = if let Ok(entry_fn) = find_entry_function(module) {
         if entry_fn.get_basic_blocks().is_empty() {
-            errors.push("Entry function has no basic blocks".to_string());
+            report.push(
+                Diagnostic::error(
+                    codes::EMPTY_ENTRY_POINT,
+                    "Entry function has no basic blocks",
+                )
+                .in_function(entry_fn),
+            );
         }
 
         // Enforce required attributes
@@ -3401,7 +4272,13 @@ pub fn validate_qir(bc_bytes: &[u8], wasm_bytes: Option<&[u8]>) -> Result<(), St
         }) {
             let val = entry_fn.get_string_attribute(AttributeLoc::Function, attr);
             if val.is_none() {
-                errors.push(format!("Missing required attribute: `{attr}`"));
+                report.push(
+                    Diagnostic::error(
+                        codes::MISSING_ENTRY_ATTRIBUTE,
+                        format!("Missing required attribute: `{attr}`"),
+                    )
+                    .in_function(entry_fn),
+                );
             }
         }
 
@@ -3421,37 +4298,49 @@ pub fn validate_qir(bc_bytes: &[u8], wasm_bytes: Option<&[u8]>) -> Result<(), St
                 })
                 == Some(0)
             {
-                errors.push(format!("Entry function must have at least one {type_}"));
+                report.push(
+                    Diagnostic::error(
+                        codes::ZERO_REQUIRED_QUBITS,
+                        format!("Entry function must have at least one {type_}"),
+                    )
+                    .in_function(entry_fn),
+                );
             }
         }
         entry_fn
     } else {
-        errors.push("No entry function found in QIR module".to_string());
-        return Err(errors.join("; "));
+        report.push(Diagnostic::error(
+            codes::MISSING_ENTRY_POINT,
+            "No entry function found in QIR module",
+        ));
+        return report;
     };
 
-    let wasm_fns = get_wasm_functions(wasm_bytes)?;
+    validate_functions(module, entry_fn, wasm_signatures, registry, &mut report);
+    validate_result_slot_usage(module, entry_fn, &mut report);
+    validate_dynamic_result_allocation_placement(module, entry_fn, &mut report);
+    validate_dynamic_array_allocation_backing(module, &mut report);
+    validate_controlled_gate_controls(module, entry_fn, &mut report);
+    validate_runtime_control_arrays(module, &mut report);
 
-    validate_functions(&module, entry_fn, &wasm_fns, &mut errors);
-    validate_result_slot_usage(&module, entry_fn, &mut errors);
-    validate_dynamic_result_allocation_placement(&module, entry_fn, &mut errors);
-    validate_dynamic_array_allocation_backing(&module, &mut errors);
+    validate_module_flags(module, &mut report);
+    validate_capability_usage(module, capability_flags, &mut report);
 
-    validate_module_flags(&module, &mut errors);
-    validate_capability_usage(&module, capability_flags, &mut errors);
+    report
+}
 
-    if !errors.is_empty() {
-        return Err(errors.join("; "));
-    }
-    log::info!("QIR validation passed");
-    Ok(())
+/// Returns `true` if `bytes` start with the LLVM bitcode magic, either raw
+/// (`BC 0xC0DE`) or inside a bitcode wrapper header (`0x0B17C0DE`).
+#[must_use]
+pub fn is_bitcode(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"BC\xC0\xDE") || bytes.starts_with(&[0xDE, 0xC0, 0x17, 0x0B])
 }
 
 /// Convert QIR LLVM IR text to QIR bitcode bytes.
 ///
 /// # Errors
-/// Returns an error string if the LLVM IR is invalid.
-pub fn qir_ll_to_bc(ll_text: &str) -> Result<Vec<u8>, String> {
+/// Returns [`Error::Parse`] if the LLVM IR is invalid.
+pub fn qir_ll_to_bc(ll_text: &str) -> Result<Vec<u8>, Error> {
     use inkwell::context::Context;
 
     let ctx = Context::create();
@@ -3511,19 +4400,26 @@ fn decode_string_attribute_value(
 /// This function assumes that QIR has been validated using `validate_qir`.
 ///
 /// # Errors
-/// Returns an error string if the input bitcode is invalid.
+/// Returns [`Error::Parse`] if the input bitcode is invalid.
 pub fn get_entry_attributes(
     bc_bytes: &[u8],
-) -> Result<std::collections::BTreeMap<String, Option<String>>, String> {
+) -> Result<std::collections::BTreeMap<String, Option<String>>, Error> {
+    let ctx = inkwell::context::Context::create();
+    let module = parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
+    Ok(get_module_entry_attributes(&module))
+}
+
+/// Get the entry point function attributes of a QIR module, as
+/// [`get_entry_attributes`] does for bitcode.
+#[must_use]
+pub fn get_module_entry_attributes(
+    module: &inkwell::module::Module<'_>,
+) -> std::collections::BTreeMap<String, Option<String>> {
     use crate::convert::{find_entry_function, get_string_attrs};
-    use inkwell::context::Context;
     use std::collections::BTreeMap;
 
-    let ctx = Context::create();
-    let module = parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
-
     let mut metadata = BTreeMap::new();
-    if let Ok(entry_fn) = find_entry_function(&module) {
+    if let Ok(entry_fn) = find_entry_function(module) {
         for attr in get_string_attrs(entry_fn) {
             let kind_id = match decode_string_attribute_kind(attr) {
                 Ok(kind_id) => kind_id,
@@ -3543,26 +4439,94 @@ pub fn get_entry_attributes(
             }
         }
     }
-    Ok(metadata)
+    metadata
+}
+
+/// Get the capabilities enabled by the QIR module flags.
+///
+/// Flags that are absent or malformed are reported as disabled; use
+/// `validate_qir` to reject such modules.
+///
+/// # Errors
+/// Returns [`Error::Parse`] if the input bitcode is invalid.
+pub fn get_capability_flags(bc_bytes: &[u8]) -> Result<CapabilityFlags, Error> {
+    let ctx = inkwell::context::Context::create();
+    let module = parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
+    Ok(get_module_capability_flags(&module))
+}
+
+/// Get the capabilities enabled by the flags of a QIR module, as
+/// [`get_capability_flags`] does for bitcode.
+#[must_use]
+pub fn get_module_capability_flags(module: &inkwell::module::Module<'_>) -> CapabilityFlags {
+    aux::get_capability_flags(module)
 }
 
 #[cfg(feature = "python")]
 mod exceptions {
-    use pyo3::exceptions::PyException;
+    use std::ffi::CString;
+
+    use pyo3::exceptions::{PyException, PyUserWarning};
+    use pyo3::prelude::*;
     use pyo3_stub_gen::create_exception;
 
     create_exception!(
         qir_qis,
         ValidationError,
         PyException,
-        "QIR ValidationError.\n\nRaised when the QIR is invalid."
+        "QIR ValidationError.\n\nRaised when the QIR is invalid.\n\nThe `kind` attribute names the error variant (e.g. \"validation\", \"parse\", \"wasm\"), `code` is its stable code (see `qir-qis explain`) and `errors` lists the individual messages."
     );
     create_exception!(
         qir_qis,
         CompilerError,
         PyException,
-        "QIR CompilerError.\n\nRaised when QIR to QIS compilation fails."
+        "QIR CompilerError.\n\nRaised when QIR to QIS compilation fails.\n\nThe `kind` attribute names the error variant (e.g. \"lowering\", \"verifier\", \"target\"), `code` is its stable code (see `qir-qis explain`) and `errors` lists the individual messages."
     );
+    create_exception!(
+        qir_qis,
+        QirWarning,
+        PyUserWarning,
+        "QIR QirWarning.\n\nIssued for suspicious but compilable QIR. Pass `deny_warnings=True` to raise instead."
+    );
+
+    /// Convert an [`Error`](crate::Error) into a Python exception of type `T`,
+    /// exposing the variant as `kind`, its stable code as `code` and the
+    /// messages as `errors`.
+    pub fn to_py_err<T: pyo3::PyTypeInfo>(err: &crate::Error) -> PyErr {
+        let py_err = PyErr::new::<T, _>(err.to_string());
+        Python::attach(|py| {
+            let value = py_err.value(py);
+            for (name, result) in [
+                ("kind", value.setattr("kind", err.kind())),
+                ("code", value.setattr("code", err.code())),
+                ("errors", value.setattr("errors", err.messages())),
+            ] {
+                if let Err(e) = result {
+                    log::warn!("Failed to set `{name}` on Python exception: {e}");
+                }
+            }
+        });
+        py_err
+    }
+
+    /// Issue every diagnostic as a Python `QirWarning`.
+    ///
+    /// # Errors
+    /// Propagates the exception raised when a warning filter turns warnings
+    /// into errors.
+    pub fn warn_all<'a>(
+        diagnostics: impl IntoIterator<Item = &'a crate::Diagnostic>,
+    ) -> PyResult<()> {
+        Python::attach(|py| {
+            let category = py.get_type::<QirWarning>();
+            for diagnostic in diagnostics {
+                let message =
+                    CString::new(diagnostic.to_string().replace('\0', "")).unwrap_or_default();
+                PyErr::warn(py, category.as_any(), &message, 1)?;
+            }
+            Ok(())
+        })
+    }
 }
 
 #[cfg(feature = "python")]
@@ -3571,37 +4535,52 @@ mod qir_qis {
     use std::borrow::Cow;
     use std::collections::BTreeMap;
 
-    use super::{PyErr, PyResult, pyfunction};
+    use super::{PyRef, PyResult, Python, pyclass, pyfunction, pymethods};
 
-    use pyo3_stub_gen::derive::gen_stub_pyfunction;
+    use pyo3::types::PyAnyMethods;
+    use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyfunction, gen_stub_pymethods};
 
     #[pymodule_export]
     use super::exceptions::CompilerError;
     #[pymodule_export]
+    use super::exceptions::QirWarning;
+    #[pymodule_export]
     use super::exceptions::ValidationError;
+    use super::exceptions::{to_py_err, warn_all};
 
     /// Validate the given QIR.
     ///
     /// # Arguments
     /// - `bc_bytes` - The QIR bytes to validate.
     /// - `wasm_bytes` - Optional WASM bytes to validate against.
+    /// - `deny_warnings` - Raise instead of issuing a `QirWarning`.
     ///
     /// # Errors
     /// Returns a `ValidationError`:
     /// - If the QIR is invalid.
     /// - If the WASM module is invalid.
     /// - If a QIR-referenced WASM function is missing from the WASM module.
+    /// - If `deny_warnings` is set and any warning is raised.
     #[gen_stub_pyfunction]
     #[pyfunction]
     #[allow(clippy::needless_pass_by_value)]
-    #[pyo3(signature = (bc_bytes, *, wasm_bytes = None))]
-    pub fn validate_qir(bc_bytes: Cow<[u8]>, wasm_bytes: Option<Cow<[u8]>>) -> PyResult<()> {
-        crate::validate_qir(&bc_bytes, wasm_bytes.as_deref())
-            .map_err(PyErr::new::<ValidationError, _>)
+    #[pyo3(signature = (bc_bytes, *, wasm_bytes = None, deny_warnings = false))]
+    pub fn validate_qir(
+        bc_bytes: Cow<[u8]>,
+        wasm_bytes: Option<Cow<[u8]>>,
+        deny_warnings: bool,
+    ) -> PyResult<()> {
+        let report = crate::validate_qir_report(&bc_bytes, wasm_bytes.as_deref())
+            .and_then(|report| report.into_result(deny_warnings))
+            .map_err(|e| to_py_err::<ValidationError>(&e))?;
+        warn_all(report.warnings())
     }
 
     /// Translate QIR bitcode to Quantinuum QIS.
     ///
+    /// The GIL is released during the translation, so calls from several
+    /// threads run in parallel.
+    ///
     /// # Arguments
     /// - `bc_bytes` - The QIR bytes to translate.
     /// - `opt_level` - The optimization level to use (0-3). Default is 2 on
@@ -3609,31 +4588,83 @@ mod qir_qis {
     /// - `target` - Target architecture (default: "aarch64" on Linux/macOS and
     ///   "native" on Windows; options: "x86-64", "native").
     /// - `wasm_bytes` - Optional WASM bytes for Wasm codegen.
+    /// - `emit` - Artifact to return: "bc" (bitcode, default), "ll" (textual
+    ///   LLVM IR), "asm" (assembly) or "obj" (object file).
+    /// - `deny_warnings` - Raise instead of issuing a `QirWarning`.
+    /// - `print_after` - Names of the stages (see `qir_qis::pipeline::STAGES`
+    ///   in the Rust API) after which to print the IR to `sys.stderr`.
+    /// - `print_after_all` - Print the IR after every stage.
+    /// - `stop_after` - Return the module as left by this stage instead of
+    ///   running the whole translation.
+    /// - `options` - A `CompileOptions` to use instead of `opt_level`,
+    ///   `target`, `wasm_bytes`, `emit` and `deny_warnings`, which may not be
+    ///   given with it.
     ///
     /// # Errors
-    /// Returns a `CompilerError` if the translation fails.
+    /// Returns a `CompilerError` if the translation fails, or if
+    /// `deny_warnings` is set and any warning is raised. Raises `ValueError`
+    /// for an invalid `opt_level`, `target`, `emit` kind or stage, or if
+    /// `options` is given with the arguments it replaces.
     #[gen_stub_pyfunction]
     #[pyfunction]
     #[allow(clippy::needless_pass_by_value)]
     #[allow(clippy::missing_errors_doc)]
-    #[cfg_attr(
-        windows,
-        pyo3(signature = (bc_bytes, *, opt_level = 0, target = "native", wasm_bytes = None))
-    )]
-    #[cfg_attr(
-        not(windows),
-        pyo3(signature = (bc_bytes, *, opt_level = 2, target = "aarch64", wasm_bytes = None))
-    )]
+    #[pyo3(signature = (bc_bytes, *, opt_level = None, target = None, wasm_bytes = None, emit = None, deny_warnings = None, print_after = None, print_after_all = false, stop_after = None, options = None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn qir_to_qis<'a>(
+        py: Python<'_>,
         bc_bytes: Cow<[u8]>,
-        opt_level: u32,
-        target: &'a str,
+        opt_level: Option<u32>,
+        target: Option<&'a str>,
         wasm_bytes: Option<Cow<'a, [u8]>>,
+        emit: Option<&str>,
+        deny_warnings: Option<bool>,
+        print_after: Option<Vec<String>>,
+        print_after_all: bool,
+        stop_after: Option<&str>,
+        options: Option<PyRef<'_, CompileOptions>>,
     ) -> PyResult<Cow<'a, [u8]>> {
-        let result = crate::qir_to_qis(&bc_bytes, opt_level, target, wasm_bytes.as_deref())
-            .map_err(PyErr::new::<CompilerError, _>)?;
+        let options = resolve_options(
+            options.as_deref(),
+            opt_level,
+            target,
+            wasm_bytes.as_deref(),
+            emit,
+            deny_warnings,
+        )?;
+        let pipeline = crate::Pipeline::debugging(
+            print_after.as_deref().unwrap_or_default(),
+            print_after_all,
+            stop_after,
+        )
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let output = match py.detach(|| options.compile_with(&bc_bytes, &pipeline)) {
+            Ok(output) => output,
+            Err(err) => {
+                // Print the IR recorded before a failing stage, to bisect it.
+                let (err, dumps) = err.split_dumps();
+                write_dumps(py, &dumps)?;
+                return Err(to_py_err::<CompilerError>(&err));
+            }
+        };
+        write_dumps(py, &output.dumps)?;
+        warn_all(&output.warnings)?;
 
-        Ok(result.into())
+        Ok(output.artifact.into())
+    }
+
+    /// Write the IR recorded after pipeline stages to `sys.stderr`.
+    ///
+    /// # Errors
+    /// Propagates the exception raised when writing to `sys.stderr`.
+    fn write_dumps(py: Python<'_>, dumps: &[crate::StageDump]) -> PyResult<()> {
+        if !dumps.is_empty() {
+            let stderr = py.import("sys")?.getattr("stderr")?;
+            for dump in dumps {
+                stderr.call_method1("write", (dump.to_string(),))?;
+            }
+        }
+        Ok(())
     }
 
     /// Convert QIR LLVM IR to QIR bitcode.
@@ -3643,7 +4674,7 @@ mod qir_qis {
     #[gen_stub_pyfunction]
     #[pyfunction]
     fn qir_ll_to_bc(ll_text: &str) -> PyResult<Cow<'_, [u8]>> {
-        let result = crate::qir_ll_to_bc(ll_text).map_err(PyErr::new::<ValidationError, _>)?;
+        let result = crate::qir_ll_to_bc(ll_text).map_err(|e| to_py_err::<ValidationError>(&e))?;
         Ok(result.into())
     }
 
@@ -3658,7 +4689,229 @@ mod qir_qis {
     #[pyfunction]
     #[allow(clippy::needless_pass_by_value)]
     fn get_entry_attributes(bc_bytes: Cow<[u8]>) -> PyResult<BTreeMap<String, Option<String>>> {
-        crate::get_entry_attributes(&bc_bytes).map_err(PyErr::new::<ValidationError, _>)
+        crate::get_entry_attributes(&bc_bytes).map_err(|e| to_py_err::<ValidationError>(&e))
+    }
+
+    /// Translate QIR bitcode to Quantinuum QIS and pack it into a single-file
+    /// bundle with the WASM module, entry point attributes and capability
+    /// flags.
+    ///
+    /// Takes the same arguments as `qir_to_qis`, except `emit` and the stage
+    /// debugging options. Use `read_bundle` to unpack the result.
+    ///
+    /// # Errors
+    /// Returns a `CompilerError` if the translation fails, or if
+    /// `deny_warnings` is set and any warning is raised. Raises `ValueError`
+    /// for an invalid `opt_level` or `target`, or if `options` is given with
+    /// the arguments it replaces.
+    #[gen_stub_pyfunction]
+    #[pyfunction]
+    #[allow(clippy::needless_pass_by_value)]
+    #[allow(clippy::missing_errors_doc)]
+    #[pyo3(signature = (bc_bytes, *, opt_level = None, target = None, wasm_bytes = None, deny_warnings = None, options = None))]
+    pub fn qir_to_bundle(
+        py: Python<'_>,
+        bc_bytes: Cow<[u8]>,
+        opt_level: Option<u32>,
+        target: Option<&str>,
+        wasm_bytes: Option<Cow<[u8]>>,
+        deny_warnings: Option<bool>,
+        options: Option<PyRef<'_, CompileOptions>>,
+    ) -> PyResult<Cow<'static, [u8]>> {
+        let options = resolve_options(
+            options.as_deref(),
+            opt_level,
+            target,
+            wasm_bytes.as_deref(),
+            None,
+            deny_warnings,
+        )?;
+        let output = py
+            .detach(|| options.compile_bundle(&bc_bytes))
+            .map_err(|e| to_py_err::<CompilerError>(&e))?;
+        warn_all(&output.warnings)?;
+
+        Ok(output.artifact.into())
+    }
+
+    /// Settings of a QIR to QIS translation, checked when created.
+    ///
+    /// Pass it as `options=` to `qir_to_qis` or `qir_to_bundle` instead of the
+    /// individual keyword arguments, which it takes with the same defaults.
+    #[gen_stub_pyclass]
+    #[pyclass(frozen, module = "qir_qis")]
+    pub struct CompileOptions(crate::CompileOptions);
+
+    #[gen_stub_pymethods]
+    #[pymethods]
+    impl CompileOptions {
+        /// # Errors
+        /// Raises `ValueError` for an invalid `opt_level`, `target` or
+        /// `emit`.
+        #[new]
+        #[allow(clippy::needless_pass_by_value)]
+        #[pyo3(signature = (*, opt_level = None, target = None, wasm_bytes = None, emit = None, deny_warnings = None))]
+        fn new(
+            opt_level: Option<u32>,
+            target: Option<&str>,
+            wasm_bytes: Option<Cow<[u8]>>,
+                emit: Option<&str>,
+            deny_warnings: Option<bool>,
+        ) -> PyResult<Self> {
+            resolve_options(
+                None,
+                opt_level,
+                target,
+                wasm_bytes.as_deref(),
+                    emit,
+                deny_warnings,
+            )
+            .map(Self)
+        }
+
+        /// The optimization level (0-3).
+        #[getter]
+        fn opt_level(&self) -> u32 {
+            self.0.opt_level().as_u32()
+        }
+
+        /// The target architecture.
+        #[getter]
+        fn target(&self) -> &'static str {
+            self.0.target().as_str()
+        }
+
+        /// The WASM module providing the functions called by the program.
+        #[getter]
+        fn wasm_bytes(&self) -> Option<Cow<'_, [u8]>> {
+            self.0.wasm().map(Cow::Borrowed)
+        }
+
+        /// The artifact to return: "bc", "ll", "asm" or "obj".
+        #[getter]
+        fn emit(&self) -> &'static str {
+            self.0.emit().as_str()
+        }
+
+        /// Whether warnings raise instead of issuing a `QirWarning`.
+        #[getter]
+        fn deny_warnings(&self) -> bool {
+            self.0.deny_warnings()
+        }
+    }
+
+    /// The `options` given to a translation, or the options of its keyword
+    /// arguments, with the platform defaults for those not given.
+    ///
+    /// # Errors
+    /// Raises `ValueError` if `options` is given together with any of the
+    /// keyword arguments it replaces, or for an invalid value.
+    fn resolve_options(
+        options: Option<&CompileOptions>,
+        opt_level: Option<u32>,
+        target: Option<&str>,
+        wasm_bytes: Option<&[u8]>,
+        emit: Option<&str>,
+        deny_warnings: Option<bool>,
+    ) -> PyResult<crate::CompileOptions> {
+        let Some(options) = options else {
+            return compile_options(
+                opt_level.unwrap_or(crate::DEFAULT_OPT_LEVEL),
+                target.unwrap_or(crate::DEFAULT_TARGET),
+                wasm_bytes,
+                emit.unwrap_or("bc"),
+                deny_warnings.unwrap_or(false),
+            );
+        };
+        if opt_level.is_some()
+            || target.is_some()
+            || wasm_bytes.is_some()
+            || emit.is_some()
+            || deny_warnings.is_some()
+        {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "`options` cannot be combined with `opt_level`, `target`, `wasm_bytes`, `emit` or `deny_warnings`",
+            ));
+        }
+        Ok(options.0.clone())
+    }
+
+    fn compile_options(
+        opt_level: u32,
+        target: &str,
+        wasm_bytes: Option<&[u8]>,
+        emit: &str,
+        deny_warnings: bool,
+    ) -> PyResult<crate::CompileOptions> {
+        let value_error = pyo3::exceptions::PyValueError::new_err;
+        let mut options = crate::CompileOptions::new()
+            .with_opt_level(crate::OptLevel::try_from(opt_level).map_err(value_error)?)
+            .with_target(target.parse().map_err(value_error)?)
+            .with_emit(emit.parse().map_err(value_error)?)
+            .with_deny_warnings(deny_warnings);
+        if let Some(wasm) = wasm_bytes {
+            options = options.with_wasm(wasm);
+        }
+        Ok(options)
+    }
+
+    /// Unpack a bundle written by `qir_to_bundle`.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the bytes are not a valid bundle.
+    #[gen_stub_pyfunction]
+    #[pyfunction]
+    #[allow(clippy::needless_pass_by_value)]
+    fn read_bundle(bundle_bytes: Cow<[u8]>) -> PyResult<Bundle> {
+        crate::Bundle::from_bytes(&bundle_bytes)
+            .map(Bundle)
+            .map_err(|e| to_py_err::<ValidationError>(&e))
+    }
+
+    /// A compiled QIS program with its WASM module and manifest.
+    #[gen_stub_pyclass]
+    #[pyclass(frozen, module = "qir_qis")]
+    pub struct Bundle(crate::Bundle);
+
+    #[gen_stub_pymethods]
+    #[pymethods]
+    impl Bundle {
+        /// The QIS bitcode.
+        #[getter]
+        fn qis(&self) -> Cow<'_, [u8]> {
+            Cow::Borrowed(&self.0.qis)
+        }
+
+        /// The WASM module providing the functions called by the program.
+        #[getter]
+        fn wasm(&self) -> Option<Cow<'_, [u8]>> {
+            self.0.wasm.as_deref().map(Cow::Borrowed)
+        }
+
+        /// Entry point attributes, as returned by `get_entry_attributes`.
+        #[getter]
+        fn entry_attributes(&self) -> BTreeMap<String, Option<String>> {
+            self.0.entry_attributes.clone()
+        }
+
+        /// The `dynamic_qubit_management`, `dynamic_result_management` and
+        /// `arrays` capability flags.
+        #[getter]
+        fn capability_flags(&self) -> BTreeMap<&'static str, bool> {
+            self.0.capability_flags.entries().into_iter().collect()
+        }
+
+        /// Target architecture the QIS was compiled for.
+        #[getter]
+        fn target(&self) -> &str {
+            &self.0.target
+        }
+
+        /// Version of `qir-qis` that compiled the QIS.
+        #[getter]
+        fn compiler_version(&self) -> &str {
+            &self.0.compiler_version
+        }
     }
 }
 
@@ -3669,15 +4922,19 @@ define_stub_info_gatherer!(stub_info);
 mod test {
     #![allow(clippy::expect_used)]
     #![allow(clippy::unwrap_used)]
+    // Most tests translate through the positional `qir_to_qis` shim.
+    #![allow(deprecated)]
     use crate::{
-        convert::get_string_label, create_module_from_ir_text, get_entry_attributes,
-        parse_bitcode_module, qir_ll_to_bc, qir_to_qis, validate_qir,
+        CompileOptions, Emit, OptLevel, Severity, Target, convert::get_string_label,
+        create_module_from_ir_text, diagnostics::codes, get_capability_flags, get_entry_attributes,
+        is_bitcode, parse_bitcode_module, qir_ll_to_bc, qir_to_qis, validate_qir,
+        validate_qir_ll_report, validate_qir_report,
     };
     use inkwell::{
         context::Context,
         memory_buffer::MemoryBuffer,
         module::Module,
-        values::{CallSiteValue, FunctionValue},
+        values::{BasicValue, CallSiteValue, FunctionValue},
     };
     use proptest::prelude::*;
     use std::{collections::BTreeMap, sync::LazyLock};
@@ -3718,6 +4975,13 @@ mod test {
         (0, "native")
     }
 
+    /// Options matching [`conservative_translation_settings`].
+    fn conservative_options() -> CompileOptions {
+        CompileOptions::new()
+            .with_opt_level(OptLevel::O0)
+            .with_target(Target::Native)
+    }
+
     fn load_fixture_bitcode(path: &str) -> Vec<u8> {
         PROPERTY_FIXTURE_BITCODE
             .get(path)
@@ -3727,8 +4991,9 @@ mod test {
 
     fn verify_bitcode_module(bitcode: &[u8], name: &str) -> Result<(), String> {
         let ctx = Context::create();
-        let module = parse_bitcode_module(&ctx, bitcode, name)?;
+        let module = parse_bitcode_module(&ctx, bitcode, name).map_err(|e| e.to_string())?;
         crate::llvm_verify::verify_module(&module, "LLVM verifier rejected translated module")
+            .map_err(|e| e.to_string())
     }
 
     fn parse_bitcode_as_file(bitcode: &[u8], name: &str) -> Result<(), String> {
@@ -4050,7 +5315,8 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
         let err = qir_to_qis(&bc_bytes, 1, "native", None)
-            .expect_err("optimized conversion should fail fast on Windows");
+            .expect_err("optimized conversion should fail fast on Windows")
+            .to_string();
         assert!(err.contains("currently unavailable on Windows"));
         assert!(err.contains("opt_level=0"));
     }
@@ -4089,6 +5355,84 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
         assert!(module.get_function("qir_qis.load_qubit").is_some());
     }
 
+    #[test]
+    fn test_two_qubit_gates_fixture_validate_and_compile() {
+        let ll_text = std::fs::read_to_string("tests/data/two_qubit_gates.ll")
+            .expect("Failed to read two_qubit_gates.ll");
+        let input_bc =
+            qir_ll_to_bc(&ll_text).expect("Failed to convert two_qubit_gates.ll to bitcode");
+
+        validate_qir(&input_bc, None).expect("Two-qubit gate fixture should validate");
+        let output_bc = qir_to_qis(&input_bc, 0, "native", None)
+            .expect("Two-qubit gate fixture should compile");
+
+        let ctx = Context::create();
+        let module = parse_bitcode_module(&ctx, &output_bc, "qis_module")
+            .expect("Compiled QIS bitcode should parse");
+        assert!(module.get_function("qmain").is_some());
+        for native in ["___rxy", "___rz", "___rzz"] {
+            assert!(module.get_function(native).is_some(), "{native}");
+        }
+    }
+
+    #[test]
+    fn test_multi_controlled_gates_fixture_validate_and_compile() {
+        let ll_text = std::fs::read_to_string("tests/data/multi_controlled_gates.ll")
+            .expect("Failed to read multi_controlled_gates.ll");
+        let input_bc =
+            qir_ll_to_bc(&ll_text).expect("Failed to convert multi_controlled_gates.ll to bitcode");
+
+        validate_qir(&input_bc, None).expect("Multi-controlled gate fixture should validate");
+        let output_bc = qir_to_qis(&input_bc, 0, "native", None)
+            .expect("Multi-controlled gate fixture should compile");
+
+        let ctx = Context::create();
+        let module = parse_bitcode_module(&ctx, &output_bc, "qis_module")
+            .expect("Compiled QIS bitcode should parse");
+        assert!(module.get_function("qmain").is_some());
+        for gate in ["ccz", "cswap", "mcx4", "mcz3"] {
+            // Without optimization the decompositions stay as functions.
+            assert!(
+                module
+                    .get_function(&format!("__quantum__qis__{gate}__body"))
+                    .is_none_or(|f| f.count_basic_blocks() > 0),
+                "{gate} should be decomposed"
+            );
+        }
+    }
+
+    #[test]
+    fn test_controlled_gates_fixture_validate_and_compile() {
+        let ll_text = std::fs::read_to_string("tests/data/controlled_gates.ll")
+            .expect("Failed to read controlled_gates.ll");
+        let input_bc =
+            qir_ll_to_bc(&ll_text).expect("Failed to convert controlled_gates.ll to bitcode");
+
+        validate_qir(&input_bc, None).expect("Controlled gate fixture should validate");
+        let output_bc = qir_to_qis(&input_bc, 0, "native", None)
+            .expect("Controlled gate fixture should compile");
+
+        let ctx = Context::create();
+        let module = parse_bitcode_module(&ctx, &output_bc, "qis_module")
+            .expect("Compiled QIS bitcode should parse");
+        assert!(module.get_function("qmain").is_some());
+        for gate in [
+            "x__ctl",
+            "h__ctladj",
+            "s__ctladj",
+            "rz__ctl",
+            "ry__ctladj",
+            "rx__ctl",
+        ] {
+            assert!(
+                module
+                    .get_function(&format!("__quantum__qis__{gate}"))
+                    .is_none(),
+                "{gate} should be decomposed"
+            );
+        }
+    }
+
     #[test]
     fn test_qir2_adaptive_fixture_validate_and_compile() {
         let ll_text = std::fs::read_to_string("tests/data/qir2_adaptive.ll")
@@ -4227,7 +5571,9 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err = validate_qir(&bc_bytes, None).expect_err("Malformed module flag should fail");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("Malformed module flag should fail")
+            .to_string();
         assert!(err.contains("Missing or unsupported module flag: qir_major_version"));
     }
 
@@ -4250,7 +5596,8 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("unsupported single-valued module flag should fail");
+            .expect_err("unsupported single-valued module flag should fail")
+            .to_string();
         assert!(err.contains("Unsupported qir_minor_version: expected i32 0"));
     }
 
@@ -4274,7 +5621,8 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("unsupported optional arrays flag should fail validation");
+            .expect_err("unsupported optional arrays flag should fail validation")
+            .to_string();
         assert!(err.contains("Unsupported arrays: expected one of i1 false, i1 true"));
     }
 
@@ -4298,8 +5646,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err =
-            validate_qir(&bc_bytes, None).expect_err("malformed optional arrays flag should fail");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("malformed optional arrays flag should fail")
+            .to_string();
         assert!(err.contains("Missing or unsupported module flag: arrays"));
     }
 
@@ -4320,7 +5669,9 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err = validate_qir(&bc_bytes, None).expect_err("Missing flag should fail");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("Missing flag should fail")
+            .to_string();
         assert!(err.contains("Missing required module flag: qir_major_version"));
     }
 
@@ -4398,7 +5749,9 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err = validate_qir(&bc_bytes, None).expect_err("malformed barrier suffix should fail");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("malformed barrier suffix should fail")
+            .to_string();
         assert!(err.contains("Unsupported QIR QIS function: __quantum__qis__barrier2__adj"));
     }
 
@@ -4454,10 +5807,186 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err = validate_qir(&bc_bytes, None).expect_err("barrier0 should be rejected");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("barrier0 should be rejected")
+            .to_string();
         assert!(err.contains("Unsupported QIR QIS function: __quantum__qis__barrier0__body"));
     }
 
+    #[test]
+    fn test_validate_qir_checks_multi_controlled_gate_arity() {
+        let ll_text = |gate: &str, required_num_qubits: u32| {
+            format!(
+                r#"
+declare void @__quantum__qis__{gate}__body(ptr, ptr, ptr)
+
+define i64 @Entry_Point_Name() #0 {{
+entry:
+  call void @__quantum__qis__{gate}__body(ptr null, ptr inttoptr (i64 1 to ptr), ptr inttoptr (i64 2 to ptr))
+  ret i64 0
+}}
+
+attributes #0 = {{ "entry_point" "qir_profiles"="base_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="{required_num_qubits}" "required_num_results"="1" }}
+
+!llvm.module.flags = !{{!0, !1, !2, !3}}
+!0 = !{{i32 1, !"qir_major_version", i32 2}}
+!1 = !{{i32 7, !"qir_minor_version", i32 0}}
+!2 = !{{i32 1, !"dynamic_qubit_management", i1 false}}
+!3 = !{{i32 1, !"dynamic_result_management", i1 false}}
+"#
+            )
+        };
+        let report = |gate: &str, required_num_qubits: u32| {
+            let bc_bytes = qir_ll_to_bc(&ll_text(gate, required_num_qubits))
+                .expect("Failed to convert to bitcode");
+            validate_qir_report(&bc_bytes, None).expect("bitcode should parse")
+        };
+
+        assert!(!report("mcx3", 3).has_errors());
+        assert!(!report("mcz3", 3).has_errors());
+        let overflow = report("mcz3", 2);
+        assert_eq!(overflow.diagnostics.len(), 1);
+        assert_eq!(
+            overflow.diagnostics[0].code,
+            codes::MULTI_CONTROLLED_ARITY_OVERFLOW
+        );
+        assert!(
+            overflow.diagnostics[0]
+                .message
+                .contains("Multi-controlled gate arity 3 exceeds module's required_num_qubits (2)")
+        );
+        // Any arity fits in a large enough program
+        assert!(!report("mcx20", 20).has_errors());
+        for gate in ["mcx0", "mcy3"] {
+            let unsupported = report(gate, 20);
+            assert_eq!(unsupported.diagnostics.len(), 1, "{gate}");
+            assert_eq!(
+                unsupported.diagnostics[0].code,
+                codes::UNSUPPORTED_QIS_FUNCTION,
+                "{gate}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_qir_checks_controlled_gate_controls() {
+        let ll_text = |controls: &str, arrays: bool| {
+            format!(
+                r#"
+declare void @__quantum__rt__qubit_array_allocate(i64, ptr, ptr)
+declare ptr @__quantum__rt__qubit_allocate(ptr)
+declare void @__quantum__qis__rz__ctl(ptr, double, ptr)
+
+define i64 @Entry_Point_Name() #0 {{
+entry:
+  %controls = alloca [2 x ptr], align 8
+  call void @__quantum__rt__qubit_array_allocate(i64 2, ptr %controls, ptr null)
+  %wide = alloca [8 x ptr], align 8
+  call void @__quantum__rt__qubit_array_allocate(i64 8, ptr %wide, ptr null)
+  %target = call ptr @__quantum__rt__qubit_allocate(ptr null)
+  call void @__quantum__qis__rz__ctl(ptr {controls}, double 5.000000e-01, ptr %target)
+  ret i64 0
+}}
+
+attributes #0 = {{ "entry_point" "qir_profiles"="adaptive_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="8" "required_num_results"="1" }}
+
+!llvm.module.flags = !{{!0, !1, !2, !3, !4}}
+!0 = !{{i32 1, !"qir_major_version", i32 2}}
+!1 = !{{i32 7, !"qir_minor_version", i32 0}}
+!2 = !{{i32 1, !"dynamic_qubit_management", i1 true}}
+!3 = !{{i32 1, !"dynamic_result_management", i1 false}}
+!4 = !{{i32 1, !"arrays", i1 {arrays}}}
+"#
+            )
+        };
+        let report = |controls: &str, arrays: bool| {
+            let bc_bytes =
+                qir_ll_to_bc(&ll_text(controls, arrays)).expect("Failed to convert to bitcode");
+            validate_qir_report(&bc_bytes, None).expect("bitcode should parse")
+        };
+
+        assert!(!report("%controls", true).has_errors());
+        let missing = report("%controls", false);
+        assert!(
+            missing
+                .diagnostics
+                .iter()
+                .any(|d| d.code == codes::MISSING_CAPABILITY
+                    && d.message.contains("__quantum__qis__rz__ctl requires both")),
+            "{missing:?}"
+        );
+        let unbacked = report("%target", true);
+        assert!(
+            unbacked
+                .diagnostics
+                .iter()
+                .any(|d| d.code == codes::INVALID_ARRAY_BACKING),
+            "{unbacked:?}"
+        );
+        // With the target, 8 controls exceed the 8 qubits of the program.
+        let wide = report("%wide", true);
+        assert!(
+            wide.diagnostics
+                .iter()
+                .any(|d| d.code == codes::MULTI_CONTROLLED_ARITY_OVERFLOW),
+            "{wide:?}"
+        );
+    }
+
+    #[test]
+    fn test_compile_qsharp_control_arrays() {
+        let ll_text = std::fs::read_to_string("tests/data/qsharp_controlled_gates.ll")
+            .expect("Failed to read qsharp_controlled_gates.ll");
+        let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert to bitcode");
+        let report = validate_qir_report(&bc_bytes, None).expect("bitcode should parse");
+        assert!(!report.has_errors(), "{report:?}");
+
+        // The runtime array and tuple are lowered to the stack and optimized out.
+        let output = CompileOptions::new()
+            .with_emit(Emit::LlvmIr)
+            .compile(&bc_bytes)
+            .expect("Q# controlled gates should compile");
+        let text = String::from_utf8(output.artifact).expect("LLVM IR should be UTF-8");
+        assert!(!text.contains("__quantum__rt__array"), "{text}");
+        assert!(!text.contains("__quantum__rt__tuple"), "{text}");
+        assert!(!text.contains("__ctl"), "{text}");
+    }
+
+    #[test]
+    fn test_validate_qir_rejects_unsupported_qsharp_arrays() {
+        let ll_text = std::fs::read_to_string("tests/data/qsharp_controlled_gates.ll")
+            .expect("Failed to read qsharp_controlled_gates.ll")
+            .replace(
+                "define i64 @ENTRYPOINT__main() #0 {\nentry:\n",
+                "define i64 @ENTRYPOINT__main() #0 {\nentry:\n  %len = call i64 @len()\n",
+            )
+            .replace(
+                "@__quantum__rt__array_create_1d(i32 8, i64 2)",
+                "@__quantum__rt__array_create_1d(i32 8, i64 %len)",
+            )
+            .replace(
+                "declare i8* @__quantum__rt__array_get_element_ptr_1d",
+                "declare i64 @len()\ndeclare i8* @__quantum__rt__array_get_element_ptr_1d",
+            );
+        let report = validate_qir_ll_report(&ll_text, "qsharp.ll", None).expect("IR should parse");
+
+        // Arrays of a runtime length cannot be lowered to the stack.
+        assert!(
+            report.diagnostics.iter().any(|d| {
+                d.code == codes::UNSUPPORTED_RUNTIME_ARRAY
+                    && d.message.contains("the length must be a constant")
+            }),
+            "{report:?}"
+        );
+        assert!(
+            !report
+                .diagnostics
+                .iter()
+                .any(|d| d.code == codes::UNSUPPORTED_RT_FUNCTION),
+            "{report:?}"
+        );
+    }
+
     #[test]
     fn test_validate_qir_rejects_unsupported_qtm_function() {
         let ll_text = r#"
@@ -4480,7 +6009,8 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("unsupported QTM declarations should fail validation");
+            .expect_err("unsupported QTM declarations should fail validation")
+            .to_string();
         assert!(err.contains("Unsupported Qtm QIS function: ___unknown_qtm"));
     }
 
@@ -4568,7 +6098,8 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("IR-defined pointer-returning helper should fail validation");
+            .expect_err("IR-defined pointer-returning helper should fail validation")
+            .to_string();
         assert!(err.contains("Function `helper` cannot return a pointer type"));
     }
 
@@ -4595,27 +6126,74 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 !3 = !{i32 1, !"dynamic_result_management", i1 false}
 "#;
 
-        let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err = qir_to_qis(&bc_bytes, 0, "native", None)
-            .expect_err("unknown declared QIS function should fail");
-        assert!(err.contains("Unsupported QIR QIS function: __quantum__qis__mystery__body"));
+        let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
+        let err = qir_to_qis(&bc_bytes, 0, "native", None)
+            .expect_err("unknown declared QIS function should fail")
+            .to_string();
+        assert!(err.contains("Unsupported QIR QIS function: __quantum__qis__mystery__body"));
+    }
+
+    #[test]
+    fn test_qir_to_qis_u1q_synonym_lowers_to_rxy() {
+        let ll_text = r#"
+%Qubit = type opaque
+
+declare void @__quantum__qis__u1q__body(double, double, %Qubit*)
+
+define i64 @Entry_Point_Name() #0 {
+entry:
+  %q0 = inttoptr i64 1 to %Qubit*
+  call void @__quantum__qis__u1q__body(double 1.0, double 0.5, %Qubit* %q0)
+  ret i64 0
+}
+
+attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="1" "required_num_results"="1" }
+
+!llvm.module.flags = !{!0, !1, !2, !3}
+!0 = !{i32 1, !"qir_major_version", i32 1}
+!1 = !{i32 7, !"qir_minor_version", i32 0}
+!2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+!3 = !{i32 1, !"dynamic_result_management", i1 false}
+"#;
+
+        let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
+        let output_bc =
+            qir_to_qis(&bc_bytes, 0, "native", None).expect("u1q synonym should compile");
+
+        let ctx = Context::create();
+        let module = parse_bitcode_module(&ctx, &output_bc, "qis_module")
+            .expect("Compiled QIS bitcode should parse");
+        assert!(module.get_function("___rxy").is_some());
+
+        #[cfg(not(windows))]
+        {
+            let text = module.to_string();
+            assert!(text.contains("___rxy"));
+        }
     }
 
     #[test]
-    fn test_qir_to_qis_u1q_synonym_lowers_to_rxy() {
+    fn test_qir_to_qis_pytket_gates_lower_to_native_gates() {
         let ll_text = r#"
 %Qubit = type opaque
 
-declare void @__quantum__qis__u1q__body(double, double, %Qubit*)
+declare void @__quantum__qis__phasedx__body(double, double, %Qubit*)
+declare void @__quantum__qis__zzmax__body(%Qubit*, %Qubit*)
+declare void @__quantum__qis__zzphase__body(double, %Qubit*, %Qubit*)
+declare void @__quantum__qis__tk2__body(double, double, double, %Qubit*, %Qubit*)
 
 define i64 @Entry_Point_Name() #0 {
 entry:
-  %q0 = inttoptr i64 1 to %Qubit*
-  call void @__quantum__qis__u1q__body(double 1.0, double 0.5, %Qubit* %q0)
+  %q0 = inttoptr i64 0 to %Qubit*
+  %q1 = inttoptr i64 1 to %Qubit*
+  call void @__quantum__qis__phasedx__body(double 1.0, double 0.5, %Qubit* %q0)
+  call void @__quantum__qis__zzmax__body(%Qubit* %q0, %Qubit* %q1)
+  call void @__quantum__qis__zzphase__body(double 0.25, %Qubit* %q0, %Qubit* %q1)
+  call void @__quantum__qis__tk2__body(double 0.1, double 0.2, double 0.3, %Qubit* %q0, %Qubit* %q1)
   ret i64 0
 }
 
-attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="1" "required_num_results"="1" }
+attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="2" "required_num_results"="1" }
 
 !llvm.module.flags = !{!0, !1, !2, !3}
 !0 = !{i32 1, !"qir_major_version", i32 1}
@@ -4625,18 +6203,37 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
+        validate_qir(&bc_bytes, None).expect("pytket gates should validate");
         let output_bc =
-            qir_to_qis(&bc_bytes, 0, "native", None).expect("u1q synonym should compile");
+            qir_to_qis(&bc_bytes, 0, "native", None).expect("pytket gates should compile");
 
         let ctx = Context::create();
         let module = parse_bitcode_module(&ctx, &output_bc, "qis_module")
             .expect("Compiled QIS bitcode should parse");
         assert!(module.get_function("___rxy").is_some());
+        assert!(module.get_function("___rzz").is_some());
+        for gate in ["phasedx", "zzmax", "zzphase", "tk2"] {
+            // Without optimization the decompositions stay as functions, and
+            // lowered native gates leave their unused declarations behind.
+            assert!(
+                module
+                    .get_function(&format!("__quantum__qis__{gate}__body"))
+                    .is_none_or(|f| {
+                        f.count_basic_blocks() > 0
+                            || f.as_global_value()
+                                .as_pointer_value()
+                                .get_first_use()
+                                .is_none()
+                    }),
+                "{gate} should be lowered"
+            );
+        }
 
         #[cfg(not(windows))]
         {
+            // zzmax is a ZZ rotation by π/2
             let text = module.to_string();
-            assert!(text.contains("___rxy"));
+            assert!(text.contains("double 0x3FF921FB54442D18"));
         }
     }
 
@@ -4675,10 +6272,172 @@ declare void @__quantum__rt__int_record_output(i64, i8*)
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("invalid required_num_results should fail validation");
+            .expect_err("invalid required_num_results should fail validation")
+            .to_string();
         assert!(err.contains("Invalid required_num_results attribute value: abc"));
     }
 
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_validate_qir_checks_wasm_declarations_against_exports() {
+        use wasm_encoder::{
+            CodeSection, Function, FunctionSection, Instruction, TypeSection, ValType,
+        };
+
+        let mut types = TypeSection::new();
+        types.ty().function([ValType::I32], [ValType::I32]);
+        types.ty().function([ValType::F64], [ValType::F64]);
+        let mut functions = FunctionSection::new();
+        let mut exports = ExportSection::new();
+        let mut code = CodeSection::new();
+        let mut body = Function::new(vec![]);
+        body.instruction(&Instruction::Unreachable)
+            .instruction(&Instruction::End);
+        for (index, (name, ty)) in [("add_one", 0), ("add", 0), ("scale", 1)]
+            .into_iter()
+            .enumerate()
+        {
+            functions.function(ty);
+            exports.export(
+                name,
+                ExportKind::Func,
+                u32::try_from(index).expect("index should fit in u32"),
+            );
+            code.function(&body);
+        }
+        let mut wasm = WasmModule::new();
+        wasm.section(&types)
+            .section(&functions)
+            .section(&exports)
+            .section(&code);
+        let wasm = wasm.finish();
+
+        let ll_text = minimal_qir_with_body(
+            "1",
+            "0",
+            "1",
+            r#"
+declare i32 @add_one(i32) #1
+declare i32 @add(i32, i32) #1
+declare i64 @scale(i64) #1
+declare void @missing() #1
+
+attributes #1 = { "wasm" }
+"#,
+            r"  %0 = call i32 @add_one(i32 1)
+  %1 = call i32 @add(i32 1, i32 2)
+  %2 = call i64 @scale(i64 3)
+  call void @missing()",
+        );
+        let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
+
+        validate_qir(&bc_bytes, None)
+            .expect("WASM declarations are not checked without a WASM module");
+        let report = validate_qir_report(&bc_bytes, Some(&wasm))
+            .expect("validation should produce a report");
+        let errors = report
+            .errors()
+            .map(|d| (d.code, d.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    codes::WASM_ARITY_MISMATCH,
+                    "WASM function `add` takes 1 parameters, but is declared with 2"
+                ),
+                (
+                    codes::WASM_SIGNATURE_MISMATCH,
+                    "Parameter 0 of WASM function `scale` is f64 in the WASM module, but `i64` in the declaration"
+                ),
+                (
+                    codes::WASM_SIGNATURE_MISMATCH,
+                    "WASM function `scale` returns f64 in the WASM module, but `i64` in the declaration"
+                ),
+                (
+                    codes::MISSING_WASM_EXPORT,
+                    "WASM function `missing` is not exported by the WASM module"
+                ),
+            ]
+        );
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_compile_ll_leaves_wasm_calls_as_is() {
+        use wasm_encoder::{
+            CodeSection, Function, FunctionSection, Instruction, TypeSection, ValType,
+        };
+
+        let mut types = TypeSection::new();
+        types.ty().function([ValType::I32], [ValType::I32]);
+        types.ty().function([ValType::F64], []);
+        let mut functions = FunctionSection::new();
+        let mut exports = ExportSection::new();
+        let mut code = CodeSection::new();
+        let mut body = Function::new(vec![]);
+        body.instruction(&Instruction::Unreachable)
+            .instruction(&Instruction::End);
+        for (index, (name, ty)) in [("add_one", 0), ("log", 1)].into_iter().enumerate() {
+            functions.function(ty);
+            exports.export(
+                name,
+                ExportKind::Func,
+                u32::try_from(index).expect("index should fit in u32"),
+            );
+            code.function(&body);
+        }
+        let mut wasm = WasmModule::new();
+        wasm.section(&types)
+            .section(&functions)
+            .section(&exports)
+            .section(&code);
+        let wasm = wasm.finish();
+
+        let ll_text = minimal_qir_with_body(
+            "1",
+            "0",
+            "1",
+            r#"
+declare i32 @add_one(i32) #1
+declare void @log(double) #1
+
+attributes #1 = { "wasm" }
+"#,
+            r"  %0 = call i32 @add_one(i32 1)
+  call void @log(double 5.000000e-01)",
+        );
+        let output = conservative_options()
+            .with_emit(Emit::LlvmIr)
+            .with_wasm(wasm)
+            .compile_ll(&ll_text, "program.ll")
+            .expect("WASM calls should be left as-is");
+        let ir = String::from_utf8(output.artifact).expect("LLVM IR should be UTF-8");
+        assert!(ir.contains("call i32 @add_one(i32 1)"));
+        assert!(ir.contains("call void @log(double 5.000000e-01)"));
+    }
+
+    #[test]
+    fn test_compile_ll_leaves_wasm_calls_as_is_without_a_wasm_module() {
+        let ll_text = minimal_qir_with_body(
+            "1",
+            "0",
+            "1",
+            r#"
+declare i32 @add_one(i32) #1
+
+attributes #1 = { "wasm" }
+"#,
+            "  %0 = call i32 @add_one(i32 1)",
+        );
+        let output = conservative_options()
+            .with_emit(Emit::LlvmIr)
+            .compile_ll(&ll_text, "program.ll")
+            .expect("WASM calls should be left as-is without a WASM module");
+        let ir = String::from_utf8(output.artifact).expect("LLVM IR should be UTF-8");
+        assert!(ir.contains("call i32 @add_one(i32 1)"));
+    }
+
     #[test]
     fn test_validate_qir_reports_missing_required_num_results_once() {
         let ll_text = r#"
@@ -4699,8 +6458,9 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err =
-            validate_qir(&bc_bytes, None).expect_err("missing required_num_results should fail");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("missing required_num_results should fail")
+            .to_string();
         assert_eq!(err, "Missing required attribute: `required_num_results`");
     }
 
@@ -4734,9 +6494,11 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).expect("Failed to convert inline QIR to bitcode");
-        let err = validate_qir(&bc_bytes, None).expect_err(
-            "result usage in IR-defined helpers should still respect required_num_results",
-        );
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err(
+                "result usage in IR-defined helpers should still respect required_num_results",
+            )
+            .to_string();
         assert!(err.contains("Result index 0 exceeds required_num_results (0)"));
     }
 
@@ -4752,7 +6514,8 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("result-backed measurements should fail validation without result slots");
+            .expect_err("result-backed measurements should fail validation without result slots")
+            .to_string();
         assert!(err.contains("Result index 0 exceeds required_num_results (0)"));
     }
 
@@ -4768,7 +6531,8 @@ attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_s
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("result reads should fail validation without result slots");
+            .expect_err("result reads should fail validation without result slots")
+            .to_string();
         assert!(err.contains("Result index 0 exceeds required_num_results (0)"));
     }
 
@@ -4788,7 +6552,8 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("result output should fail validation without result slots");
+            .expect_err("result output should fail validation without result slots")
+            .to_string();
         assert!(err.contains("Result index 0 exceeds required_num_results (0)"));
     }
 
@@ -4804,7 +6569,8 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("out-of-bounds result indices should fail during validation");
+            .expect_err("out-of-bounds result indices should fail during validation")
+            .to_string();
         assert!(err.contains("Result index 5 exceeds required_num_results (1)"));
     }
 
@@ -4820,7 +6586,8 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = qir_to_qis(&bc_bytes, 0, "native", None)
-            .expect_err("malformed mz_leaked calls should fail cleanly");
+            .expect_err("malformed mz_leaked calls should fail cleanly")
+            .to_string();
         assert!(err.contains("Malformed mz_leaked call"));
     }
 
@@ -4836,7 +6603,8 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = qir_to_qis(&bc_bytes, 0, "native", None)
-            .expect_err("mz_leaked with the wrong signature should fail cleanly");
+            .expect_err("mz_leaked with the wrong signature should fail cleanly")
+            .to_string();
         assert!(err.contains("Malformed mz_leaked call: expected signature i64 (ptr)"));
     }
 
@@ -4852,7 +6620,8 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = qir_to_qis(&bc_bytes, 0, "native", None)
-            .expect_err("mz_leaked with the wrong return width should fail cleanly");
+            .expect_err("mz_leaked with the wrong return width should fail cleanly")
+            .to_string();
         assert_eq!(
             err,
             "Malformed mz_leaked call: expected signature i64 (ptr)"
@@ -4871,7 +6640,8 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
 
         let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert inline QIR to bitcode");
         let err = qir_to_qis(&bc_bytes, 0, "native", None)
-            .expect_err("mz_leaked with a non-pointer parameter should fail cleanly");
+            .expect_err("mz_leaked with a non-pointer parameter should fail cleanly")
+            .to_string();
         assert_eq!(
             err,
             "Malformed mz_leaked call: expected signature i64 (ptr)"
@@ -5006,7 +6776,7 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
             let bc = qir_ll_to_bc(&ll_text)
                 .map_err(|err| TestCaseError::fail(format!("inline IR should parse: {err}")))?;
             let err = validate_qir(&bc, None)
-                .expect_err("validation should reject missing required attributes");
+                .expect_err("validation should reject missing required attributes").to_string();
             let expected = format!("Missing required attribute: `{missing_attr}`");
             prop_assert!(err.contains(&expected));
         }
@@ -5020,7 +6790,7 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
             if matches!(major, 1 | 2) {
                 prop_assert!(result.is_ok());
             } else {
-                let err = result.expect_err("invalid major versions must fail");
+                let err = result.expect_err("invalid major versions must fail").to_string();
                 prop_assert!(err.contains("Unsupported qir_major_version"));
             }
         }
@@ -5039,7 +6809,7 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
             if valid_first || valid_second {
                 prop_assert!(result.is_ok());
             } else {
-                let err = result.expect_err("all-invalid duplicate major flags must fail");
+                let err = result.expect_err("all-invalid duplicate major flags must fail").to_string();
                 prop_assert!(err.contains("Unsupported qir_major_version"));
             }
         }
@@ -5094,7 +6864,7 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
             if barrier_arity <= required_num_qubits {
                 prop_assert!(result.is_ok());
             } else {
-                let err = result.expect_err("oversized barrier arity must fail");
+                let err = result.expect_err("oversized barrier arity must fail").to_string();
                 prop_assert!(err.contains("Barrier arity"));
             }
         }
@@ -5114,7 +6884,9 @@ declare void @__quantum__rt__result_record_output(%Result*, i8*)
     fn test_zero_qubits_fail_validation() {
         let ll_text = minimal_qir_with_body("0", "1", "1", "", "");
         let bc = qir_ll_to_bc(&ll_text).expect("inline IR should parse");
-        let err = validate_qir(&bc, None).expect_err("validation should reject zero qubits");
+        let err = validate_qir(&bc, None)
+            .expect_err("validation should reject zero qubits")
+            .to_string();
         assert!(err.contains("Entry function must have at least one qubit"));
     }
 
@@ -5203,7 +6975,8 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("called dynamic result functions should fail validation");
+            .expect_err("called dynamic result functions should fail validation")
+            .to_string();
         assert!(
             err.contains(
                 "__quantum__rt__result_allocate requires `dynamic_result_management=true`"
@@ -5237,12 +7010,419 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("called dynamic qubit functions should fail validation");
+            .expect_err("called dynamic qubit functions should fail validation")
+            .to_string();
         assert!(
             err.contains("__quantum__rt__qubit_allocate requires `dynamic_qubit_management=true`")
         );
     }
 
+    #[test]
+    fn test_validate_qir_report_locates_capability_violations() {
+        let ll_text = r#"
+define i64 @Entry_Point_Name() #0 {
+entry:
+  %err = alloca i1, align 1
+  %q = call ptr @__quantum__rt__qubit_allocate(ptr %err)
+  call void @__quantum__rt__qubit_release(ptr %q)
+  ret i64 0
+}
+
+declare ptr @__quantum__rt__qubit_allocate(ptr)
+declare void @__quantum__rt__qubit_release(ptr)
+
+attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="1" "required_num_results"="1" }
+
+!llvm.module.flags = !{!0, !1, !2, !3, !4}
+!0 = !{i32 1, !"qir_major_version", i32 2}
+!1 = !{i32 7, !"qir_minor_version", i32 0}
+!2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+!3 = !{i32 1, !"dynamic_result_management", i1 false}
+!4 = !{i32 1, !"arrays", i1 false}
+"#;
+
+        let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
+        let report = validate_qir_report(&bc_bytes, None).expect("bitcode should parse");
+        assert!(report.has_errors());
+        assert!(
+            report
+                .diagnostics
+                .iter()
+                .all(|d| d.code == codes::MISSING_CAPABILITY && d.severity == Severity::Error)
+        );
+        let allocate = &report.diagnostics[0];
+        assert_eq!(allocate.function.as_deref(), Some("Entry_Point_Name"));
+        assert_eq!(allocate.block.as_deref(), Some("entry"));
+        assert_eq!(
+            allocate.instruction.as_deref(),
+            Some("%q = call ptr @__quantum__rt__qubit_allocate(ptr %err)")
+        );
+        assert_eq!(report.diagnostics.len(), 2);
+
+        let err = validate_qir(&bc_bytes, None).expect_err("validation should fail");
+        assert_eq!(err, crate::Error::Validation(report));
+    }
+
+    #[test]
+    fn test_validate_qir_ll_report_maps_diagnostics_to_source_lines() {
+        let ll_text = minimal_qir_with_body(
+            "1",
+            "1",
+            "1",
+            "declare void @__quantum__qis__mz__body(%Qubit*, %Result*)\ndeclare void @__quantum__qis__mystery__body(%Qubit*)",
+            "  call void @__quantum__qis__mz__body(%Qubit* null, %Result* inttoptr (i64 5 to %Result*))",
+        );
+        let report =
+            validate_qir_ll_report(&ll_text, "program.ll", None).expect("inline IR should parse");
+        let locations: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.location.as_ref().map(ToString::to_string)))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                (
+                    codes::UNSUPPORTED_QIS_FUNCTION,
+                    Some("program.ll:5:14".to_string())
+                ),
+                (
+                    codes::RESULT_INDEX_OUT_OF_RANGE,
+                    Some("program.ll:9:3".to_string())
+                ),
+            ]
+        );
+        assert!(
+            report.diagnostics[1]
+                .to_string()
+                .starts_with("program.ll:9:3: error[E015]: Result index 5 exceeds")
+        );
+    }
+
+    #[test]
+    fn test_compile_ll_prefixes_lowering_errors_with_source_location() {
+        let ll_text = minimal_qir_with_body(
+            "1",
+            "0",
+            "1",
+            "declare i64 @__quantum__qis__mz_leaked__body(i64)",
+            "  %0 = call i64 @__quantum__qis__mz_leaked__body(i64 0)",
+        );
+        let err = conservative_options()
+            .compile_ll(&ll_text, "program.ll")
+            .expect_err("malformed mz_leaked call should fail lowering");
+        assert_eq!(
+            err,
+            crate::Error::Lowering(
+                "program.ll:8:3: Malformed mz_leaked call: expected signature i64 (ptr)"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_is_bitcode_detects_bitcode_magic() {
+        assert!(is_bitcode(&load_fixture_bitcode("tests/data/base.ll")));
+        assert!(is_bitcode(&[0xDE, 0xC0, 0x17, 0x0B, 0, 0, 0, 0]));
+        assert!(!is_bitcode(b"; ModuleID = 'base'"));
+        assert!(!is_bitcode(b"BC"));
+    }
+
+    #[test]
+    fn test_compile_bundle_packs_qis_with_manifest() {
+        let bc_bytes = load_fixture_bitcode("tests/data/base.ll");
+        let output = conservative_options()
+            .compile_bundle(&bc_bytes)
+            .expect("fixture should compile");
+        let bundle = crate::Bundle::from_bytes(&output.artifact).expect("bundle should parse");
+        assert!(is_bitcode(&bundle.qis));
+        assert_eq!(bundle.wasm, None);
+        assert_eq!(
+            bundle.entry_attributes,
+            get_entry_attributes(&bc_bytes).expect("fixture bitcode should parse")
+        );
+        assert_eq!(bundle.capability_flags, crate::CapabilityFlags::default());
+        assert_eq!(bundle.target, "native");
+        assert_eq!(bundle.compiler_version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_compile_with_runs_custom_and_partial_pipelines() {
+        use crate::{Error, Pass, Pipeline};
+
+        let bc_bytes = load_fixture_bitcode("tests/data/base.ll");
+        let mut pipeline = Pipeline::default();
+        pipeline
+            .insert_after(
+                "find-entry",
+                Pass::new("check-entry", |cx| {
+                    assert_eq!(cx.entry_fn_name, "Entry_Point_Name");
+                    Ok(())
+                }),
+            )
+            .expect("stage should exist")
+            .stop_after("lower-entry")
+            .expect("stage should exist");
+        let output = conservative_options()
+            .with_emit(Emit::LlvmIr)
+            .compile_with(&bc_bytes, &pipeline)
+            .expect("fixture should compile");
+        let ll_text = String::from_utf8(output.artifact).expect("IR should be UTF-8");
+        assert!(ll_text.contains("@___user_qir_Entry_Point_Name"));
+        assert!(!ll_text.contains("@qmain"));
+
+        pipeline.push(Pass::new("fail", |_| {
+            Err(Error::Lowering("custom pass failed".to_owned()))
+        }));
+        assert_eq!(
+            conservative_options()
+                .compile_with(&bc_bytes, &pipeline)
+                .err(),
+            Some(Error::Lowering("custom pass failed".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected_before_lowering() {
+        let bc_bytes = load_fixture_bitcode("tests/data/base.ll");
+        assert_eq!(
+            qir_to_qis(&bc_bytes, 4, "native", None).err(),
+            Some(crate::Error::InvalidOption(
+                "Invalid optimization level: 4 (expected one of 0, 1, 2, 3)".to_owned()
+            ))
+        );
+        assert_eq!(
+            qir_to_qis(&bc_bytes, 0, "riscv", None).err(),
+            Some(crate::Error::InvalidOption(
+                "Invalid target architecture: riscv (expected one of aarch64, x86-64, native)"
+                    .to_owned()
+            ))
+        );
+
+        let output = conservative_options()
+            .with_emit(Emit::LlvmIr)
+            .compile(&bc_bytes)
+            .expect("fixture should compile");
+        assert!(String::from_utf8_lossy(&output.artifact).contains("@qmain"));
+    }
+
+    #[test]
+    fn test_get_capability_flags_reads_module_flags() {
+        let ll_text = std::fs::read_to_string("tests/data/dynamic_qubit_array_checked.ll")
+            .expect("Failed to read LLVM IR fixture");
+        let flags = get_capability_flags(&qir_ll_to_bc(&ll_text).unwrap())
+            .expect("fixture bitcode should parse");
+        assert!(flags.dynamic_qubit_management);
+        assert!(!flags.dynamic_result_management);
+        assert!(flags.arrays);
+
+        let base = load_fixture_bitcode("tests/data/base.ll");
+        assert_eq!(
+            get_capability_flags(&base).expect("fixture bitcode should parse"),
+            crate::CapabilityFlags::default()
+        );
+    }
+
+    #[test]
+    fn test_in_memory_module_api_matches_bitcode_api() {
+        let bc_bytes = load_fixture_bitcode("tests/data/adaptive.ll");
+        let ctx = Context::create();
+        let module =
+            parse_bitcode_module(&ctx, &bc_bytes, "bitcode").expect("fixture should parse");
+
+        let report = crate::validate_qir_module(&module, None).expect("validation should run");
+        assert_eq!(
+            report,
+            validate_qir_report(&bc_bytes, None).expect("validation should run")
+        );
+        assert_eq!(
+            crate::get_module_entry_attributes(&module),
+            get_entry_attributes(&bc_bytes).expect("fixture bitcode should parse")
+        );
+        assert_eq!(
+            crate::get_module_capability_flags(&module),
+            get_capability_flags(&bc_bytes).expect("fixture bitcode should parse")
+        );
+
+        let options = conservative_options().with_emit(Emit::LlvmIr);
+        let lowered = options
+            .lower_module(&ctx, &module)
+            .expect("fixture should lower");
+        let expected = options.compile(&bc_bytes).expect("fixture should compile");
+        assert_eq!(
+            crate::opt::emit(
+                lowered.module,
+                Emit::LlvmIr,
+                options.opt_level(),
+                options.target()
+            )
+            .expect("lowered module should emit"),
+            expected.artifact
+        );
+        assert_eq!(lowered.warnings, expected.warnings);
+    }
+
+    /// Bitcode of every fixture at the top of `tests/data`.
+    fn all_fixture_bitcode() -> Vec<Vec<u8>> {
+        let mut paths: Vec<_> = std::fs::read_dir("tests/data")
+            .expect("Failed to list fixtures")
+            .map(|entry| entry.expect("Failed to list fixtures").path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ll"))
+            .collect();
+        paths.sort();
+        paths
+            .iter()
+            .map(|path| {
+                let ll_text = std::fs::read_to_string(path).expect("Failed to read fixture");
+                qir_ll_to_bc(&ll_text).expect("Failed to convert fixture to bitcode")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compile_many_matches_sequential_compilation() {
+        let options = crate::CompileOptions::new().with_emit(Emit::LlvmIr);
+        let programs = all_fixture_bitcode();
+        let expected: Vec<_> = programs
+            .iter()
+            .map(|bc_bytes| options.compile(bc_bytes))
+            .collect();
+
+        // Repeat each fixture so that identical programs race each other.
+        let repeated: Vec<_> = (0..4).flat_map(|_| programs.iter()).collect();
+        for jobs in [
+            None,
+            std::num::NonZeroUsize::new(1),
+            std::num::NonZeroUsize::new(8),
+        ] {
+            let results = crate::compile_many(&repeated, &options, jobs);
+            assert_eq!(results.len(), repeated.len());
+            for (result, expected) in results.iter().zip(expected.iter().cycle()) {
+                assert_eq!(result, expected);
+            }
+        }
+        assert!(crate::compile_many::<&[u8]>(&[], &options, None).is_empty());
+    }
+
+    #[test]
+    fn test_public_api_is_safe_to_call_from_many_threads() {
+        let (opt_level, target) = conservative_translation_settings();
+        let programs = all_fixture_bitcode();
+        let expected: Vec<_> = programs
+            .iter()
+            .map(|bc_bytes| qir_to_qis(bc_bytes, opt_level, target, None))
+            .collect();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for (bc_bytes, expected) in programs.iter().zip(&expected) {
+                        assert_eq!(&qir_to_qis(bc_bytes, opt_level, target, None), expected);
+                        validate_qir_report(bc_bytes, None).expect("validation should run");
+                        get_entry_attributes(bc_bytes).expect("fixture bitcode should parse");
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_compile_ll_returns_warnings_and_denies_them_on_request() {
+        let ll_text = minimal_qir_with_body(
+            "1",
+            "1",
+            "1",
+            "declare void @__quantum__qis__m__body(%Qubit*, %Result*)",
+            "  call void @__quantum__qis__m__body(%Qubit* null, %Result* null)",
+        );
+        let output = conservative_options()
+            .compile_ll(&ll_text, "program.ll")
+            .expect("warnings should not fail compilation");
+        assert!(is_bitcode(&output.artifact));
+        let warnings: Vec<_> = output
+            .warnings
+            .iter()
+            .map(|d| {
+                (
+                    d.code,
+                    d.severity,
+                    d.function.as_deref(),
+                    d.location.as_ref().map(ToString::to_string),
+                )
+            })
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (codes::NO_GLOBALS, Severity::Warning, None, None),
+                (
+                    codes::QSHARP_M_SYNONYM,
+                    Severity::Warning,
+                    Some("Entry_Point_Name"),
+                    Some("program.ll:8:3".to_string())
+                ),
+            ]
+        );
+
+        let err = conservative_options()
+            .with_deny_warnings(true)
+            .compile_ll(&ll_text, "program.ll")
+            .expect_err("denied warnings should fail compilation");
+        assert!(matches!(
+            &err,
+            crate::Error::Validation(report)
+                if report.errors().map(|d| d.code).eq([codes::NO_GLOBALS, codes::QSHARP_M_SYNONYM])
+        ));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_validate_qir_report_warns_about_target_triple_and_data_layout() {
+        let ll_text = format!(
+            "target datalayout = \"e-m:e-i64:64-n32:64-S128\"\ntarget triple = \"x86_64-unknown-linux-gnu\"\n{}",
+            minimal_qir_with_body("1", "0", "1", "", "")
+        );
+        let bc_bytes = qir_ll_to_bc(&ll_text).unwrap();
+        let report = validate_qir_report(&bc_bytes, None).expect("bitcode should parse");
+        assert!(!report.has_errors());
+        assert_eq!(
+            report.warnings().map(|d| d.code).collect::<Vec<_>>(),
+            vec![codes::DATA_LAYOUT_PRESENT, codes::TARGET_TRIPLE_PRESENT]
+        );
+        assert!(validate_qir(&bc_bytes, None).is_ok());
+        assert!(matches!(
+            report.into_result(true),
+            Err(crate::Error::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_qir_report_attaches_codes_to_declarations_and_flags() {
+        let ll_text = minimal_qir_with_body(
+            "1",
+            "1",
+            "7",
+            "declare void @__quantum__qis__mystery__body(%Qubit*)",
+            "",
+        );
+        let bc_bytes = qir_ll_to_bc(&ll_text).unwrap();
+        let report = validate_qir_report(&bc_bytes, None).expect("bitcode should parse");
+        let codes_found: Vec<_> = report.errors().map(|d| d.code).collect();
+        assert_eq!(
+            codes_found,
+            vec![
+                codes::UNSUPPORTED_QIS_FUNCTION,
+                codes::UNSUPPORTED_MODULE_FLAG_VALUE
+            ]
+        );
+        assert_eq!(
+            report.diagnostics[0].function.as_deref(),
+            Some("__quantum__qis__mystery__body")
+        );
+        assert!(report.diagnostics[1].function.is_none());
+        assert_eq!(report.warnings().count(), 0);
+    }
+
     #[test]
     fn test_validate_qir_rejects_malformed_dynamic_qubit_allocate_signature() {
         let ll_text = r#"
@@ -5268,7 +7448,8 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("malformed dynamic runtime declaration should fail validation");
+            .expect_err("malformed dynamic runtime declaration should fail validation")
+            .to_string();
         assert!(
             err.contains("Malformed QIR RT function declaration: __quantum__rt__qubit_allocate")
         );
@@ -5295,8 +7476,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 "#;
 
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err =
-            validate_qir(&bc_bytes, None).expect_err("unsupported RT declarations should fail");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("unsupported RT declarations should fail")
+            .to_string();
         assert!(err.contains("Unsupported QIR RT function: __quantum__rt__mystery"));
     }
 
@@ -5447,7 +7629,8 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("mismatched result array output backing should fail validation");
+            .expect_err("mismatched result array output backing should fail validation")
+            .to_string();
         assert!(err.contains(
             "__quantum__rt__result_array_record_output requires a fixed-size backing array"
         ));
@@ -5479,7 +7662,8 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
         let err = validate_qir(&bc_bytes, None)
-            .expect_err("oversized result array output length should fail validation");
+            .expect_err("oversized result array output length should fail validation")
+            .to_string();
         assert!(err.contains(
             "__quantum__rt__result_array_record_output requires an array length that fits in i32 for RESULT_ARRAY output"
         ));
@@ -5511,7 +7695,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 false}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err("fixture should fail validation");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("fixture should fail validation")
+            .to_string();
         assert!(
             err.contains("__quantum__rt__result_allocate is only supported in the entry block")
         );
@@ -5546,7 +7732,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 false}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err("fixture should fail validation");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("fixture should fail validation")
+            .to_string();
         assert!(
             err.contains("__quantum__rt__result_allocate is only supported in the entry block")
         );
@@ -5575,7 +7763,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 false}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err("fixture should fail validation");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("fixture should fail validation")
+            .to_string();
         assert!(err.contains("Input QIR must not define internal helper function"));
         assert!(err.contains("qir_qis.qubit_allocate"));
     }
@@ -5602,7 +7792,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 true}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err("fixture should fail validation");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("fixture should fail validation")
+            .to_string();
         assert!(
             err.contains("__quantum__rt__qubit_array_allocate requires a fixed-size backing array")
         );
@@ -5631,7 +7823,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 true}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err("fixture should fail validation");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("fixture should fail validation")
+            .to_string();
         assert!(
             err.contains(
                 "__quantum__rt__result_array_allocate requires a fixed-size backing array"
@@ -5662,7 +7856,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 true}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err("fixture should fail validation");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("fixture should fail validation")
+            .to_string();
         assert!(
             err.contains("__quantum__rt__qubit_array_release requires a fixed-size backing array")
         );
@@ -5691,7 +7887,9 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 true}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err("fixture should fail validation");
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err("fixture should fail validation")
+            .to_string();
         assert!(
             err.contains("__quantum__rt__result_array_release requires a fixed-size backing array")
         );
@@ -5773,9 +7971,11 @@ attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeli
 !4 = !{i32 1, !"arrays", i1 true}
 "#;
         let bc_bytes = qir_ll_to_bc(ll_text).unwrap();
-        let err = validate_qir(&bc_bytes, None).expect_err(
-            "non-zero GEP-backed pointer should not count as a fixed-size array backing",
-        );
+        let err = validate_qir(&bc_bytes, None)
+            .expect_err(
+                "non-zero GEP-backed pointer should not count as a fixed-size array backing",
+            )
+            .to_string();
         assert!(err.contains(
             "__quantum__rt__qubit_array_allocate requires a fixed-size backing array allocated as [N x ptr]"
         ));
@@ -5959,7 +8159,7 @@ attributes #0 = {{ {attrs} }}
             if backing_len == requested_len {
                 prop_assert!(result.is_ok());
             } else {
-                let err = result.expect_err("mismatched fixed-size backing should fail");
+                let err = result.expect_err("mismatched fixed-size backing should fail").to_string();
                 prop_assert!(err.contains("requires a fixed-size backing array"));
                 prop_assert!(err.contains("requested length"));
             }
@@ -6017,7 +8217,7 @@ attributes #0 = {{ {attrs} }}
             if arrays_enabled && dynamic_enabled {
                 prop_assert!(result.is_ok());
             } else {
-                let err = result.expect_err("missing capability flag combination should fail");
+                let err = result.expect_err("missing capability flag combination should fail").to_string();
                 prop_assert!(err.contains(expected_error));
             }
         }
@@ -1,8 +1,10 @@
 use inkwell::module::Module;
+
+use crate::Error;
 #[cfg(windows)]
 use llvm_sys::analysis::{LLVMVerifierFailureAction, LLVMVerifyModule};
 
-pub fn verify_module(module: &Module, error_prefix: &str) -> Result<(), String> {
+pub fn verify_module(module: &Module, error_prefix: &str) -> Result<(), Error> {
     #[cfg(windows)]
     let verify_rc = unsafe {
         LLVMVerifyModule(
@@ -20,16 +22,18 @@ pub fn verify_module(module: &Module, error_prefix: &str) -> Result<(), String>
         // Re-checked locally on Windows Arm64 on March 23, 2026: asking LLVM
         // to populate the verifier message pointer led to process instability,
         // so keep the Windows path on the null-pointer fallback for now.
-        return Err(format!(
-            "{error_prefix}: LLVM verifier failed (message pointer unavailable on this platform; rerun on Linux/macOS for detailed verifier diagnostics)"
-        ));
+        return Err(Error::Verifier {
+            stage: error_prefix.to_string(),
+            message: "LLVM verifier failed (message pointer unavailable on this platform; rerun on Linux/macOS for detailed verifier diagnostics)".to_string(),
+        });
     }
 
     #[cfg(not(windows))]
     {
-        module
-            .verify()
-            .map_err(|err| format!("{error_prefix}: {err}"))
+        module.verify().map_err(|err| Error::Verifier {
+            stage: error_prefix.to_string(),
+            message: err.to_string(),
+        })
     }
 }
 
@@ -50,6 +54,10 @@ mod tests {
 
         let err = verify_module(&module, "verification failed")
             .expect_err("unterminated function should fail verification");
-        assert!(err.contains("verification failed"));
+        assert!(matches!(
+            &err,
+            crate::Error::Verifier { stage, .. } if stage == "verification failed"
+        ));
+        assert!(err.to_string().contains("verification failed"));
     }
 }
@@ -1,54 +1,1404 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
 use std::fs;
+use std::io::{self, Read, Write};
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::process::exit;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use qir_qis::{
-    DEFAULT_OPT_LEVEL, DEFAULT_TARGET, get_entry_attributes, qir_ll_to_bc, qir_to_qis, validate_qir,
+    Bundle, CapabilityFlags, CompileOptions, Diagnostic, Emit, Error, OptLevel, Pipeline,
+    StageDump, Target,
+    bundle::{MANIFEST_ENTRY, QIS_ENTRY, WASM_ENTRY},
+    create_module_from_ir_text,
+    diagnostics::codes,
+    explain, get_module_capability_flags, get_module_entry_attributes, is_bitcode,
+    parse_bitcode_module, validate_qir_ll_module, validate_qir_module,
 };
 
-use bpaf::Bpaf;
+use inkwell::context::Context;
+use inkwell::module::Module;
+
+use bpaf::{Bpaf, Parser};
+
+/// Exit status when the program is invalid, including denied warnings.
+const EXIT_VALIDATION_FAILED: i32 = 1;
+/// Exit status when a valid program fails to compile.
+const EXIT_COMPILE_FAILED: i32 = 2;
+/// Exit status when files cannot be read or written, or options conflict.
+const EXIT_IO_FAILED: i32 = 3;
+
+/// Version of the `--format json` schema, bumped on incompatible changes.
+const JSON_SCHEMA_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Bpaf)]
 #[bpaf(options)]
-struct Args {
+enum Cli {
+    /// Validate a QIR program without compiling it
+    #[bpaf(command)]
+    Validate(#[bpaf(external(validate_args))] ValidateArgs),
+
+    /// Validate and compile a QIR program to QIS bitcode
+    #[bpaf(command)]
+    Compile(#[bpaf(external(compile_args))] CompileArgs),
+
+    /// Validate and compile a QIR program into a bundle with its WASM module and manifest
+    #[bpaf(command)]
+    Bundle(#[bpaf(external(bundle_args))] BundleArgs),
+
+    /// Extract the QIS bitcode, WASM module and manifest from a bundle
+    #[bpaf(command)]
+    Unbundle(#[bpaf(external(unbundle_args))] UnbundleArgs),
+
+    /// Print the entry point attributes as `name=value` lines
+    #[bpaf(command)]
+    Attrs(#[bpaf(external(input_args))] InputArgs),
+
+    /// Print the capabilities enabled by the module flags as `name=value` lines
+    #[bpaf(command)]
+    Flags(#[bpaf(external(input_args))] InputArgs),
+
+    /// Explain a diagnostic code, e.g. `qir-qis explain E021`
+    #[bpaf(command)]
+    Explain {
+        /// Diagnostic code, as printed in `severity[CODE]`
+        #[bpaf(positional("CODE"))]
+        code: String,
+    },
+}
+
+/// How command results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Human-readable diagnostics on stderr, results on stdout.
+    Text,
+    /// A single JSON object on stdout, see the README for the schema.
+    Json,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Invalid format: {s} (expected text or json)")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Bpaf)]
+struct InputArgs {
+    /// Output format: text or json
+    #[bpaf(
+        long("format"),
+        argument("FORMAT"),
+        fallback(Format::Text),
+        display_fallback
+    )]
+    format: Format,
+
+    /// Path to input QIR file (.ll or .bc), or `-` for stdin
+    #[bpaf(positional("FILE"))]
+    path: String,
+}
+
+#[derive(Debug, Clone, Bpaf)]
+struct ValidateArgs {
+    #[bpaf(external(wasm_path))]
+    wasm: Option<String>,
+
+    /// Treat warnings as errors
+    #[bpaf(long("deny-warnings"), switch)]
+    deny_warnings: bool,
+
+    #[bpaf(external(input_args))]
+    input: InputArgs,
+}
+
+#[derive(Debug, Clone, Bpaf)]
+struct CompileArgs {
+    /// Optimization level (0, 1, 2, 3)
+    #[bpaf(
+        short('O'),
+        long("opt-level"),
+        argument("LEVEL"),
+        fallback(OptLevel::default()),
+        display_fallback
+    )]
+    opt_level: OptLevel,
+
+    #[allow(clippy::doc_markdown)]
+    /// Target architecture: aarch64, x86-64 or native
+    #[bpaf(
+        short('t'),
+        long("target"),
+        argument("TARGET"),
+        fallback(Target::default()),
+        display_fallback
+    )]
+    target: Target,
+
+    /// Artifact to emit: bc, ll, asm or obj
+    #[bpaf(
+        long("emit"),
+        argument("KIND"),
+        fallback(Emit::Bitcode),
+        display_fallback
+    )]
+    emit: Emit,
+
+    /// Output path, or `-` for stdout [default: <FILE>.qis.<ext>, or stdout when reading stdin]
+    #[bpaf(short('o'), long("output"), argument("PATH"))]
+    output: Option<String>,
+
+    #[bpaf(external(wasm_path))]
+    wasm: Option<String>,
+
+    /// Number of files to compile in parallel [default: available CPUs]
+    #[bpaf(short('j'), long("jobs"), argument("N"))]
+    jobs: Option<usize>,
+
+    /// Keep compiling the remaining files after one fails
+    #[bpaf(long("keep-going"), switch)]
+    keep_going: bool,
+
+    /// Treat warnings as errors
+    #[bpaf(long("deny-warnings"), switch)]
+    deny_warnings: bool,
+
+    /// Print the IR to stderr after STAGE (verify-input, decompose, find-entry, rename-entry, qubit-array, lower-entry, lower-ir-functions, free-qubits, qmain-wrapper, verify, metadata, optimize, prune-helpers)
+    #[bpaf(long("print-after"), argument("STAGE"))]
+    print_after: Vec<String>,
+
+    /// Print the IR to stderr after every stage
+    #[bpaf(long("print-after-all"), switch)]
+    print_after_all: bool,
+
+    /// Stop after STAGE and emit the module as left by it
+    #[bpaf(long("stop-after"), argument("STAGE"))]
+    stop_after: Option<String>,
+
+    /// Output format: text or json
+    #[bpaf(
+        long("format"),
+        argument("FORMAT"),
+        fallback(Format::Text),
+        display_fallback
+    )]
+    format: Format,
+
+    /// Input QIR files (.ll or .bc) or directories to search for them, or `-` for stdin
+    #[bpaf(positional("FILE"), some("expected at least one input file"))]
+    inputs: Vec<String>,
+}
+
+impl CompileArgs {
+    /// The compilation settings given on the command line.
+    fn options(&self, wasm: Option<Vec<u8>>) -> CompileOptions {
+        compile_options(self.opt_level, self.target, wasm)
+            .with_emit(self.emit)
+            .with_deny_warnings(self.deny_warnings)
+    }
+}
+
+#[derive(Debug, Clone, Bpaf)]
+struct BundleArgs {
     /// Optimization level (0, 1, 2, 3)
-    #[bpaf(short('O'), long("opt-level"), fallback(DEFAULT_OPT_LEVEL))]
-    opt_level: u32,
+    #[bpaf(
+        short('O'),
+        long("opt-level"),
+        argument("LEVEL"),
+        fallback(OptLevel::default()),
+        display_fallback
+    )]
+    opt_level: OptLevel,
 
     #[allow(clippy::doc_markdown)]
-    /// Target architecture (e.g., "aarch64", "x86-64", "native")
-    #[bpaf(short('t'), long("target"), fallback(String::from(DEFAULT_TARGET)))]
-    target: String,
+    /// Target architecture: aarch64, x86-64 or native
+    #[bpaf(
+        short('t'),
+        long("target"),
+        argument("TARGET"),
+        fallback(Target::default()),
+        display_fallback
+    )]
+    target: Target,
+
+    /// Output path, or `-` for stdout [default: <FILE>.qis.bundle, or stdout when reading stdin]
+    #[bpaf(short('o'), long("output"), argument("PATH"))]
+    output: Option<String>,
+
+    #[bpaf(external(wasm_path))]
+    wasm: Option<String>,
+
+    /// Treat warnings as errors
+    #[bpaf(long("deny-warnings"), switch)]
+    deny_warnings: bool,
+
+    #[bpaf(external(input_args))]
+    input: InputArgs,
+}
+
+#[derive(Debug, Clone, Bpaf)]
+struct UnbundleArgs {
+    /// Directory to extract into [default: <FILE> without its extension]
+    #[bpaf(short('o'), long("output"), argument("DIR"))]
+    output: Option<String>,
+
+    /// Output format: text or json
+    #[bpaf(
+        long("format"),
+        argument("FORMAT"),
+        fallback(Format::Text),
+        display_fallback
+    )]
+    format: Format,
 
-    /// Path to input LLVM IR file (.ll)
-    #[bpaf(positional)]
-    ll_path: String,
+    /// Path to the bundle, or `-` for stdin
+    #[bpaf(positional("FILE"))]
+    path: String,
 }
 
 fn main() {
     // Initialize logging
     env_logger::init();
 
-    let args = args().run();
+    match cli().run() {
+        Cli::Validate(args) => execute("validate", &args.input, |outcome| {
+            validate_file(outcome, &args)
+        }),
+        Cli::Compile(args) => compile_all(&args),
+        Cli::Bundle(args) => execute("bundle", &args.input, |outcome| bundle(outcome, &args)),
+        Cli::Unbundle(args) => {
+            let mut outcome = Outcome::new("unbundle", args.format, &args.path);
+            let status = unbundle(&mut outcome, &args).err().unwrap_or(0);
+            outcome.finish(status)
+        }
+        Cli::Attrs(args) => execute("attrs", &args, |outcome| {
+            let input = read_input(outcome, &args.path)?;
+            let ctx = Context::create();
+            let module = input
+                .module(&ctx)
+                .map_err(|err| outcome.fail_with(&err, EXIT_VALIDATION_FAILED))?;
+            outcome.entry_attributes = Some(get_module_entry_attributes(&module));
+            Ok(())
+        }),
+        Cli::Flags(args) => execute("flags", &args, |outcome| {
+            let input = read_input(outcome, &args.path)?;
+            let ctx = Context::create();
+            let module = input
+                .module(&ctx)
+                .map_err(|err| outcome.fail_with(&err, EXIT_VALIDATION_FAILED))?;
+            outcome.capability_flags = Some(get_module_capability_flags(&module));
+            Ok(())
+        }),
+        Cli::Explain { code } => {
+            if let Some(explanation) = explain(&code) {
+                print!("{explanation}");
+            } else {
+                eprintln!("Unknown diagnostic code: {code}");
+                exit(1);
+            }
+        }
+    }
+}
 
-    let ll_path = Path::new(&args.ll_path);
-    let ll_text = fs::read_to_string(ll_path).expect("Failed to read input file");
+/// Run `command`, then report its [`Outcome`] and exit with its status.
+fn execute(
+    name: &'static str,
+    args: &InputArgs,
+    command: impl FnOnce(&mut Outcome) -> Result<(), i32>,
+) -> ! {
+    let mut outcome = Outcome::new(name, args.format, &args.path);
+    let status = command(&mut outcome).err().unwrap_or(0);
+    outcome.finish(status)
+}
 
-    let bc_bytes = qir_ll_to_bc(&ll_text).unwrap();
-    if let Err(err) = validate_qir(&bc_bytes, None) {
-        eprintln!("QIR validation failed: {err:?}");
-        exit(1);
+/// Everything a command reports, printed as text or as one JSON object.
+struct Outcome {
+    command: &'static str,
+    format: Format,
+    input: String,
+    started: Instant,
+    /// Wall-clock time of the command, once it has stopped.
+    total: Option<Duration>,
+    diagnostics: Vec<Diagnostic>,
+    entry_attributes: Option<BTreeMap<String, Option<String>>>,
+    capability_flags: Option<CapabilityFlags>,
+    output: Option<String>,
+    timings: Vec<(&'static str, Duration)>,
+    /// Kind, code (for library errors) and message of the failure.
+    error: Option<(&'static str, Option<&'static str>, String)>,
+}
+
+impl Outcome {
+    fn new(command: &'static str, format: Format, input: &str) -> Self {
+        Self {
+            command,
+            format,
+            input: input.to_owned(),
+            started: Instant::now(),
+            total: None,
+            diagnostics: Vec::new(),
+            entry_attributes: None,
+            capability_flags: None,
+            output: None,
+            timings: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Record a failure and return the exit status to report it with.
+    fn fail(&mut self, kind: &'static str, message: impl Into<String>, status: i32) -> i32 {
+        self.error = Some((kind, None, message.into()));
+        status
     }
 
-    println!("{:#?}", get_entry_attributes(&bc_bytes));
+    /// Record a failure with its stable code from the catalog.
+    fn fail_coded(
+        &mut self,
+        kind: &'static str,
+        code: &'static str,
+        message: impl Into<String>,
+        status: i32,
+    ) -> i32 {
+        self.error = Some((kind, Some(code), message.into()));
+        status
+    }
+
+    fn fail_with(&mut self, err: &Error, status: i32) -> i32 {
+        self.fail_coded(err.kind(), err.code(), err.to_string(), status)
+    }
 
-    let qis_module = match qir_to_qis(&bc_bytes, args.opt_level, &args.target, None) {
-        Ok(qis_module) => qis_module,
+    /// Run `step`, recording how long it took under `name`.
+    fn timed<T>(&mut self, name: &'static str, step: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = step();
+        self.timings.push((name, start.elapsed()));
+        result
+    }
+
+    /// Stop the clock for [`Outcome::total`].
+    fn stop(&mut self) {
+        self.total = Some(self.started.elapsed());
+    }
+
+    fn total(&self) -> Duration {
+        self.total.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    fn finish(self, status: i32) -> ! {
+        match self.format {
+            Format::Text => self.print_text(),
+            Format::Json => println!("{}", self.to_json(status)),
+        }
+        exit(status)
+    }
+
+    fn print_text(&self) {
+        // Diagnostics from LLVM IR text are printed as
+        // `file.ll:line:column: severity[code]: message` so that editors can
+        // jump to the offending instruction.
+        for diagnostic in &self.diagnostics {
+            eprintln!("{diagnostic}");
+        }
+        for (name, value) in self.entry_attributes.iter().flatten() {
+            match value {
+                Some(value) => println!("{name}={value}"),
+                None => println!("{name}"),
+            }
+        }
+        for (name, enabled) in self
+            .capability_flags
+            .map(CapabilityFlags::entries)
+            .iter()
+            .flatten()
+        {
+            println!("{name}={enabled}");
+        }
+        if let Some((_, _, message)) = &self.error {
+            eprintln!("{message}");
+        }
+    }
+
+    fn to_json(&self, status: i32) -> String {
+        let error = self.error.as_ref().map_or_else(
+            || "null".to_owned(),
+            |(kind, code, message)| {
+                json_object([
+                    ("kind", json_string(kind)),
+                    ("code", json_optional_string(*code)),
+                    ("message", json_string(message)),
+                ])
+            },
+        );
+        let entry_attributes =
+            self.entry_attributes.as_ref().map_or_else(
+                || "null".to_owned(),
+                |attrs| {
+                    json_object(attrs.iter().map(|(name, value)| {
+                        (name.as_str(), json_optional_string(value.as_deref()))
+                    }))
+                },
+            );
+        let capability_flags = self.capability_flags.map_or_else(
+            || "null".to_owned(),
+            |flags| {
+                json_object(
+                    flags
+                        .entries()
+                        .into_iter()
+                        .map(|(name, enabled)| (name, enabled.to_string())),
+                )
+            },
+        );
+        let total = self.total();
+        let timings = json_object(
+            self.timings
+                .iter()
+                .copied()
+                .chain([("total", total)])
+                .map(|(name, duration)| (name, json_millis(duration))),
+        );
+        json_object([
+            ("schema_version", JSON_SCHEMA_VERSION.to_string()),
+            ("command", json_string(self.command)),
+            ("input", json_string(&self.input)),
+            ("success", (status == 0).to_string()),
+            ("exit_code", status.to_string()),
+            ("error", error),
+            (
+                "diagnostics",
+                json_array(self.diagnostics.iter().map(diagnostic_json)),
+            ),
+            ("entry_attributes", entry_attributes),
+            ("capability_flags", capability_flags),
+            ("output", json_optional_string(self.output.as_deref())),
+            ("timings_ms", timings),
+        ])
+    }
+}
+
+/// A QIR program read from a file or stdin.
+enum Input {
+    Text { name: String, text: String },
+    Bitcode(Vec<u8>),
+}
+
+impl Input {
+    /// Parse the program into a module of `ctx`, which is then validated,
+    /// inspected and compiled in place.
+    fn module<'ctx>(&self, ctx: &'ctx Context) -> Result<Module<'ctx>, Error> {
+        match self {
+            Self::Text { name, text } => create_module_from_ir_text(ctx, text, name),
+            Self::Bitcode(bytes) => parse_bitcode_module(ctx, bytes, "bitcode"),
+        }
+    }
+}
+
+/// Read `path` (or stdin for `-`), detecting bitcode by its magic number.
+fn read_input(outcome: &mut Outcome, path: &str) -> Result<Input, i32> {
+    let name = if path == "-" { "<stdin>" } else { path };
+    let bytes = read_bytes(outcome, path)?;
+    if is_bitcode(&bytes) {
+        return Ok(Input::Bitcode(bytes));
+    }
+    String::from_utf8(bytes)
+        .map(|text| Input::Text {
+            name: name.to_owned(),
+            text,
+        })
+        .map_err(|_| {
+            outcome.fail_coded(
+                "parse",
+                codes::PARSE_FAILED,
+                format!(
+                    "Failed to read {name}: input is neither LLVM bitcode nor UTF-8 LLVM IR text"
+                ),
+                EXIT_VALIDATION_FAILED,
+            )
+        })
+}
+
+/// Read the raw contents of `path`, or stdin for `-`.
+fn read_bytes(outcome: &mut Outcome, path: &str) -> Result<Vec<u8>, i32> {
+    let name = if path == "-" { "<stdin>" } else { path };
+    outcome
+        .timed("read", || {
+            if path == "-" {
+                let mut bytes = Vec::new();
+                io::stdin().lock().read_to_end(&mut bytes).map(|_| bytes)
+            } else {
+                fs::read(path)
+            }
+        })
+        .map_err(|err| {
+            outcome.fail(
+                "io",
+                format!("Failed to read {name}: {err}"),
+                EXIT_IO_FAILED,
+            )
+        })
+}
+
+/// `--wasm`, the WASM module providing the functions called by the program.
+fn wasm_path() -> impl Parser<Option<String>> {
+    bpaf::long("wasm")
+        .help("WASM module providing the functions called by the program")
+        .argument("WASM")
+        .optional()
+}
+
+/// Read the WASM module passed with `--wasm`, if any.
+fn read_wasm(path: Option<&str>) -> Result<Option<Vec<u8>>, String> {
+    path.map(|path| fs::read(path).map_err(|err| format!("Failed to read {path}: {err}")))
+        .transpose()
+}
+
+/// Options compiling for `target` at `opt_level` against the `wasm` module.
+fn compile_options(opt_level: OptLevel, target: Target, wasm: Option<Vec<u8>>) -> CompileOptions {
+    let options = CompileOptions::new()
+        .with_opt_level(opt_level)
+        .with_target(target);
+    match wasm {
+        Some(wasm) => options.with_wasm(wasm),
+        None => options,
+    }
+}
+
+/// Write `bytes` to `path`, or to stdout for `-`.
+fn write_output(path: &str, bytes: &[u8]) -> io::Result<()> {
+    if path == "-" {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(bytes)?;
+        stdout.flush()
+    } else {
+        fs::write(path, bytes)
+    }
+}
+
+/// Parse `input` into a module of `ctx`, failing validation if it cannot be
+/// parsed.
+fn parse<'ctx>(
+    outcome: &mut Outcome,
+    input: &Input,
+    ctx: &'ctx Context,
+) -> Result<Module<'ctx>, i32> {
+    outcome.timed("parse", || input.module(ctx)).map_err(|err| {
+        outcome.fail_coded(
+            err.kind(),
+            err.code(),
+            format!("QIR validation failed: {err}"),
+            EXIT_VALIDATION_FAILED,
+        )
+    })
+}
+
+/// Validate the program named by `args` against its `--wasm` module.
+fn validate_file(outcome: &mut Outcome, args: &ValidateArgs) -> Result<(), i32> {
+    let wasm = read_wasm(args.wasm.as_deref())
+        .map_err(|message| outcome.fail("io", message, EXIT_IO_FAILED))?;
+    let input = read_input(outcome, &args.input.path)?;
+    let ctx = Context::create();
+    let module = parse(outcome, &input, &ctx)?;
+    validate(
+        outcome,
+        &input,
+        &module,
+        wasm.as_deref(),
+        args.deny_warnings,
+    )
+}
+
+/// Record validation diagnostics and fail if the program is invalid.
+fn validate(
+    outcome: &mut Outcome,
+    input: &Input,
+    module: &Module<'_>,
+    wasm: Option<&[u8]>,
+    deny_warnings: bool,
+) -> Result<(), i32> {
+    let report = outcome.timed("validate", || match input {
+        Input::Text { name, text } => validate_qir_ll_module(module, text, name, wasm),
+        Input::Bitcode(_) => validate_qir_module(module, wasm),
+    });
+    let mut report = report.map_err(|err| {
+        outcome.fail_coded(
+            err.kind(),
+            err.code(),
+            format!("QIR validation failed: {err}"),
+            EXIT_VALIDATION_FAILED,
+        )
+    })?;
+    if deny_warnings {
+        report.deny_warnings();
+    }
+    let has_errors = report.has_errors();
+    outcome.diagnostics.extend(report.diagnostics);
+    if has_errors {
+        return Err(outcome.fail_coded(
+            "validation",
+            codes::VALIDATION_FAILED,
+            "QIR validation failed",
+            EXIT_VALIDATION_FAILED,
+        ));
+    }
+    Ok(())
+}
+
+/// The path to write the artifact for `path` to: `output` if given, else
+/// `path` with `extension`, or stdout when reading stdin.
+fn output_path(
+    outcome: &mut Outcome,
+    output: Option<&str>,
+    path: &str,
+    extension: &str,
+) -> Result<String, i32> {
+    let output_path = output.map_or_else(
+        || {
+            if path == "-" {
+                "-".to_owned()
+            } else {
+                Path::new(path)
+                    .with_extension(extension)
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        },
+        str::to_owned,
+    );
+    if outcome.format == Format::Json && output_path == "-" {
+        return Err(outcome.fail(
+            "io",
+            "Cannot write the artifact to stdout with `--format json`; pass `-o <path>`",
+            EXIT_IO_FAILED,
+        ));
+    }
+    Ok(output_path)
+}
+
+fn compile(
+    outcome: &mut Outcome,
+    args: &CompileArgs,
+    options: &CompileOptions,
+    pipeline: &Pipeline,
+    path: &str,
+) -> Result<(), i32> {
+    let output_path = output_path(outcome, args.output.as_deref(), path, args.emit.extension())?;
+    let input = read_input(outcome, path)?;
+    let ctx = Context::create();
+    let module = parse(outcome, &input, &ctx)?;
+    validate(
+        outcome,
+        &input,
+        &module,
+        options.wasm(),
+        options.deny_warnings(),
+    )?;
+
+    if outcome.format == Format::Json {
+        outcome.entry_attributes = Some(get_module_entry_attributes(&module));
+        outcome.capability_flags = Some(get_module_capability_flags(&module));
+    }
+
+    let artifact = compile_input(outcome, &input, &ctx, &module, options, pipeline)?;
+    write_artifact(outcome, output_path, &artifact)
+}
+
+/// Compile the validated `module` parsed from `input` in place to its
+/// artifact with `pipeline`, recording its warnings and printing its stage
+/// dumps to stderr.
+fn compile_input<'ctx>(
+    outcome: &mut Outcome,
+    input: &Input,
+    ctx: &'ctx Context,
+    module: &Module<'ctx>,
+    options: &CompileOptions,
+    pipeline: &Pipeline,
+) -> Result<Vec<u8>, i32> {
+    let result = outcome.timed("compile", || match input {
+        Input::Text { name, text } => {
+            options.compile_ll_module_with(ctx, module, text, name, pipeline)
+        }
+        Input::Bitcode(_) => options.compile_module_with(ctx, module, pipeline),
+    });
+    let output = match result {
+        Ok(output) => output,
         Err(err) => {
-            eprintln!("QIR compilation failed: {err}");
-            exit(1);
+            // Print the IR recorded before a failing stage, to bisect it.
+            let (err, dumps) = err.split_dumps();
+            print_dumps(&dumps);
+            if let Error::Validation(report) = err {
+                outcome.diagnostics.extend(report.diagnostics);
+                return Err(outcome.fail_coded(
+                    "validation",
+                    codes::VALIDATION_FAILED,
+                    "QIR compilation failed: warnings denied",
+                    EXIT_VALIDATION_FAILED,
+                ));
+            }
+            return Err(outcome.fail_coded(
+                err.kind(),
+                err.code(),
+                format!("QIR compilation failed: {err}"),
+                EXIT_COMPILE_FAILED,
+            ));
         }
     };
-    let qis_path = ll_path.with_extension("qis.bc");
-    fs::write(&qis_path, qis_module).expect("Failed to write output file");
+    print_dumps(&output.dumps);
+    outcome.diagnostics.extend(output.warnings);
+    Ok(output.artifact)
+}
+
+/// Print the IR recorded after pipeline stages to stderr.
+fn print_dumps(dumps: &[StageDump]) {
+    if !dumps.is_empty() {
+        // One write per file, so that dumps of parallel compilations do not
+        // interleave.
+        let dumps: String = dumps.iter().map(ToString::to_string).collect();
+        eprint!("{dumps}");
+    }
+}
+
+/// Write `bytes` to `output_path` and record it as the command's output.
+fn write_artifact(outcome: &mut Outcome, output_path: String, bytes: &[u8]) -> Result<(), i32> {
+    write_output(&output_path, bytes).map_err(|err| {
+        outcome.fail(
+            "io",
+            format!("Failed to write {output_path}: {err}"),
+            EXIT_IO_FAILED,
+        )
+    })?;
+    outcome.output = Some(output_path);
+    Ok(())
+}
+
+/// Validate and compile a program, then write it as a bundle together with
+/// its WASM module and manifest.
+fn bundle(outcome: &mut Outcome, args: &BundleArgs) -> Result<(), i32> {
+    let path = &args.input.path;
+    let output_path = output_path(outcome, args.output.as_deref(), path, "qis.bundle")?;
+    let wasm = read_wasm(args.wasm.as_deref())
+        .map_err(|message| outcome.fail("io", message, EXIT_IO_FAILED))?;
+    let options = compile_options(args.opt_level, args.target, wasm)
+        .with_deny_warnings(args.deny_warnings);
+    let input = read_input(outcome, path)?;
+    let ctx = Context::create();
+    let module = parse(outcome, &input, &ctx)?;
+    validate(outcome, &input, &module, options.wasm(), args.deny_warnings)?;
+
+    // Lowering rewrites the entry point attributes, so read them first.
+    let mut bundle = Bundle::for_module(&module, args.target.as_str(), options.wasm());
+    bundle.qis = compile_input(
+        outcome,
+        &input,
+        &ctx,
+        &module,
+        &options,
+        &Pipeline::default(),
+    )?;
+    if outcome.format == Format::Json {
+        outcome.entry_attributes = Some(bundle.entry_attributes.clone());
+        outcome.capability_flags = Some(bundle.capability_flags);
+    }
+    write_artifact(outcome, output_path, &bundle.to_bytes())
+}
+
+/// Extract the entries of a bundle into a directory.
+fn unbundle(outcome: &mut Outcome, args: &UnbundleArgs) -> Result<(), i32> {
+    let path = &args.path;
+    let dir = match &args.output {
+        Some(dir) => dir.clone(),
+        None if path == "-" => {
+            return Err(outcome.fail(
+                "io",
+                "Pass `-o <dir>` to extract a bundle read from stdin",
+                EXIT_IO_FAILED,
+            ));
+        }
+        None => Path::new(path)
+            .with_extension("")
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let bytes = read_bytes(outcome, path)?;
+    let bundle = Bundle::from_bytes(&bytes)
+        .map_err(|err| outcome.fail_with(&err, EXIT_VALIDATION_FAILED))?;
+
+    let manifest = bundle.manifest();
+    let entries = [
+        (QIS_ENTRY, Some(bundle.qis.as_slice())),
+        (WASM_ENTRY, bundle.wasm.as_deref()),
+        (MANIFEST_ENTRY, Some(manifest.as_bytes())),
+    ];
+    fs::create_dir_all(&dir)
+        .and_then(|()| {
+            entries
+                .into_iter()
+                .filter_map(|(name, data)| data.map(|data| (name, data)))
+                .try_for_each(|(name, data)| fs::write(Path::new(&dir).join(name), data))
+        })
+        .map_err(|err| {
+            outcome.fail(
+                "io",
+                format!("Failed to write {dir}: {err}"),
+                EXIT_IO_FAILED,
+            )
+        })?;
+    if outcome.format == Format::Json {
+        outcome.entry_attributes = Some(bundle.entry_attributes);
+        outcome.capability_flags = Some(bundle.capability_flags);
+    }
+    outcome.output = Some(dir);
+    Ok(())
+}
+
+/// Compile a single file, or every file of a batch when given several inputs
+/// or a directory.
+fn compile_all(args: &CompileArgs) -> ! {
+    let pipeline = Pipeline::debugging(
+        &args.print_after,
+        args.print_after_all,
+        args.stop_after.as_deref(),
+    );
+    if let [path] = args.inputs.as_slice()
+        && !Path::new(path).is_dir()
+    {
+        let mut outcome = Outcome::new("compile", args.format, path);
+        let status = pipeline
+            .map_err(|err| outcome.fail_with(&err, EXIT_IO_FAILED))
+            .and_then(|pipeline| {
+                let wasm = read_wasm(args.wasm.as_deref())
+                    .map_err(|message| outcome.fail("io", message, EXIT_IO_FAILED))?;
+                let options = args.options(wasm);
+                compile(&mut outcome, args, &options, &pipeline, path)
+            })
+            .err()
+            .unwrap_or(0);
+        outcome.finish(status)
+    }
+    let mut batch = Batch::new(args.format);
+    let status = pipeline
+        .map_err(|err| batch.fail_with(&err, EXIT_IO_FAILED))
+        .and_then(|pipeline| compile_batch(&mut batch, args, &pipeline))
+        .err()
+        .unwrap_or(0);
+    batch.finish(status)
+}
+
+/// Results of compiling several files in one invocation.
+struct Batch {
+    format: Format,
+    started: Instant,
+    /// Every input file with its outcome and exit status, `None` if skipped.
+    results: Vec<(String, Option<(Outcome, i32)>)>,
+    /// Kind, code (for library errors) and message of the failure.
+    error: Option<(&'static str, Option<&'static str>, String)>,
+}
+
+impl Batch {
+    fn new(format: Format) -> Self {
+        Self {
+            format,
+            started: Instant::now(),
+            results: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Record a failure that prevents compiling any file.
+    fn fail(&mut self, kind: &'static str, message: impl Into<String>, status: i32) -> i32 {
+        self.error = Some((kind, None, message.into()));
+        status
+    }
+
+    fn fail_with(&mut self, err: &Error, status: i32) -> i32 {
+        self.error = Some((err.kind(), Some(err.code()), err.to_string()));
+        status
+    }
+
+    fn finish(self, status: i32) -> ! {
+        match self.format {
+            Format::Text => self.print_text(),
+            Format::Json => println!("{}", self.to_json(status)),
+        }
+        exit(status)
+    }
+
+    /// Print per-file diagnostics to stderr and a summary table to stdout.
+    fn print_text(&self) {
+        if let Some((_, _, message)) = &self.error {
+            eprintln!("{message}");
+            return;
+        }
+        for (outcome, _) in self
+            .results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref())
+        {
+            for diagnostic in &outcome.diagnostics {
+                eprintln!("{diagnostic}");
+            }
+        }
+
+        let (mut compiled, mut failed, mut skipped) = (0_usize, 0_usize, 0_usize);
+        println!("{:<7} {:>10}  FILE", "STATUS", "TIME (ms)");
+        for (path, result) in &self.results {
+            match result {
+                Some((outcome, 0)) => {
+                    compiled = compiled.saturating_add(1);
+                    println!(
+                        "{:<7} {:>10}  {path} -> {}",
+                        "ok",
+                        json_millis(outcome.total()),
+                        outcome.output.as_deref().unwrap_or("-"),
+                    );
+                }
+                Some((outcome, _)) => {
+                    failed = failed.saturating_add(1);
+                    println!(
+                        "{:<7} {:>10}  {path}: {}",
+                        "FAILED",
+                        json_millis(outcome.total()),
+                        outcome.error.as_ref().map_or("", |(_, _, message)| message),
+                    );
+                }
+                None => {
+                    skipped = skipped.saturating_add(1);
+                    println!("{:<7} {:>10}  {path}", "skipped", "-");
+                }
+            }
+        }
+        println!(
+            "\n{} files: {compiled} compiled, {failed} failed, {skipped} skipped",
+            self.results.len()
+        );
+    }
+
+    fn to_json(&self, status: i32) -> String {
+        let error = self.error.as_ref().map_or_else(
+            || "null".to_owned(),
+            |(kind, code, message)| {
+                json_object([
+                    ("kind", json_string(kind)),
+                    ("code", json_optional_string(*code)),
+                    ("message", json_string(message)),
+                ])
+            },
+        );
+        let results = self
+            .results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref())
+            .map(|(outcome, status)| outcome.to_json(*status));
+        let skipped = self
+            .results
+            .iter()
+            .filter(|(_, result)| result.is_none())
+            .map(|(path, _)| json_string(path));
+        json_object([
+            ("schema_version", JSON_SCHEMA_VERSION.to_string()),
+            ("command", json_string("compile")),
+            ("success", (status == 0).to_string()),
+            ("exit_code", status.to_string()),
+            ("error", error),
+            ("results", json_array(results)),
+            ("skipped", json_array(skipped)),
+            (
+                "timings_ms",
+                json_object([("total", json_millis(self.started.elapsed()))]),
+            ),
+        ])
+    }
+}
+
+/// Compile every input file on `--jobs` worker threads.
+///
+/// Each compilation creates its own LLVM `Context`, so workers share no LLVM
+/// state. Unless `--keep-going` is set, workers stop picking up new files
+/// after the first failure and the remaining files are reported as skipped.
+fn compile_batch(batch: &mut Batch, args: &CompileArgs, pipeline: &Pipeline) -> Result<(), i32> {
+    if args.output.is_some() {
+        return Err(batch.fail(
+            "io",
+            "`-o` cannot be used when compiling several inputs or a directory",
+            EXIT_IO_FAILED,
+        ));
+    }
+    let files = collect_inputs(&args.inputs)
+        .map_err(|message| batch.fail("io", message, EXIT_IO_FAILED))?;
+    if files.is_empty() {
+        return Err(batch.fail(
+            "io",
+            "No .ll or .bc files found in the given inputs",
+            EXIT_IO_FAILED,
+        ));
+    }
+    let wasm = read_wasm(args.wasm.as_deref())
+        .map_err(|message| batch.fail("io", message, EXIT_IO_FAILED))?;
+    let options = &args.options(wasm);
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+        .clamp(1, files.len());
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let mut results: Vec<Option<(Outcome, i32)>> = files.iter().map(|_| None).collect();
+    let (next, failed, files_ref) = (&next, &failed, &files);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut done = Vec::new();
+                    while args.keep_going || !failed.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = files_ref.get(index) else {
+                            break;
+                        };
+                        let mut outcome = Outcome::new("compile", args.format, path);
+                        let status = compile(&mut outcome, args, options, pipeline, path)
+                            .err()
+                            .unwrap_or(0);
+                        outcome.stop();
+                        if status != 0 {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                        done.push((index, outcome, status));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for worker in workers {
+            match worker.join() {
+                Ok(done) => {
+                    for (index, outcome, status) in done {
+                        if let Some(slot) = results.get_mut(index) {
+                            *slot = Some((outcome, status));
+                        }
+                    }
+                }
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+    });
+
+    // Report the highest exit status of any file, so that I/O failures win
+    // over compile failures, which win over validation failures.
+    let status = results
+        .iter()
+        .flatten()
+        .map(|(_, status)| *status)
+        .max()
+        .unwrap_or(0);
+    batch.results = files.into_iter().zip(results).collect();
+    if status != 0 {
+        return Err(status);
+    }
+    Ok(())
+}
+
+/// Expand directories in `inputs` into the QIR files they contain, recursively
+/// and in sorted order.
+fn collect_inputs(inputs: &[String]) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input == "-" {
+            return Err("Cannot read stdin (`-`) when compiling several inputs".to_owned());
+        }
+        let path = Path::new(input);
+        if path.is_dir() {
+            collect_dir(path, &mut files)
+                .map_err(|err| format!("Failed to read {input}: {err}"))?;
+        } else {
+            files.push(input.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn collect_dir(dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_dir(&path, files)?;
+        } else if is_qir_source(&path) {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+/// `.ll` and `.bc` files, skipping artifacts written by earlier compilations.
+fn is_qir_source(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    (name.ends_with(".ll") || name.ends_with(".bc"))
+        && !Emit::ALL
+            .iter()
+            .any(|emit| name.ends_with(&format!(".{}", emit.extension())))
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len().saturating_add(2));
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if ch < ' ' => {
+                let _ = write!(out, "\\u{:04x}", u32::from(ch));
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_optional_string(value: Option<&str>) -> String {
+    value.map_or_else(|| "null".to_owned(), json_string)
+}
+
+fn json_millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// Render `fields` as a JSON object; values must already be valid JSON.
+fn json_object<'a>(fields: impl IntoIterator<Item = (&'a str, String)>) -> String {
+    let fields: Vec<_> = fields
+        .into_iter()
+        .map(|(name, value)| format!("{}:{value}", json_string(name)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Render `items` as a JSON array; items must already be valid JSON.
+fn json_array(items: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","))
+}
+
+fn diagnostic_json(diagnostic: &Diagnostic) -> String {
+    let location = diagnostic.location.as_ref().map_or_else(
+        || "null".to_owned(),
+        |location| {
+            json_object([
+                ("file", json_string(&location.file)),
+                ("line", location.line.to_string()),
+                ("column", location.column.to_string()),
+            ])
+        },
+    );
+    json_object([
+        ("code", json_string(diagnostic.code)),
+        ("severity", json_string(&diagnostic.severity.to_string())),
+        ("message", json_string(&diagnostic.message)),
+        (
+            "function",
+            json_optional_string(diagnostic.function.as_deref()),
+        ),
+        ("block", json_optional_string(diagnostic.block.as_deref())),
+        (
+            "instruction",
+            json_optional_string(diagnostic.instruction.as_deref()),
+        ),
+        ("location", location),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use std::path::Path;
+
+    use super::{
+        Diagnostic, EXIT_VALIDATION_FAILED, Format, Outcome, Parser, Pipeline, compile,
+        compile_args, diagnostic_json, is_qir_source, json_string, read_wasm, validate_args,
+        validate_file,
+    };
+    use proptest::prelude::*;
+    use qir_qis::{SourceLocation, diagnostics::codes, is_bitcode};
+    use wasm_encoder::{
+        CodeSection, ExportKind, ExportSection, Function, FunctionSection, Instruction,
+        Module as WasmModule, TypeSection, ValType,
+    };
+
+    /// A WASM module exporting `add(i32, i32) -> i32`.
+    fn add_module() -> Vec<u8> {
+        let mut types = TypeSection::new();
+        types
+            .ty()
+            .function([ValType::I32, ValType::I32], [ValType::I32]);
+        let mut functions = FunctionSection::new();
+        functions.function(0);
+        let mut exports = ExportSection::new();
+        exports.export("add", ExportKind::Func, 0);
+        let mut body = Function::new(vec![]);
+        body.instruction(&Instruction::LocalGet(0))
+            .instruction(&Instruction::LocalGet(1))
+            .instruction(&Instruction::I32Add)
+            .instruction(&Instruction::End);
+        let mut code = CodeSection::new();
+        code.function(&body);
+        let mut wasm = WasmModule::new();
+        wasm.section(&types)
+            .section(&functions)
+            .section(&exports)
+            .section(&code);
+        wasm.finish()
+    }
+
+    /// Write `tests/data/base.ll` with a call to the WASM function `add`,
+    /// declared as taking and returning `ty`, to `dir/name`.
+    fn write_program(dir: &Path, name: &str, ty: &str) -> String {
+        let ll_text = std::fs::read_to_string("tests/data/base.ll")
+            .expect("Failed to read fixture")
+            .replace(
+                "  br label %measurements\n",
+                &format!("  %sum = call {ty} @add({ty} 1, {ty} 2)\n  br label %measurements\n"),
+            );
+        let ll_text = format!(
+            "{ll_text}\ndeclare {ty} @add({ty}, {ty}) #2\n\nattributes #2 = {{ \"wasm\" }}\n"
+        );
+        let path = dir.join(name);
+        std::fs::write(&path, ll_text).expect("Failed to write program");
+        path.to_str()
+            .expect("temporary path should be UTF-8")
+            .to_owned()
+    }
+
+    #[test]
+    fn test_validate_and_compile_against_a_wasm_module() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let wasm = dir.path().join("add.wasm");
+        std::fs::write(&wasm, add_module()).expect("Failed to write WASM module");
+        let wasm = wasm.to_str().expect("temporary path should be UTF-8");
+        let good = write_program(dir.path(), "good.ll", "i32");
+        let bad = write_program(dir.path(), "bad.ll", "i64");
+
+        for (path, expected) in [(&good, Ok(())), (&bad, Err(EXIT_VALIDATION_FAILED))] {
+            let args = validate_args()
+                .to_options()
+                .run_inner(&["--wasm", wasm, path.as_str()][..])
+                .expect("arguments should parse");
+            let mut outcome = Outcome::new("validate", Format::Json, path);
+            assert_eq!(validate_file(&mut outcome, &args), expected);
+            assert_eq!(
+                outcome
+                    .diagnostics
+                    .iter()
+                    .any(|d| d.code == codes::WASM_SIGNATURE_MISMATCH),
+                expected.is_err()
+            );
+
+            let output = format!("{path}.qis.bc");
+            let args = compile_args()
+                .to_options()
+                .run_inner(
+                    &[
+                        "-O", "0", "-t", "native", "--wasm", wasm, "-o", &output, path,
+                    ][..],
+                )
+                .expect("arguments should parse");
+            let options = args.options(read_wasm(args.wasm.as_deref()).expect("WASM should read"));
+            let mut outcome = Outcome::new("compile", Format::Json, path);
+            assert_eq!(
+                compile(&mut outcome, &args, &options, &Pipeline::default(), path),
+                expected
+            );
+            assert_eq!(
+                std::fs::read(&output).is_ok_and(|bytes| is_bitcode(&bytes)),
+                expected.is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_string_escapes_control_characters() {
+        assert_eq!(
+            json_string("a \"b\"\\\n\t\u{1}"),
+            r#""a \"b\"\\\n\t\u0001""#
+        );
+    }
+
+    #[test]
+    fn test_json_string_round_trips_through_a_json_parser() {
+        let control: String = ('\0'..' ').chain(['\u{7f}']).collect();
+        for value in [
+            "",
+            "plain",
+            "\"quoted\" \\ back\\slash /",
+            control.as_str(),
+            "caf\u{e9} \u{6f22}\u{5b57} \u{1f600} \u{2028}\u{2029} \u{feff}",
+        ] {
+            let parsed: String =
+                serde_json::from_str(&json_string(value)).expect("output should be valid JSON");
+            assert_eq!(parsed, value);
+        }
+
+        let mut diagnostic = Diagnostic::error(codes::RESULT_INDEX_OUT_OF_RANGE, "bad \"é\"\n");
+        diagnostic.function = Some("ma\u{1}in".to_string());
+        let parsed: serde_json::Value = serde_json::from_str(&diagnostic_json(&diagnostic))
+            .expect("diagnostic should be valid JSON");
+        assert_eq!(parsed["message"], "bad \"é\"\n");
+        assert_eq!(parsed["function"], "ma\u{1}in");
+        assert!(parsed["location"].is_null());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_json_string_round_trips(value in "\\PC*|[\\x00-\\x1f\"\\\\]*") {
+            let parsed: String = serde_json::from_str(&json_string(&value))
+                .map_err(|err| TestCaseError::fail(format!("invalid JSON: {err}")))?;
+            prop_assert_eq!(parsed, value);
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_json_includes_location() {
+        let mut diagnostic = Diagnostic::error(codes::RESULT_INDEX_OUT_OF_RANGE, "out of range");
+        diagnostic.function = Some("main".to_string());
+        diagnostic.location = Some(SourceLocation {
+            file: "program.ll".to_string(),
+            line: 9,
+            column: 3,
+        });
+        assert_eq!(
+            diagnostic_json(&diagnostic),
+            r#"{"code":"E015","severity":"error","message":"out of range","function":"main","block":null,"instruction":null,"location":{"file":"program.ll","line":9,"column":3}}"#
+        );
+    }
+
+    #[test]
+    fn test_is_qir_source_skips_compiled_artifacts() {
+        assert!(is_qir_source(Path::new("tests/data/base.ll")));
+        assert!(is_qir_source(Path::new("program.bc")));
+        assert!(!is_qir_source(Path::new("tests/data/base.qis.bc")));
+        assert!(!is_qir_source(Path::new("tests/data/base.qis.ll")));
+        assert!(!is_qir_source(Path::new("README.md")));
+    }
 }
@@ -2,17 +2,24 @@ use inkwell::OptimizationLevel;
 use inkwell::module::Module;
 use inkwell::passes::PassBuilderOptions;
 use inkwell::targets::{
-    CodeModel, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple,
+    CodeModel, FileType, InitializationConfig, RelocMode, Target as LlvmTarget, TargetMachine,
+    TargetTriple,
 };
-use std::sync::Mutex;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
 
-// Ensure LLVM targets are initialized only once to prevent SIGBUS crashes
-static TARGET_INIT: std::sync::LazyLock<Mutex<()>> = std::sync::LazyLock::new(|| {
-    Target::initialize_x86(&InitializationConfig::default());
-    Target::initialize_aarch64(&InitializationConfig::default());
+use crate::{Error, OptLevel, Target};
+
+// Ensure LLVM targets are initialized only once to prevent SIGBUS crashes.
+// This is the only global LLVM state the translation touches: forcing it
+// blocks concurrent callers until the registry is complete, after which it
+// is only read, so translations in separate contexts may run in parallel.
+static TARGET_INIT: LazyLock<()> = LazyLock::new(|| {
+    LlvmTarget::initialize_x86(&InitializationConfig::default());
+    LlvmTarget::initialize_aarch64(&InitializationConfig::default());
     #[cfg(not(windows))]
-    let _ = Target::initialize_native(&InitializationConfig::default());
-    Mutex::new(())
+    let _ = LlvmTarget::initialize_native(&InitializationConfig::default());
 });
 
 type TargetConfig<'a> = (&'a str, &'a str, &'a str, &'a str);
@@ -31,25 +38,27 @@ const X86_CONFIG: TargetConfig = ("x86-64", "x86-64", "x86_64-unknown-linux-gnu"
 /// Sentinel config for native codegen target
 const NATIVE_CONFIG: TargetConfig = ("", "", "", "");
 
-fn get_target_config(target: &str) -> Result<TargetConfig<'_>, String> {
+const fn get_target_config(target: Target) -> TargetConfig<'static> {
     match target {
-        "x86-64" => Ok(X86_CONFIG),
-        "aarch64" => Ok(AARCH64_CONFIG),
-        "native" => Ok(NATIVE_CONFIG),
-        _ => Err(format!("Invalid target architecture: {target}")),
+        Target::X86_64 => X86_CONFIG,
+        Target::Aarch64 => AARCH64_CONFIG,
+        Target::Native => NATIVE_CONFIG,
     }
 }
 
-fn get_target_machine(target: &str, opt_level: OptimizationLevel) -> Result<TargetMachine, String> {
+fn get_target_machine(
+    target: Target,
+    opt_level: OptimizationLevel,
+) -> Result<TargetMachine, String> {
     // Ensure targets are initialized
-    let _ = *TARGET_INIT;
+    LazyLock::force(&TARGET_INIT);
 
-    let target_config = get_target_config(target)?;
+    let target_config = get_target_config(target);
     let reloc_mode = RelocMode::PIC;
     let code_model = CodeModel::Default;
     if target_config == NATIVE_CONFIG {
         let triple = TargetMachine::get_default_triple();
-        let target = Target::from_triple(&triple)
+        let target = LlvmTarget::from_triple(&triple)
             .map_err(|e| format!("Failed to create target from triple: {e}"))?;
         #[cfg(windows)]
         let (cpu, features) = ("generic".to_string(), String::new());
@@ -67,8 +76,8 @@ fn get_target_machine(target: &str, opt_level: OptimizationLevel) -> Result<Targ
             .ok_or("Failed to create target machine")?)
     } else {
         let (name, cpu, triple, features) = target_config;
-        let target =
-            Target::from_name(name).ok_or_else(|| format!("Failed to create target: {name}"))?;
+        let target = LlvmTarget::from_name(name)
+            .ok_or_else(|| format!("Failed to create target: {name}"))?;
         Ok(target
             .create_target_machine(
                 &TargetTriple::create(triple),
@@ -82,50 +91,207 @@ fn get_target_machine(target: &str, opt_level: OptimizationLevel) -> Result<Targ
     }
 }
 
+/// Kind of artifact produced by a QIR to QIS translation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Emit {
+    /// LLVM bitcode (`bc`).
+    #[default]
+    Bitcode,
+    /// Textual LLVM IR (`ll`).
+    LlvmIr,
+    /// Target assembly (`asm`).
+    Assembly,
+    /// Target object file (`obj`).
+    Object,
+}
+
+impl Emit {
+    /// Every emit kind, in the order they are documented.
+    pub const ALL: [Self; 4] = [Self::Bitcode, Self::LlvmIr, Self::Assembly, Self::Object];
+
+    /// Short name accepted by [`Emit::from_str`], e.g. `"bc"`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Bitcode => "bc",
+            Self::LlvmIr => "ll",
+            Self::Assembly => "asm",
+            Self::Object => "obj",
+        }
+    }
+
+    /// Conventional file extension for the artifact, e.g. `"qis.bc"`.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Bitcode => "qis.bc",
+            Self::LlvmIr => "qis.ll",
+            Self::Assembly => "qis.s",
+            Self::Object => "qis.o",
+        }
+    }
+}
+
+impl fmt::Display for Emit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Emit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|emit| emit.as_str() == s)
+            .ok_or_else(|| format!("Invalid emit kind: {s} (expected one of bc, ll, asm, obj)"))
+    }
+}
+
+/// Serialize `module` as the requested artifact.
+///
+/// Assembly and object files are generated with the same target machine
+/// configuration as [`optimize`].
+///
+/// # Errors
+/// Returns [`Error::Target`] if the target machine cannot be created or code
+/// generation fails.
+pub fn emit(
+    module: &Module,
+    emit: Emit,
+    opt_level: OptLevel,
+    target: Target,
+) -> Result<Vec<u8>, Error> {
+    emit_with(module, emit, opt_level, target, None)
+}
+
+/// Like [`emit`], reusing `target_machine` if one is given.
+pub(crate) fn emit_with(
+    module: &Module,
+    emit: Emit,
+    opt_level: OptLevel,
+    target: Target,
+    target_machine: Option<&TargetMachine>,
+) -> Result<Vec<u8>, Error> {
+    let file_type = match emit {
+        Emit::Bitcode => {
+            return Ok(crate::memory_buffer_to_owned_bytes(
+                &module.write_bitcode_to_memory(),
+            ));
+        }
+        Emit::LlvmIr => return Ok(module.print_to_string().to_bytes().to_vec()),
+        Emit::Assembly => FileType::Assembly,
+        Emit::Object => FileType::Object,
+    };
+    let created;
+    let target_machine = match target_machine {
+        Some(target_machine) => target_machine,
+        None => {
+            created = get_target_machine(target, codegen_opt_level(opt_level))
+                .map_err(|e| Error::Target(format!("Failed to get target machine: {e}")))?;
+            &created
+        }
+    };
+    module.set_triple(&target_machine.get_triple());
+    module.set_data_layout(&target_machine.get_target_data().get_data_layout());
+    let buffer = target_machine
+        .write_to_memory_buffer(module, file_type)
+        .map_err(|e| Error::Target(format!("Failed to emit {emit}: {e}")))?;
+    Ok(buffer.as_slice().to_vec())
+}
+
+/// Create the target machine that [`optimize`] and [`emit`] would create for
+/// these settings, or `None` if they would not need one.
+///
+/// # Errors
+/// Returns [`Error::Target`] if the target machine cannot be created.
+pub(crate) fn session_target_machine(
+    opt_level: OptLevel,
+    target: Target,
+    emit: Emit,
+) -> Result<Option<TargetMachine>, Error> {
+    // Optimized conversion fails on Windows before any target machine is
+    // used, and O0 avoids creating one; see `optimize`.
+    let optimizes = opt_level != OptLevel::O0 && !cfg!(windows);
+    if !optimizes && matches!(emit, Emit::Bitcode | Emit::LlvmIr) {
+        return Ok(None);
+    }
+    get_target_machine(target, codegen_opt_level(opt_level))
+        .map(Some)
+        .map_err(|e| Error::Target(format!("Failed to get target machine: {e}")))
+}
+
+const fn codegen_opt_level(opt_level: OptLevel) -> OptimizationLevel {
+    match opt_level {
+        OptLevel::O0 => OptimizationLevel::None,
+        OptLevel::O1 => OptimizationLevel::Less,
+        OptLevel::O2 => OptimizationLevel::Default,
+        OptLevel::O3 => OptimizationLevel::Aggressive,
+    }
+}
+
 /// Optimize the given LLVM module using the specified optimization level and target architecture.
 ///
 /// # Errors
-/// Returns an error if module verification fails
-pub fn optimize(module: &Module, opt_level: u32, target: &str) -> Result<(), String> {
+/// Returns [`Error::Target`] if the target machine cannot be created or the
+/// optimization passes fail.
+pub fn optimize(module: &Module, opt_level: OptLevel, target: Target) -> Result<(), Error> {
+    optimize_with(module, opt_level, target, None)
+}
+
+/// Like [`optimize`], reusing `target_machine` if one is given.
+pub(crate) fn optimize_with(
+    module: &Module,
+    opt_level: OptLevel,
+    target: Target,
+    target_machine: Option<&TargetMachine>,
+) -> Result<(), Error> {
     #[cfg(windows)]
-    if opt_level > 0 {
-        return Err(format!(
+    if opt_level != OptLevel::O0 {
+        return Err(Error::Target(format!(
             "Optimized QIR-to-QIS conversion is currently unavailable on Windows with the LLVM 21 integration. Re-run with `opt_level=0` and preferably `target=\"native\"` (requested opt_level={opt_level}, target=\"{target}\")."
-        ));
+        )));
     }
 
     // O0 preserves semantics without running transformation passes.
     // Avoid creating a TargetMachine in this mode; TargetMachine teardown has
     // caused access violations in some Windows environments.
-    if opt_level == 0 {
-        let target_config =
-            get_target_config(target).map_err(|e| format!("Failed to get target machine: {e}"))?;
-        #[cfg(not(windows))]
-        {
-            let triple = if target_config == NATIVE_CONFIG {
-                TargetMachine::get_default_triple()
-            } else {
-                let (_, _, triple, _) = target_config;
-                TargetTriple::create(triple)
-            };
-            module.set_triple(&triple);
+    let opt_str = match opt_level {
+        OptLevel::O0 => {
+            #[cfg(not(windows))]
+            {
+                let target_config = get_target_config(target);
+                let triple = if target_config == NATIVE_CONFIG {
+                    TargetMachine::get_default_triple()
+                } else {
+                    let (_, _, triple, _) = target_config;
+                    TargetTriple::create(triple)
+                };
+                module.set_triple(&triple);
+            }
+            #[cfg(windows)]
+            // Keep the Windows O0 path as a no-op.
+            // Attempting to update the module triple here reproduced the same
+            // access-violation crash in CI on March 17, 2026 that originally
+            // motivated the O0 fast path.
+            let _ = (module, target);
+            return Ok(());
+        }
+        OptLevel::O1 => "default<O1>",
+        OptLevel::O2 => "default<O2>",
+        OptLevel::O3 => "default<O3>",
+    };
+    let created;
+    let target_machine = match target_machine {
+        Some(target_machine) => target_machine,
+        None => {
+            created = get_target_machine(target, codegen_opt_level(opt_level))
+                .map_err(|e| Error::Target(format!("Failed to get target machine: {e}")))?;
+            &created
         }
-        #[cfg(windows)]
-        // Keep the Windows O0 path as a no-op after target validation.
-        // Attempting to update the module triple here reproduced the same
-        // access-violation crash in CI on March 17, 2026 that originally
-        // motivated the O0 fast path.
-        let _ = (module, target_config);
-        return Ok(());
-    }
-
-    let (opt, opt_str) = match opt_level {
-        1 => (OptimizationLevel::Less, "default<O1>"),
-        3 => (OptimizationLevel::Aggressive, "default<O3>"),
-        _ => (OptimizationLevel::Default, "default<O2>"),
     };
-    let target_machine = get_target_machine(target, opt)
-        .map_err(|e| format!("Failed to get target machine: {e}"))?;
 
     let (data_layout, triple) = {
         (
@@ -136,8 +302,8 @@ pub fn optimize(module: &Module, opt_level: u32, target: &str) -> Result<(), Str
     module.set_triple(&triple);
     module.set_data_layout(&data_layout);
     module
-        .run_passes(opt_str, &target_machine, PassBuilderOptions::create())
-        .map_err(|e| format!("Failed to run passes: {e}"))?;
+        .run_passes(opt_str, target_machine, PassBuilderOptions::create())
+        .map_err(|e| Error::Target(format!("Failed to run passes: {e}")))?;
     Ok(())
 }
 
@@ -145,8 +311,11 @@ pub fn optimize(module: &Module, opt_level: u32, target: &str) -> Result<(), Str
 mod tests {
     #![allow(clippy::expect_used)]
 
+    use super::Emit;
     #[cfg(not(windows))]
-    use super::optimize;
+    use super::{emit, optimize};
+    #[cfg(not(windows))]
+    use crate::{OptLevel, Target};
     #[cfg(not(windows))]
     use inkwell::context::Context;
     #[cfg(not(windows))]
@@ -157,11 +326,13 @@ mod tests {
     fn test_optimize_o0_sets_native_and_explicit_triples_differently() {
         let native_ctx = Context::create();
         let native_module = native_ctx.create_module("native");
-        optimize(&native_module, 0, "native").expect("native O0 optimize should succeed");
+        optimize(&native_module, OptLevel::O0, Target::Native)
+            .expect("native O0 optimize should succeed");
 
         let aarch64_ctx = Context::create();
         let aarch64_module = aarch64_ctx.create_module("aarch64");
-        optimize(&aarch64_module, 0, "aarch64").expect("aarch64 O0 optimize should succeed");
+        optimize(&aarch64_module, OptLevel::O0, Target::Aarch64)
+            .expect("aarch64 O0 optimize should succeed");
 
         let native_triple = native_module
             .get_triple()
@@ -191,9 +362,48 @@ mod tests {
         let context = Context::create();
         let module = context.create_module("x86_64");
 
-        optimize(&module, 0, "x86-64").expect("explicit x86-64 O0 optimize should succeed");
+        optimize(&module, OptLevel::O0, Target::X86_64)
+            .expect("explicit x86-64 O0 optimize should succeed");
 
         let triple = module.get_triple().as_str().to_string_lossy().into_owned();
         assert_eq!(triple, "x86_64-unknown-linux-gnu");
     }
+
+    #[test]
+    fn test_emit_kinds_round_trip_through_names() {
+        for kind in Emit::ALL {
+            assert_eq!(kind.as_str().parse::<Emit>(), Ok(kind));
+        }
+        assert_eq!(Emit::default(), Emit::Bitcode);
+        assert_eq!(
+            "exe".parse::<Emit>(),
+            Err("Invalid emit kind: exe (expected one of bc, ll, asm, obj)".to_string())
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_emit_produces_each_artifact_kind() {
+        let context = Context::create();
+        let module = context.create_module("emit");
+        let function = module.add_function("f", context.void_type().fn_type(&[], false), None);
+        let builder = context.create_builder();
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+        builder.build_return(None).expect("return should build");
+
+        let bitcode = emit(&module, Emit::Bitcode, OptLevel::O0, Target::X86_64)
+            .expect("bitcode should emit");
+        assert!(crate::is_bitcode(&bitcode));
+
+        let ir = emit(&module, Emit::LlvmIr, OptLevel::O0, Target::X86_64).expect("IR should emit");
+        assert!(String::from_utf8_lossy(&ir).contains("define void @f()"));
+
+        let asm = emit(&module, Emit::Assembly, OptLevel::O0, Target::X86_64)
+            .expect("assembly should emit");
+        assert!(String::from_utf8_lossy(&asm).contains("f:"));
+
+        let obj =
+            emit(&module, Emit::Object, OptLevel::O0, Target::X86_64).expect("object should emit");
+        assert!(obj.starts_with(b"\x7fELF"));
+    }
 }
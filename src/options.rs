@@ -0,0 +1,613 @@
+//! Typed settings of a QIR to QIS translation.
+//!
+//! [`CompileOptions`] gathers every setting of a translation, with each value
+//! checked when it is built rather than half-way through the translation:
+//!
+//! ```no_run
+//! use qir_qis::{CompileOptions, Emit, OptLevel, Target};
+//!
+//! # fn main() -> Result<(), qir_qis::Error> {
+//! # let bc_bytes = Vec::new();
+//! let output = CompileOptions::new()
+//!     .with_opt_level(OptLevel::O3)
+//!     .with_target(Target::X86_64)
+//!     .with_emit(Emit::LlvmIr)
+//!     .compile(&bc_bytes)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use inkwell::context::Context;
+
+use crate::{
+    Bundle, CompileOutput, DecompositionRegistry, Emit, Error, LoweredModule, PassContext,
+    Pipeline, ValidationReport, source_map::SourceMap,
+};
+
+/// LLVM optimization level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum OptLevel {
+    /// No optimization (`0`).
+    O0,
+    /// Light optimization (`1`).
+    O1,
+    /// Default optimization (`2`).
+    O2,
+    /// Aggressive optimization (`3`).
+    O3,
+}
+
+impl OptLevel {
+    /// Every optimization level, from least to most aggressive.
+    pub const ALL: [Self; 4] = [Self::O0, Self::O1, Self::O2, Self::O3];
+
+    /// The level as a number, e.g. `2`.
+    #[must_use]
+    pub const fn as_u32(self) -> u32 {
+        match self {
+            Self::O0 => 0,
+            Self::O1 => 1,
+            Self::O2 => 2,
+            Self::O3 => 3,
+        }
+    }
+}
+
+impl Default for OptLevel {
+    /// The platform default, [`DEFAULT_OPT_LEVEL`](crate::DEFAULT_OPT_LEVEL).
+    fn default() -> Self {
+        if cfg!(windows) { Self::O0 } else { Self::O2 }
+    }
+}
+
+impl fmt::Display for OptLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_u32())
+    }
+}
+
+impl TryFrom<u32> for OptLevel {
+    type Error = String;
+
+    fn try_from(level: u32) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|opt_level| opt_level.as_u32() == level)
+            .ok_or_else(|| invalid_opt_level(level))
+    }
+}
+
+impl FromStr for OptLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u32>()
+            .map_err(|_| invalid_opt_level(s))
+            .and_then(Self::try_from)
+    }
+}
+
+fn invalid_opt_level(level: impl fmt::Display) -> String {
+    format!("Invalid optimization level: {level} (expected one of 0, 1, 2, 3)")
+}
+
+/// Target architecture of the generated code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Target {
+    /// 64-bit ARM (`aarch64`).
+    Aarch64,
+    /// 64-bit x86 (`x86-64`).
+    X86_64,
+    /// The host machine (`native`).
+    Native,
+}
+
+impl Target {
+    /// Every target, in the order they are documented.
+    pub const ALL: [Self; 3] = [Self::Aarch64, Self::X86_64, Self::Native];
+
+    /// Name accepted by [`Target::from_str`], e.g. `"x86-64"`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Aarch64 => "aarch64",
+            Self::X86_64 => "x86-64",
+            Self::Native => "native",
+        }
+    }
+}
+
+impl Default for Target {
+    /// The platform default, [`DEFAULT_TARGET`](crate::DEFAULT_TARGET).
+    fn default() -> Self {
+        if cfg!(windows) {
+            Self::Native
+        } else {
+            Self::Aarch64
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|target| target.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid target architecture: {s} (expected one of aarch64, x86-64, native)"
+                )
+            })
+    }
+}
+
+/// Settings of a QIR to QIS translation.
+///
+/// Start from [`CompileOptions::new`], which uses the platform defaults, and
+/// chain the `with_*` setters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompileOptions {
+    opt_level: OptLevel,
+    target: Target,
+    wasm: Option<Vec<u8>>,
+    emit: Emit,
+    deny_warnings: bool,
+    decompositions: DecompositionRegistry,
+}
+
+impl CompileOptions {
+    /// Options with the platform defaults, emitting bitcode without a WASM
+    /// module.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the optimization level.
+    #[must_use]
+    pub const fn with_opt_level(mut self, opt_level: OptLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Set the target architecture.
+    #[must_use]
+    pub const fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Set the WASM module providing the functions called by the program.
+    #[must_use]
+    pub fn with_wasm(mut self, wasm: impl Into<Vec<u8>>) -> Self {
+        self.wasm = Some(wasm.into());
+        self
+    }
+
+    /// Set the kind of artifact to produce.
+    #[must_use]
+    pub const fn with_emit(mut self, emit: Emit) -> Self {
+        self.emit = emit;
+        self
+    }
+
+    /// Fail the translation if any warning is raised.
+    #[must_use]
+    pub const fn with_deny_warnings(mut self, deny_warnings: bool) -> Self {
+        self.deny_warnings = deny_warnings;
+        self
+    }
+
+    /// Decompose and accept the gates registered in `decompositions`.
+    #[must_use]
+    pub fn with_decompositions(mut self, decompositions: DecompositionRegistry) -> Self {
+        self.decompositions = decompositions;
+        self
+    }
+
+    /// The optimization level.
+    #[must_use]
+    pub const fn opt_level(&self) -> OptLevel {
+        self.opt_level
+    }
+
+    /// The target architecture.
+    #[must_use]
+    pub const fn target(&self) -> Target {
+        self.target
+    }
+
+    /// The WASM module, if any.
+    #[must_use]
+    pub fn wasm(&self) -> Option<&[u8]> {
+        self.wasm.as_deref()
+    }
+
+    /// The kind of artifact to produce.
+    #[must_use]
+    pub const fn emit(&self) -> Emit {
+        self.emit
+    }
+
+    /// Whether warnings fail the translation.
+    #[must_use]
+    pub const fn deny_warnings(&self) -> bool {
+        self.deny_warnings
+    }
+
+    /// The gates decomposed on top of the built-in ones.
+    #[must_use]
+    pub const fn decompositions(&self) -> &DecompositionRegistry {
+        &self.decompositions
+    }
+
+    /// Validate QIR bitcode, as [`validate_qir_report`](crate::validate_qir_report),
+    /// accepting the registered gates.
+    ///
+    /// # Errors
+    /// Returns [`Error::Parse`] if the bitcode cannot be parsed, or
+    /// [`Error::Wasm`] if the WASM module cannot be parsed.
+    pub fn validate(&self, bc_bytes: &[u8]) -> Result<ValidationReport, Error> {
+        let ctx = Context::create();
+        let module = crate::parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
+        self.validate_module(&module)
+    }
+
+    /// Validate QIR LLVM IR text, as
+    /// [`validate_qir_ll_report`](crate::validate_qir_ll_report), accepting
+    /// the registered gates.
+    ///
+    /// # Errors
+    /// Returns [`Error::Parse`] if the text cannot be parsed, or
+    /// [`Error::Wasm`] if the WASM module cannot be parsed.
+    pub fn validate_ll(&self, ll_text: &str, source_name: &str) -> Result<ValidationReport, Error> {
+        let ctx = Context::create();
+        let module = crate::create_module_from_ir_text(&ctx, ll_text, source_name)?;
+        let mut report = self.validate_module(&module)?;
+        report.resolve_locations(&SourceMap::new(source_name, ll_text));
+        Ok(report)
+    }
+
+    /// Validate a QIR module in place, as
+    /// [`validate_qir_module`](crate::validate_qir_module), accepting the
+    /// registered gates.
+    ///
+    /// # Errors
+    /// Returns [`Error::Wasm`] if the WASM module cannot be parsed.
+    pub fn validate_module(
+        &self,
+        module: &inkwell::module::Module<'_>,
+    ) -> Result<ValidationReport, Error> {
+        Ok(crate::validate_module(
+            module,
+            crate::get_wasm_signatures(self.wasm())?.as_ref(),
+            Some(&self.decompositions),
+        ))
+    }
+
+    /// Lower a QIR module of `ctx` to QIS in place, without a bitcode
+    /// round-trip, decomposing the registered gates.
+    ///
+    /// Runs the passes of [`compile`](Self::compile) over `module`, so that a
+    /// frontend already holding an inkwell module can validate it with
+    /// [`validate_module`](Self::validate_module), lower it and emit it with
+    /// [`opt::emit`](crate::opt::emit), passing
+    /// [`opt_level`](Self::opt_level) and [`target`](Self::target), in a
+    /// single context. The emit kind is ignored.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] describing the stage at which the lowering
+    /// failed, or [`Error::Validation`] listing the warnings if they are
+    /// denied. The module is then left partially lowered: the stages of
+    /// [`STAGES`](crate::pipeline::STAGES) before the failing one have run,
+    /// and the failing one may have rewritten part of it, e.g. `lower-entry`
+    /// stops at the first call it cannot lower. Warnings are only checked by
+    /// `lower-entry`, so denied warnings leave the module after every stage
+    /// up to and including `lower-entry`. Such a module should be discarded.
+    pub fn lower_module<'a, 'ctx>(
+        &self,
+        ctx: &'ctx Context,
+        module: &'a inkwell::module::Module<'ctx>,
+    ) -> Result<LoweredModule<'a, 'ctx>, Error> {
+        self.lower_module_with(ctx, module, &Pipeline::default())
+    }
+
+    /// Lower a QIR module of `ctx` in place by running a custom [`Pipeline`]
+    /// over it, as [`compile_with`](Self::compile_with) does.
+    ///
+    /// # Errors
+    /// Returns the error of the first pass that fails, leaving the module as
+    /// the passes before it and the failing pass left it.
+    pub fn lower_module_with<'a, 'ctx>(
+        &self,
+        ctx: &'ctx Context,
+        module: &'a inkwell::module::Module<'ctx>,
+        pipeline: &Pipeline,
+    ) -> Result<LoweredModule<'a, 'ctx>, Error> {
+        let mut cx = PassContext::new(ctx, module, self.opt_level, self.target);
+        cx.wasm_bytes = self.wasm();
+        cx.deny_warnings = self.deny_warnings;
+        cx.registry = Some(&self.decompositions);
+        pipeline.run(&mut cx)?;
+        Ok(LoweredModule {
+            module,
+            warnings: cx.warnings,
+            dumps: cx.dumps,
+        })
+    }
+
+    /// Translate QIR bitcode to QIS, returning lowering warnings.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] describing the stage at which the translation
+    /// failed, or [`Error::Validation`] listing the warnings if they are
+    /// denied.
+    pub fn compile(&self, bc_bytes: &[u8]) -> Result<CompileOutput, Error> {
+        self.compile_with(bc_bytes, &Pipeline::default())
+    }
+
+    /// Translate QIR bitcode by running a custom [`Pipeline`] over it.
+    ///
+    /// `pipeline` replaces the default one: start from
+    /// [`Pipeline::default`] to insert, remove or stop after stages. The
+    /// artifact is the module as left by the last pass.
+    ///
+    /// # Errors
+    /// Returns the error of the first pass that fails, or an
+    /// [`Error::Target`] if the module cannot be emitted.
+    pub fn compile_with(
+        &self,
+        bc_bytes: &[u8],
+        pipeline: &Pipeline,
+    ) -> Result<CompileOutput, Error> {
+        let ctx = Context::create();
+        let module = crate::parse_bitcode_module(&ctx, bc_bytes, "bitcode")?;
+        self.compile_module_with(&ctx, &module, pipeline)
+    }
+
+    /// Translate a QIR module of `ctx` in place by running a custom
+    /// [`Pipeline`] over it, then emit it, as [`compile_with`](Self::compile_with)
+    /// does for bitcode.
+    ///
+    /// # Errors
+    /// Returns the error of the first pass that fails, or an
+    /// [`Error::Target`] if the module cannot be emitted.
+    pub fn compile_module_with<'ctx>(
+        &self,
+        ctx: &'ctx Context,
+        module: &inkwell::module::Module<'ctx>,
+        pipeline: &Pipeline,
+    ) -> Result<CompileOutput, Error> {
+        crate::compile_module(ctx, module, pipeline, self, &HashMap::new())
+    }
+
+    /// Translate QIR LLVM IR text to QIS.
+    ///
+    /// Warnings and lowering errors caused by a specific instruction carry its
+    /// position in the source, e.g.
+    /// `program.ll:42:3: Malformed mz_leaked call: ...`.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] describing the stage at which the translation
+    /// failed, or [`Error::Validation`] listing the warnings if they are
+    /// denied.
+    pub fn compile_ll(&self, ll_text: &str, source_name: &str) -> Result<CompileOutput, Error> {
+        self.compile_ll_with(ll_text, source_name, &Pipeline::default())
+    }
+
+    /// Translate QIR LLVM IR text by running a custom [`Pipeline`] over it,
+    /// as [`compile_with`](Self::compile_with) does for bitcode.
+    ///
+    /// # Errors
+    /// Returns the error of the first pass that fails, or an
+    /// [`Error::Target`] if the module cannot be emitted.
+    pub fn compile_ll_with(
+        &self,
+        ll_text: &str,
+        source_name: &str,
+        pipeline: &Pipeline,
+    ) -> Result<CompileOutput, Error> {
+        let ctx = Context::create();
+        let module = crate::create_module_from_ir_text(&ctx, ll_text, source_name)?;
+        self.compile_ll_module_with(&ctx, &module, ll_text, source_name, pipeline)
+    }
+
+    /// Translate a QIR module of `ctx` parsed from `ll_text` in place, as
+    /// [`compile_module_with`](Self::compile_module_with), with the source
+    /// positions of [`compile_ll`](Self::compile_ll).
+    ///
+    /// # Errors
+    /// Returns the error of the first pass that fails, or an
+    /// [`Error::Target`] if the module cannot be emitted.
+    pub fn compile_ll_module_with<'ctx>(
+        &self,
+        ctx: &'ctx Context,
+        module: &inkwell::module::Module<'ctx>,
+        ll_text: &str,
+        source_name: &str,
+        pipeline: &Pipeline,
+    ) -> Result<CompileOutput, Error> {
+        let locations = SourceMap::new(source_name, ll_text).instruction_locations(module);
+        crate::compile_module(ctx, module, pipeline, self, &locations)
+    }
+
+    /// Translate QIR bitcode to QIS bitcode and pack it into a serialized
+    /// [`Bundle`](crate::Bundle) with the WASM module, entry point attributes
+    /// and capability flags. The emit kind is ignored.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] describing the stage at which the translation
+    /// failed, or [`Error::Validation`] listing the warnings if they are
+    /// denied.
+    pub fn compile_bundle(&self, bc_bytes: &[u8]) -> Result<CompileOutput, Error> {
+        let output = self.clone().with_emit(Emit::Bitcode).compile(bc_bytes)?;
+        let bundle = Bundle::new(bc_bytes, output.artifact, self.target.as_str(), self.wasm())?;
+        Ok(CompileOutput {
+            artifact: bundle.to_bytes(),
+            warnings: output.warnings,
+            dumps: output.dumps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use super::{CompileOptions, OptLevel, Target};
+    use crate::{Compiler, DEFAULT_OPT_LEVEL, DEFAULT_TARGET, DecompositionRegistry, Emit};
+
+    #[test]
+    fn test_defaults_match_the_platform_defaults() {
+        let options = CompileOptions::new();
+        assert_eq!(options.opt_level().as_u32(), DEFAULT_OPT_LEVEL);
+        assert_eq!(options.target().as_str(), DEFAULT_TARGET);
+        assert_eq!(options.emit(), Emit::Bitcode);
+        assert_eq!(options.wasm(), None);
+        assert!(!options.deny_warnings());
+        assert!(options.decompositions().is_empty());
+    }
+
+    #[test]
+    fn test_builder_sets_every_option() {
+        let options = CompileOptions::new()
+            .with_opt_level(OptLevel::O3)
+            .with_target(Target::X86_64)
+            .with_wasm(b"\0asm".as_slice())
+            .with_emit(Emit::Assembly)
+            .with_deny_warnings(true);
+        assert_eq!(options.opt_level(), OptLevel::O3);
+        assert_eq!(options.target(), Target::X86_64);
+        assert_eq!(options.wasm(), Some(b"\0asm".as_slice()));
+        assert_eq!(options.emit(), Emit::Assembly);
+        assert!(options.deny_warnings());
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        for level in OptLevel::ALL {
+            assert_eq!(level.to_string().parse::<OptLevel>(), Ok(level));
+        }
+        for target in Target::ALL {
+            assert_eq!(target.to_string().parse::<Target>(), Ok(target));
+        }
+        assert_eq!(
+            OptLevel::try_from(4),
+            Err("Invalid optimization level: 4 (expected one of 0, 1, 2, 3)".to_owned())
+        );
+        assert!("fast".parse::<OptLevel>().is_err());
+        assert_eq!(
+            "riscv".parse::<Target>(),
+            Err(
+                "Invalid target architecture: riscv (expected one of aarch64, x86-64, native)"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_registered_gates_are_validated_and_compiled() {
+        let ll_text = std::fs::read_to_string("tests/data/base.ll")
+            .expect("Failed to read fixture")
+            .replace("__quantum__qis__h__body", "__quantum__qis__sx__body");
+        let options = CompileOptions::new().with_emit(Emit::LlvmIr);
+        let report = options
+            .validate_ll(&ll_text, "base.ll")
+            .expect("Validation failed");
+        assert!(report.has_errors());
+        assert!(options.compile_ll(&ll_text, "base.ll").is_err());
+
+        let mut registry = DecompositionRegistry::new();
+        registry
+            .register_ir(
+                "__quantum__qis__sx__body",
+                "define void @__quantum__qis__sx__body(ptr %q) {
+                   call void @__quantum__qis__rxy__body(double 0x3FF921FB54442D18, double 0.0, ptr %q)
+                   ret void
+                 }
+                 declare void @__quantum__qis__rxy__body(double, double, ptr)",
+            )
+            .expect("sx should register");
+        let options = options.with_decompositions(registry);
+        let report = options
+            .validate_ll(&ll_text, "base.ll")
+            .expect("Validation failed");
+        assert!(!report.has_errors(), "{report:?}");
+        let output = options
+            .compile_ll(&ll_text, "base.ll")
+            .expect("Compilation with registered gates failed");
+        let ir = String::from_utf8(output.artifact).expect("IR should be UTF-8");
+        assert!(ir.contains("___rxy"));
+
+        let ctx = inkwell::context::Context::create();
+        let compiler = Compiler::new(&ctx, options).expect("Failed to create compiler");
+        assert!(
+            !compiler
+                .validate_ll(&ll_text, "base.ll")
+                .expect("Validation failed")
+                .has_errors()
+        );
+        compiler
+            .compile_ll(&ll_text, "base.ll")
+            .expect("Session compilation with registered gates failed");
+    }
+
+    #[test]
+    fn test_registered_gates_are_validated_and_lowered_in_place() {
+        let ll_text = std::fs::read_to_string("tests/data/base.ll")
+            .expect("Failed to read fixture")
+            .replace("__quantum__qis__h__body", "__quantum__qis__sx__body");
+        let mut registry = DecompositionRegistry::new();
+        registry
+            .register_ir(
+                "__quantum__qis__sx__body",
+                "define void @__quantum__qis__sx__body(ptr %q) {
+                   call void @__quantum__qis__rxy__body(double 0x3FF921FB54442D18, double 0.0, ptr %q)
+                   ret void
+                 }
+                 declare void @__quantum__qis__rxy__body(double, double, ptr)",
+            )
+            .expect("sx should register");
+        let options = CompileOptions::new().with_decompositions(registry);
+
+        let ctx = inkwell::context::Context::create();
+        let module = crate::create_module_from_ir_text(&ctx, &ll_text, "base.ll")
+            .expect("Failed to parse fixture");
+        assert!(
+            crate::validate_qir_module(&module, None)
+                .expect("Validation failed")
+                .has_errors()
+        );
+        let report = options.validate_module(&module).expect("Validation failed");
+        assert!(!report.has_errors(), "{report:?}");
+        let lowered = options
+            .lower_module(&ctx, &module)
+            .expect("Lowering with registered gates failed");
+        assert!(lowered.module.get_function("___rxy").is_some());
+        assert!(
+            !lowered
+                .module
+                .print_to_string()
+                .to_string()
+                .contains("call void @__quantum__qis__sx__body")
+        );
+
+        // Without the registry, the gate is left undecomposed.
+        let module = crate::create_module_from_ir_text(&ctx, &ll_text, "base.ll")
+            .expect("Failed to parse fixture");
+        assert!(CompileOptions::new().lower_module(&ctx, &module).is_err());
+    }
+}
@@ -0,0 +1,633 @@
+//! The sequence of named passes that translates a QIR module to QIS.
+//!
+//! [`Pipeline::default`] is the pipeline run by
+//! [`CompileOptions::compile`](crate::CompileOptions::compile) and friends,
+//! with the stages listed in [`STAGES`]. Custom passes can be inserted before
+//! or after any stage, stages can be removed, and a pipeline
+//! can be cut short with [`Pipeline::stop_after`] to inspect the module
+//! half-way through the translation. [`Pipeline::print_after`] records the
+//! textual IR after a stage in [`PassContext::dumps`], which a failing later
+//! stage hands over in an [`Error::Stage`]; printing after `metadata` shows the
+//! module right before LLVM optimization.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use inkwell::attributes::AttributeLoc;
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::targets::TargetMachine;
+use inkwell::values::{FunctionValue, InstructionValue, PointerValue};
+
+use crate::{
+    CapabilityFlags, Diagnostic, Error, OptLevel, SourceLocation, Target, ValidationReport,
+    aux::{get_capability_flags, process_entry_function},
+    convert::{
+        add_qmain_wrapper, create_qubit_array, find_entry_function, free_all_qubits,
+        get_string_attrs, process_ir_defined_q_fns, prune_unused_ir_qis_helpers,
+    },
+    decompose::{DecompositionRegistry, Decompositions, add_decompositions, link_decompositions},
+    opt::optimize_with,
+    utils::add_generator_metadata,
+};
+
+/// Names of the stages of the default pipeline, in order.
+pub const STAGES: [&str; 13] = [
+    "verify-input",
+    "decompose",
+    "find-entry",
+    "rename-entry",
+    "qubit-array",
+    "lower-entry",
+    "lower-ir-functions",
+    "free-qubits",
+    "qmain-wrapper",
+    "verify",
+    "metadata",
+    "optimize",
+    "prune-helpers",
+];
+
+/// The module being translated, the compilation settings and the state
+/// handed from one pass to the next.
+pub struct PassContext<'a, 'ctx> {
+    /// The LLVM context owning `module`.
+    pub ctx: &'ctx Context,
+    /// The module being translated.
+    pub module: &'a Module<'ctx>,
+    /// The optimization level to use.
+    pub opt_level: OptLevel,
+    /// Target architecture of the generated code.
+    pub target: Target,
+    /// WASM module providing the functions called by the program.
+    pub wasm_bytes: Option<&'a [u8]>,
+    /// Fail the translation if any warning is raised.
+    pub deny_warnings: bool,
+    /// The entry function, once `find-entry` has run.
+    pub entry_fn: Option<FunctionValue<'ctx>>,
+    /// The name of the entry function in the QIR program, once `find-entry`
+    /// has run.
+    pub entry_fn_name: String,
+    /// Capabilities enabled by the module flags, once `find-entry` has run.
+    pub capability_flags: CapabilityFlags,
+    /// The static qubit array, once `qubit-array` has run on a program
+    /// without dynamic qubit management.
+    pub qubit_array: Option<PointerValue<'ctx>>,
+    /// Warnings raised so far.
+    pub warnings: Vec<Diagnostic>,
+    /// IR recorded after the stages marked with [`Pipeline::print_after`].
+    pub dumps: Vec<StageDump>,
+    /// Source positions of instructions, for programs parsed from text.
+    pub(crate) locations: Option<&'a HashMap<InstructionValue<'ctx>, SourceLocation>>,
+    /// Prebuilt decompositions to link in instead of building them.
+    pub(crate) decompositions: Option<&'a Decompositions<'ctx>>,
+    /// Gates decomposed on top of the built-in ones, unless already in
+    /// `decompositions`.
+    pub(crate) registry: Option<&'a DecompositionRegistry>,
+    /// Target machine to reuse for optimization and code generation.
+    pub(crate) target_machine: Option<&'a TargetMachine>,
+}
+
+impl<'a, 'ctx> PassContext<'a, 'ctx> {
+    /// Prepare to translate `module` with the given settings and no WASM
+    /// module.
+    #[must_use]
+    pub const fn new(
+        ctx: &'ctx Context,
+        module: &'a Module<'ctx>,
+        opt_level: OptLevel,
+        target: Target,
+    ) -> Self {
+        Self {
+            ctx,
+            module,
+            opt_level,
+            target,
+            wasm_bytes: None,
+            deny_warnings: false,
+            entry_fn: None,
+            entry_fn_name: String::new(),
+            capability_flags: CapabilityFlags {
+                dynamic_qubit_management: false,
+                dynamic_result_management: false,
+                arrays: false,
+            },
+            qubit_array: None,
+            warnings: Vec::new(),
+            dumps: Vec::new(),
+            locations: None,
+            decompositions: None,
+            registry: None,
+            target_machine: None,
+        }
+    }
+
+    /// The entry function found by the `find-entry` stage.
+    ///
+    /// # Errors
+    /// Returns [`Error::Lowering`] if `find-entry` has not run.
+    pub fn entry_fn(&self) -> Result<FunctionValue<'ctx>, Error> {
+        self.entry_fn.ok_or_else(|| {
+            Error::Lowering("No entry function: the `find-entry` stage has not run".to_owned())
+        })
+    }
+
+    fn location(&self, instr: Option<InstructionValue<'ctx>>) -> Option<&SourceLocation> {
+        instr.and_then(|instr| self.locations?.get(&instr))
+    }
+}
+
+/// Textual IR of the module after a stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageDump {
+    /// Name of the stage.
+    pub stage: String,
+    /// The module as LLVM IR text.
+    pub ir: String,
+}
+
+impl fmt::Display for StageDump {
+    /// Print the dump with an LLVM-style `; *** IR Dump After ... ***` header.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "; *** IR Dump After {} ***", self.stage)?;
+        f.write_str(&self.ir)
+    }
+}
+
+/// Signature of the function run by a [`Pass`].
+pub type PassFn = dyn Fn(&mut PassContext<'_, '_>) -> Result<(), Error> + Send + Sync;
+
+/// A named step of a [`Pipeline`].
+pub struct Pass {
+    name: String,
+    run: Box<PassFn>,
+    print_after: bool,
+}
+
+impl Pass {
+    /// A pass called `name` that runs `run` on the module.
+    #[must_use]
+    pub fn new(
+        name: impl Into<String>,
+        run: impl Fn(&mut PassContext<'_, '_>) -> Result<(), Error> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            run: Box::new(run),
+            print_after: false,
+        }
+    }
+
+    /// The name of the pass.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run the pass on `cx`.
+    ///
+    /// # Errors
+    /// Returns the error raised by the pass.
+    pub fn run(&self, cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+        (self.run)(cx)
+    }
+}
+
+impl fmt::Debug for Pass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pass").field(&self.name).finish()
+    }
+}
+
+/// An ordered sequence of passes.
+#[derive(Debug)]
+pub struct Pipeline {
+    passes: Vec<Pass>,
+}
+
+impl Default for Pipeline {
+    /// The pipeline run by [`CompileOptions::compile`](crate::CompileOptions::compile).
+    fn default() -> Self {
+        Self {
+            passes: vec![
+                Pass::new("verify-input", verify_input),
+                Pass::new("decompose", decompose),
+                Pass::new("find-entry", find_entry),
+                Pass::new("rename-entry", rename_entry),
+                Pass::new("qubit-array", qubit_array),
+                Pass::new("lower-entry", lower_entry),
+                Pass::new("lower-ir-functions", lower_ir_functions),
+                Pass::new("free-qubits", free_qubits),
+                Pass::new("qmain-wrapper", qmain_wrapper),
+                Pass::new("verify", verify),
+                Pass::new("metadata", metadata),
+                Pass::new("optimize", run_optimize),
+                Pass::new("prune-helpers", prune_helpers),
+            ],
+        }
+    }
+}
+
+impl Pipeline {
+    /// An empty pipeline.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self { passes: Vec::new() }
+    }
+
+    /// Names of the passes, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.passes.iter().map(Pass::name)
+    }
+
+    /// Append `pass` to the pipeline.
+    pub fn push(&mut self, pass: Pass) -> &mut Self {
+        self.passes.push(pass);
+        self
+    }
+
+    /// Insert `pass` right before the stage called `stage`.
+    ///
+    /// # Errors
+    /// Returns [`Error::UnknownStage`] if the pipeline has no stage called
+    /// `stage`.
+    pub fn insert_before(&mut self, stage: &str, pass: Pass) -> Result<&mut Self, Error> {
+        let index = self.position(stage)?;
+        self.passes.insert(index, pass);
+        Ok(self)
+    }
+
+    /// Insert `pass` right after the stage called `stage`.
+    ///
+    /// # Errors
+    /// Returns [`Error::UnknownStage`] if the pipeline has no stage called
+    /// `stage`.
+    pub fn insert_after(&mut self, stage: &str, pass: Pass) -> Result<&mut Self, Error> {
+        let index = self.position(stage)?.saturating_add(1);
+        self.passes.insert(index, pass);
+        Ok(self)
+    }
+
+    /// Remove the stage called `stage`, returning it.
+    ///
+    /// # Errors
+    /// Returns [`Error::UnknownStage`] if the pipeline has no stage called
+    /// `stage`.
+    pub fn remove(&mut self, stage: &str) -> Result<Pass, Error> {
+        let index = self.position(stage)?;
+        Ok(self.passes.remove(index))
+    }
+
+    /// Record the IR after the stage called `stage` in
+    /// [`PassContext::dumps`].
+    ///
+    /// # Errors
+    /// Returns [`Error::UnknownStage`] if the pipeline has no stage called
+    /// `stage`.
+    pub fn print_after(&mut self, stage: &str) -> Result<&mut Self, Error> {
+        let index = self.position(stage)?;
+        if let Some(pass) = self.passes.get_mut(index) {
+            pass.print_after = true;
+        }
+        Ok(self)
+    }
+
+    /// Record the IR after every stage in [`PassContext::dumps`].
+    pub fn print_after_all(&mut self) -> &mut Self {
+        for pass in &mut self.passes {
+            pass.print_after = true;
+        }
+        self
+    }
+
+    /// The default pipeline, set up for debugging the translation: the IR is
+    /// recorded after each stage in `print_after` (or every stage if
+    /// `print_after_all` is set), and the pipeline ends after `stop_after`.
+    ///
+    /// # Errors
+    /// Returns [`Error::UnknownStage`] for the first unknown stage.
+    pub fn debugging<S: AsRef<str>>(
+        print_after: &[S],
+        print_after_all: bool,
+        stop_after: Option<&str>,
+    ) -> Result<Self, Error> {
+        let mut pipeline = Self::default();
+        if let Some(stage) = stop_after {
+            pipeline.stop_after(stage)?;
+        }
+        if print_after_all {
+            pipeline.print_after_all();
+        }
+        for stage in print_after {
+            pipeline.print_after(stage.as_ref())?;
+        }
+        Ok(pipeline)
+    }
+
+    /// Drop every stage after the stage called `stage`.
+    ///
+    /// # Errors
+    /// Returns [`Error::UnknownStage`] if the pipeline has no stage called
+    /// `stage`.
+    pub fn stop_after(&mut self, stage: &str) -> Result<&mut Self, Error> {
+        let index = self.position(stage)?.saturating_add(1);
+        self.passes.truncate(index);
+        Ok(self)
+    }
+
+    /// Run every pass on `cx`, in order.
+    ///
+    /// # Errors
+    /// Returns the error of the first pass that fails. If IR was recorded
+    /// after earlier stages, it is moved from [`PassContext::dumps`] into an
+    /// [`Error::Stage`], so that it is not lost with the context.
+    pub fn run(&self, cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+        for pass in &self.passes {
+            log::debug!("Running pass `{}`", pass.name());
+            if let Err(error) = pass.run(cx) {
+                if cx.dumps.is_empty() {
+                    return Err(error);
+                }
+                return Err(Error::Stage {
+                    stage: pass.name().to_owned(),
+                    error: Box::new(error),
+                    dumps: std::mem::take(&mut cx.dumps),
+                });
+            }
+            if pass.print_after {
+                let ir = cx.module.print_to_string().to_string();
+                cx.dumps.push(StageDump {
+                    stage: pass.name().to_owned(),
+                    ir,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn position(&self, stage: &str) -> Result<usize, Error> {
+        self.passes
+            .iter()
+            .position(|pass| pass.name() == stage)
+            .ok_or_else(|| Error::UnknownStage {
+                stage: stage.to_owned(),
+                expected: self.names().map(ToOwned::to_owned).collect(),
+            })
+    }
+}
+
+fn verify_input(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    crate::llvm_verify::verify_module(cx.module, "LLVM module verification failed after parse")
+}
+
+fn decompose(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    match (cx.decompositions, cx.registry) {
+        (Some(decompositions), _) => link_decompositions(cx.ctx, cx.module, decompositions),
+        (None, Some(registry)) => {
+            add_decompositions(cx.ctx, cx.module)?;
+            registry.add_to(cx.ctx, cx.module)
+        }
+        (None, None) => add_decompositions(cx.ctx, cx.module),
+    }
+}
+
+fn find_entry(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    let entry_fn = find_entry_function(cx.module).map_err(|_| Error::MissingEntryFunction)?;
+    cx.entry_fn_name = entry_fn
+        .get_name()
+        .to_str()
+        .map_err(|e| Error::Lowering(format!("Invalid UTF-8 in entry function name: {e}")))?
+        .to_owned();
+    cx.entry_fn = Some(entry_fn);
+    cx.capability_flags = get_capability_flags(cx.module);
+    log::trace!("Entry function: {}", cx.entry_fn_name);
+    Ok(())
+}
+
+fn rename_entry(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    let new_name = format!("___user_qir_{}", cx.entry_fn_name);
+    cx.entry_fn()?.as_global_value().set_name(&new_name);
+    log::debug!("Renamed entry function to: {new_name}");
+    Ok(())
+}
+
+fn qubit_array(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    if !cx.capability_flags.dynamic_qubit_management {
+        cx.qubit_array =
+            Some(create_qubit_array(cx.ctx, cx.module, cx.entry_fn()?).map_err(Error::Lowering)?);
+    }
+    Ok(())
+}
+
+fn lower_entry(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    let entry_fn = cx.entry_fn()?;
+    let mut lowering_warnings = Vec::new();
+    process_entry_function(
+        cx.ctx,
+        cx.module,
+        entry_fn,
+        cx.qubit_array,
+        cx.capability_flags,
+        &mut lowering_warnings,
+    )
+    .map_err(|failure| {
+        let message = match cx.location(failure.instr) {
+            Some(location) => format!("{location}: {}", failure.message),
+            None => failure.message,
+        };
+        Error::Lowering(message)
+    })?;
+
+    // Report warnings against the user's entry function name and source text.
+    let lowered_name = entry_fn.get_name().to_str().ok().map(ToOwned::to_owned);
+    let warnings: Vec<Diagnostic> = lowering_warnings
+        .into_iter()
+        .map(|warning| {
+            let mut diagnostic = warning.diagnostic;
+            if diagnostic.function.is_some() && diagnostic.function == lowered_name {
+                diagnostic.function = Some(cx.entry_fn_name.clone());
+            }
+            diagnostic.location = cx.location(warning.instr).cloned();
+            diagnostic
+        })
+        .collect();
+    if cx.deny_warnings && !warnings.is_empty() {
+        let mut report = ValidationReport {
+            diagnostics: warnings,
+        };
+        report.deny_warnings();
+        return Err(Error::Validation(report));
+    }
+    cx.warnings.extend(warnings);
+    Ok(())
+}
+
+fn lower_ir_functions(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    // Handle IR defined functions that take qubits
+    process_ir_defined_q_fns(
+        cx.ctx,
+        cx.module,
+        cx.entry_fn()?,
+        cx.capability_flags.dynamic_qubit_management,
+    )
+    .map_err(Error::Lowering)
+}
+
+fn free_qubits(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    if let Some(qubit_array) = cx.qubit_array {
+        free_all_qubits(cx.ctx, cx.module, cx.entry_fn()?, qubit_array).map_err(Error::Lowering)?;
+    }
+    Ok(())
+}
+
+fn qmain_wrapper(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    // Add qmain wrapper that calls setup, entry function, and teardown
+    let _ = add_qmain_wrapper(cx.ctx, cx.module, cx.entry_fn()?);
+    Ok(())
+}
+
+fn verify(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    crate::llvm_verify::verify_module(cx.module, "LLVM module verification failed")
+}
+
+fn metadata(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    // Clean up the translated module
+    let entry_fn = cx.entry_fn()?;
+    for attr in get_string_attrs(entry_fn) {
+        let kind = crate::decode_string_attribute_kind(attr).map_err(Error::Lowering)?;
+        entry_fn.remove_string_attribute(AttributeLoc::Function, &kind);
+    }
+
+    // TODO: remove global module metadata
+    // seems inkwell doesn't support this yet
+
+    // Add metadata to the module
+    let md_string = cx.ctx.metadata_string("mainlib");
+    let md_node = cx.ctx.metadata_node(&[md_string.into()]);
+    cx.module
+        .add_global_metadata("name", &md_node)
+        .map_err(|e| Error::Lowering(format!("Failed to add global metadata: {e}")))?;
+    add_generator_metadata(cx.ctx, cx.module, "gen_name", env!("CARGO_PKG_NAME"))
+        .map_err(Error::Lowering)?;
+    add_generator_metadata(cx.ctx, cx.module, "gen_version", env!("CARGO_PKG_VERSION"))
+        .map_err(Error::Lowering)
+}
+
+fn run_optimize(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    optimize_with(cx.module, cx.opt_level, cx.target, cx.target_machine)
+}
+
+fn prune_helpers(cx: &mut PassContext<'_, '_>) -> Result<(), Error> {
+    prune_unused_ir_qis_helpers(cx.module);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use super::{Pass, Pipeline, STAGES};
+    use crate::{CompileOptions, Error, qir_ll_to_bc};
+
+    #[test]
+    fn test_default_pipeline_runs_the_documented_stages() {
+        assert!(Pipeline::default().names().eq(STAGES));
+    }
+
+    #[test]
+    fn test_pipeline_inserts_removes_and_truncates_stages() {
+        let mut pipeline = Pipeline::default();
+        pipeline
+            .insert_before("decompose", Pass::new("before", |_| Ok(())))
+            .expect("stage should exist")
+            .insert_after("optimize", Pass::new("after", |_| Ok(())))
+            .expect("stage should exist");
+        pipeline.remove("verify").expect("stage should exist");
+        pipeline.stop_after("after").expect("stage should exist");
+        let names: Vec<_> = pipeline.names().collect();
+        assert_eq!(
+            names.get(..3),
+            Some(["verify-input", "before", "decompose"].as_slice())
+        );
+        assert_eq!(names.last(), Some(&"after"));
+        assert!(!names.contains(&"verify"));
+        assert!(!names.contains(&"prune-helpers"));
+
+        let err = pipeline
+            .insert_after("peephole", Pass::new("x", |_| Ok(())))
+            .expect_err("unknown stages should be rejected");
+        assert!(matches!(&err, Error::UnknownStage { stage, .. } if stage == "peephole"));
+        assert!(err.to_string().starts_with(
+            "Unknown pipeline stage `peephole` (expected one of: verify-input, before,"
+        ));
+    }
+
+    #[test]
+    fn test_debugging_pipeline_rejects_unknown_stages() {
+        let pipeline = Pipeline::debugging(&["decompose"], false, Some("lower-entry"))
+            .expect("stages should exist");
+        assert_eq!(pipeline.names().last(), Some("lower-entry"));
+        assert_eq!(
+            pipeline
+                .passes
+                .iter()
+                .filter(|pass| pass.print_after)
+                .count(),
+            1
+        );
+
+        let all = Pipeline::debugging::<&str>(&[], true, None).expect("no stages to check");
+        assert!(all.passes.iter().all(|pass| pass.print_after));
+
+        // Stages after `stop_after` cannot be printed.
+        let err = Pipeline::debugging(&["optimize"], false, Some("decompose"))
+            .expect_err("optimize comes after decompose");
+        assert_eq!(
+            err,
+            Error::UnknownStage {
+                stage: "optimize".to_owned(),
+                expected: ["verify-input", "decompose"]
+                    .map(ToOwned::to_owned)
+                    .to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_failing_stage_keeps_earlier_dumps() {
+        let ll_text =
+            std::fs::read_to_string("tests/data/base.ll").expect("Failed to read fixture");
+        let bc_bytes = qir_ll_to_bc(&ll_text).expect("Failed to convert fixture");
+        let mut pipeline =
+            Pipeline::debugging(&["decompose"], false, None).expect("stages should exist");
+        pipeline
+            .insert_after(
+                "verify",
+                Pass::new("fail", |_| Err(Error::Target("boom".to_owned()))),
+            )
+            .expect("stage should exist");
+
+        let err = CompileOptions::new()
+            .compile_with(&bc_bytes, &pipeline)
+            .expect_err("the failing stage should fail the translation");
+        assert!(matches!(&err, Error::Stage { stage, .. } if stage == "fail"));
+        assert_eq!(err.kind(), "target");
+        assert_eq!(err.to_string(), "boom");
+        let (err, dumps) = err.split_dumps();
+        assert_eq!(err, Error::Target("boom".to_owned()));
+        assert_eq!(dumps.len(), 1);
+        assert!(dumps.iter().all(|dump| dump.stage == "decompose"));
+
+        // Without dumps to keep, the error of the pass is returned as is.
+        let mut pipeline = Pipeline::default();
+        pipeline
+            .insert_after(
+                "verify",
+                Pass::new("fail", |_| Err(Error::Target("boom".to_owned()))),
+            )
+            .expect("stage should exist");
+        assert_eq!(
+            CompileOptions::new().compile_with(&bc_bytes, &pipeline),
+            Err(Error::Target("boom".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Mapping from LLVM values back to positions in `.ll` source text.
+//!
+//! LLVM does not retain source positions when parsing textual IR, so the
+//! textual module is scanned alongside the parse: function definitions,
+//! basic blocks and instructions appear in the parsed module in exactly the
+//! order they appear in the text, which lets positions be recovered by index.
+
+use std::collections::HashMap;
+
+use inkwell::module::Module;
+use inkwell::values::InstructionValue;
+
+use crate::diagnostics::{IrPosition, SourceLocation};
+
+/// 1-based `(line, column)` pair.
+type LineColumn = (usize, usize);
+
+#[derive(Debug, Default)]
+struct FunctionSource {
+    location: LineColumn,
+    blocks: Vec<Vec<LineColumn>>,
+}
+
+/// Source positions of the functions and instructions in a `.ll` file.
+#[derive(Debug)]
+pub struct SourceMap {
+    file: String,
+    functions: HashMap<String, FunctionSource>,
+}
+
+impl SourceMap {
+    /// Scan `ll_text`, reporting positions relative to `file`.
+    pub fn new(file: &str, ll_text: &str) -> Self {
+        let mut functions = HashMap::new();
+        // Function currently being defined and whether its body has started.
+        let mut current: Option<(String, FunctionSource, bool)> = None;
+        let mut bracket_depth = 0_i64;
+
+        for (line_idx, raw_line) in ll_text.lines().enumerate() {
+            let line_no = line_idx.saturating_add(1);
+            let line = strip_comment(raw_line);
+            let trimmed = line.trim_start();
+            let column = line.len().saturating_sub(trimmed.len()).saturating_add(1);
+            let trimmed = trimmed.trim_end();
+
+            let Some((_, function, in_body)) = current.as_mut() else {
+                let is_define = trimmed.starts_with("define ");
+                if (is_define || trimmed.starts_with("declare "))
+                    && let Some((name, at)) = function_name(line)
+                {
+                    let function = FunctionSource {
+                        location: (line_no, at.saturating_add(1)),
+                        blocks: Vec::new(),
+                    };
+                    if is_define {
+                        current = Some((name, function, trimmed.ends_with('{')));
+                    } else {
+                        functions.insert(name, function);
+                    }
+                }
+                continue;
+            };
+
+            if !*in_body {
+                // Multi-line function header: wait for the opening brace.
+                *in_body = trimmed.ends_with('{');
+                continue;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            if bracket_depth > 0 {
+                // Continuation of a multi-line instruction such as `switch`.
+                bracket_depth = bracket_depth.saturating_add(bracket_delta(trimmed));
+                continue;
+            }
+            if trimmed == "}" {
+                if let Some((name, function, _)) = current.take() {
+                    functions.insert(name, function);
+                }
+                continue;
+            }
+            if is_label(trimmed) {
+                function.blocks.push(Vec::new());
+                continue;
+            }
+            if function.blocks.is_empty() {
+                // Unlabelled entry block.
+                function.blocks.push(Vec::new());
+            }
+            if let Some(block) = function.blocks.last_mut() {
+                block.push((line_no, column));
+            }
+            bracket_depth = bracket_delta(trimmed);
+        }
+
+        Self {
+            file: file.to_owned(),
+            functions,
+        }
+    }
+
+    fn location(&self, (line, column): LineColumn) -> SourceLocation {
+        SourceLocation {
+            file: self.file.clone(),
+            line,
+            column,
+        }
+    }
+
+    /// Location of the instruction at `position`, falling back to the
+    /// enclosing function when the position is unknown.
+    pub fn resolve(&self, function: &str, position: Option<IrPosition>) -> Option<SourceLocation> {
+        let source = self.functions.get(function)?;
+        let line_column = position
+            .and_then(|position| {
+                source
+                    .blocks
+                    .get(position.block)?
+                    .get(position.instruction)
+                    .copied()
+            })
+            .unwrap_or(source.location);
+        Some(self.location(line_column))
+    }
+
+    /// Map every instruction of the freshly parsed `module` to its location.
+    ///
+    /// Must be called before the module is transformed, while its
+    /// instructions still correspond one-to-one with the source text.
+    pub fn instruction_locations<'ctx>(
+        &self,
+        module: &Module<'ctx>,
+    ) -> HashMap<InstructionValue<'ctx>, SourceLocation> {
+        let mut locations = HashMap::new();
+        for function in module.get_functions() {
+            let Some(source) = function
+                .get_name()
+                .to_str()
+                .ok()
+                .and_then(|name| self.functions.get(name))
+            else {
+                continue;
+            };
+            for (bb, lines) in function.get_basic_blocks().into_iter().zip(&source.blocks) {
+                for (instr, line_column) in bb.get_instructions().zip(lines) {
+                    locations.insert(instr, self.location(*line_column));
+                }
+            }
+        }
+        locations
+    }
+}
+
+/// Remove a trailing `;` comment, ignoring semicolons inside string literals.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (idx, ch) in line.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Net number of `[` minus `]` outside string literals.
+fn bracket_delta(line: &str) -> i64 {
+    let mut in_string = false;
+    let mut delta = 0_i64;
+    for ch in line.chars() {
+        match ch {
+            '"' => in_string = !in_string,
+            '[' if !in_string => delta = delta.saturating_add(1),
+            ']' if !in_string => delta = delta.saturating_sub(1),
+            _ => {}
+        }
+    }
+    delta
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '-' | '$' | '.' | '_')
+}
+
+/// Whether `trimmed` is a basic block label such as `entry:` or `"a b":`.
+fn is_label(trimmed: &str) -> bool {
+    let Some(label) = trimmed.strip_suffix(':') else {
+        return false;
+    };
+    if let Some(quoted) = label.strip_prefix('"') {
+        return quoted.ends_with('"') && !quoted.is_empty();
+    }
+    !label.is_empty() && label.chars().all(is_identifier_char)
+}
+
+/// Name of the function defined or declared on `line`, with the byte offset
+/// of its `@` sigil.
+fn function_name(line: &str) -> Option<(String, usize)> {
+    let at = line.find('@')?;
+    let rest = &line[at.saturating_add(1)..];
+    let name = if let Some(quoted) = rest.strip_prefix('"') {
+        &quoted[..quoted.find('"')?]
+    } else {
+        let end = rest
+            .find(|ch: char| !is_identifier_char(ch))
+            .unwrap_or(rest.len());
+        &rest[..end]
+    };
+    (!name.is_empty()).then(|| (name.to_owned(), at))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+    use super::SourceMap;
+    use crate::diagnostics::IrPosition;
+
+    const LL: &str = r#"; ModuleID = 'example'
+declare void @__quantum__qis__h__body(ptr)
+
+define i64 @main() #0 {
+  %x = alloca i1 ; comment with "quote"
+  br label %body
+
+body:                                             ; preds = %0
+  switch i64 0, label %done [
+    i64 1, label %done
+  ]
+
+done:
+  call void @__quantum__qis__h__body(ptr null)
+  ret i64 0
+}
+"#;
+
+    #[test]
+    fn test_source_map_tracks_functions_blocks_and_instructions() {
+        let map = SourceMap::new("example.ll", LL);
+
+        let declaration = map
+            .resolve("__quantum__qis__h__body", None)
+            .expect("declaration should be mapped");
+        assert_eq!(declaration.to_string(), "example.ll:2:14");
+
+        let at = |block, instruction| {
+            map.resolve("main", Some(IrPosition { block, instruction }))
+                .expect("instruction should be mapped")
+                .to_string()
+        };
+        assert_eq!(at(0, 0), "example.ll:5:3");
+        assert_eq!(at(0, 1), "example.ll:6:3");
+        assert_eq!(at(1, 0), "example.ll:9:3");
+        assert_eq!(at(2, 0), "example.ll:14:3");
+        assert_eq!(at(2, 1), "example.ll:15:3");
+
+        // Unknown positions fall back to the function definition.
+        assert_eq!(at(7, 0), "example.ll:4:12");
+        assert_eq!(
+            map.resolve("main", None).map(|l| l.to_string()).as_deref(),
+            Some("example.ll:4:12")
+        );
+        assert!(map.resolve("missing", None).is_none());
+    }
+}
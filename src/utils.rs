@@ -1,5 +1,6 @@
 #[cfg(feature = "wasm")]
 use std::collections::BTreeMap;
+use std::fmt;
 
 use inkwell::{
     context::Context,
@@ -7,7 +8,7 @@ use inkwell::{
     values::{BasicValueEnum, InstructionValue},
 };
 #[cfg(feature = "wasm")]
-use wasmparser::{Export, ExternalKind, Payload};
+use wasmparser::{Export, ExternalKind, FuncType, Payload, TypeRef, ValType};
 
 /// Add metadata to the generator section
 ///
@@ -77,13 +78,121 @@ pub fn parse_wasm_functions(wasm_bytes: &[u8]) -> Result<BTreeMap<String, u64>,
     }
     Ok(wasm_fns)
 }
+
+/// Value type of a WASM function parameter or result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmType {
+    I32,
+    I64,
+    F32,
+    F64,
+    /// A vector or reference type, which QIR declarations cannot express.
+    #[cfg_attr(not(feature = "wasm"), allow(dead_code))]
+    Unsupported,
+}
+
+impl fmt::Display for WasmType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::Unsupported => "v128 or reference",
+        })
+    }
+}
+
+/// Parameter and result types of an exported WASM function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasmSignature {
+    pub params: Vec<WasmType>,
+    pub results: Vec<WasmType>,
+}
+
+#[cfg(feature = "wasm")]
+impl From<&FuncType> for WasmSignature {
+    fn from(ty: &FuncType) -> Self {
+        let convert = |ty: &ValType| match ty {
+            ValType::I32 => WasmType::I32,
+            ValType::I64 => WasmType::I64,
+            ValType::F32 => WasmType::F32,
+            ValType::F64 => WasmType::F64,
+            ValType::V128 | ValType::Ref(_) => WasmType::Unsupported,
+        };
+        Self {
+            params: ty.params().iter().map(convert).collect(),
+            results: ty.results().iter().map(convert).collect(),
+        }
+    }
+}
+
+/// Parses the signatures of the exported WASM functions from the given bytes.
+///
+/// # Errors
+/// Returns an error if the WASM module could not be parsed, or if an export
+/// refers to a function or type that does not exist.
+#[cfg(feature = "wasm")]
+pub fn parse_wasm_signatures(wasm_bytes: &[u8]) -> Result<BTreeMap<String, WasmSignature>, String> {
+    let mut types = Vec::new();
+    // Type index of every function, imported functions first.
+    let mut function_types = Vec::new();
+    let mut exports = Vec::new();
+    let parser = wasmparser::Parser::new(0);
+    for payload in parser.parse_all(wasm_bytes) {
+        let payload = payload.map_err(|e| format!("Failed to parse WASM: {e}"))?;
+        if let Payload::TypeSection(reader) = payload {
+            for ty in reader.into_iter_err_on_gc_types() {
+                let ty = ty.map_err(|e| format!("Failed to parse WASM type: {e}"))?;
+                types.push(WasmSignature::from(&ty));
+            }
+        } else if let Payload::ImportSection(reader) = payload {
+            for import in reader.into_imports() {
+                let import = import.map_err(|e| format!("Failed to parse WASM import: {e}"))?;
+                if let TypeRef::Func(ty) | TypeRef::FuncExact(ty) = import.ty {
+                    function_types.push(ty);
+                }
+            }
+        } else if let Payload::FunctionSection(reader) = payload {
+            for ty in reader {
+                function_types.push(ty.map_err(|e| format!("Failed to parse WASM function: {e}"))?);
+            }
+        } else if let Payload::ExportSection(reader) = payload {
+            for export in reader {
+                let export = export.map_err(|e| format!("Failed to parse WASM export: {e}"))?;
+                if export.kind == ExternalKind::Func {
+                    exports.push((export.name.to_string(), export.index));
+                }
+            }
+        }
+    }
+
+    exports
+        .into_iter()
+        .map(|(name, index)| {
+            let signature = usize::try_from(index)
+                .ok()
+                .and_then(|index| function_types.get(index))
+                .and_then(|ty| usize::try_from(*ty).ok())
+                .and_then(|ty| types.get(ty))
+                .ok_or_else(|| {
+                    format!("WASM export `{name}` refers to unknown function {index}")
+                })?;
+            Ok((name, signature.clone()))
+        })
+        .collect()
+}
+
 #[cfg(all(test, feature = "wasm"))]
 mod tests {
     #![allow(clippy::expect_used)]
 
-    use super::parse_wasm_functions;
+    use super::{WasmSignature, WasmType, parse_wasm_functions, parse_wasm_signatures};
     use proptest::prelude::*;
-    use wasm_encoder::{ExportKind, ExportSection, Module};
+    use wasm_encoder::{
+        CodeSection, EntityType, ExportKind, ExportSection, Function, FunctionSection,
+        ImportSection, Instruction, Module, TypeSection, ValType,
+    };
 
     fn build_wasm_exports(exports: &[(String, u32)]) -> Vec<u8> {
         let mut module = Module::new();
@@ -95,6 +204,59 @@ mod tests {
         module.finish()
     }
 
+    #[test]
+    fn test_parse_wasm_signatures_resolves_imported_and_defined_functions() {
+        let mut types = TypeSection::new();
+        types.ty().function([ValType::I32], [ValType::I64]);
+        types.ty().function([ValType::F64, ValType::I32], []);
+        let mut imports = ImportSection::new();
+        imports.import("env", "log", EntityType::Function(1));
+        let mut functions = FunctionSection::new();
+        functions.function(0);
+        let mut exports = ExportSection::new();
+        exports.export("log", ExportKind::Func, 0);
+        exports.export("add_one", ExportKind::Func, 1);
+        let mut code = CodeSection::new();
+        let mut body = Function::new(vec![]);
+        body.instruction(&Instruction::Unreachable)
+            .instruction(&Instruction::End);
+        code.function(&body);
+        let mut module = Module::new();
+        module
+            .section(&types)
+            .section(&imports)
+            .section(&functions)
+            .section(&exports)
+            .section(&code);
+
+        let signatures = parse_wasm_signatures(&module.finish()).expect("WASM module should parse");
+        assert_eq!(
+            signatures.get("add_one"),
+            Some(&WasmSignature {
+                params: vec![WasmType::I32],
+                results: vec![WasmType::I64],
+            })
+        );
+        assert_eq!(
+            signatures.get("log"),
+            Some(&WasmSignature {
+                params: vec![WasmType::F64, WasmType::I32],
+                results: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_wasm_signatures_rejects_dangling_exports() {
+        let mut exports = ExportSection::new();
+        exports.export("missing", ExportKind::Func, 3);
+        let mut module = Module::new();
+        module.section(&exports);
+
+        let err = parse_wasm_signatures(&module.finish()).expect_err("export should dangle");
+        assert_eq!(err, "WASM export `missing` refers to unknown function 3");
+    }
+
     proptest! {
         #[test]
         fn prop_parse_wasm_functions_round_trips_exports(
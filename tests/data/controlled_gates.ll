@@ -0,0 +1,50 @@
+@0 = internal constant [5 x i8] c"ctl0\00"
+
+define i64 @Entry_Point_Name() #0 {
+entry:
+  %controls = alloca [2 x ptr], align 8
+  call void @__quantum__rt__qubit_array_allocate(i64 2, ptr %controls, ptr null)
+  %target = call ptr @__quantum__rt__qubit_allocate(ptr null)
+  %c0_ptr = getelementptr inbounds [2 x ptr], ptr %controls, i64 0, i64 0
+  %c0 = load ptr, ptr %c0_ptr, align 8
+  call void @__quantum__qis__h__body(ptr %c0)
+  call void @__quantum__qis__x__ctl(ptr %controls, ptr %target)
+  call void @__quantum__qis__h__ctladj(ptr %controls, ptr %target)
+  call void @__quantum__qis__s__ctladj(ptr %controls, ptr %target)
+  call void @__quantum__qis__rz__ctl(ptr %controls, double 5.000000e-01, ptr %target)
+  call void @__quantum__qis__ry__ctladj(ptr %controls, double 2.500000e-01, ptr %target)
+  %rx_args = alloca { double, ptr }, align 8
+  store double 7.500000e-01, ptr %rx_args, align 8
+  %rx_target = getelementptr inbounds { double, ptr }, ptr %rx_args, i64 0, i32 1
+  store ptr %target, ptr %rx_target, align 8
+  call void @__quantum__qis__rx__ctl(ptr %controls, ptr %rx_args)
+  call void @__quantum__qis__mz__body(ptr %target, ptr null)
+  call void @__quantum__rt__result_record_output(ptr null, ptr @0)
+  call void @__quantum__rt__qubit_release(ptr %target)
+  call void @__quantum__rt__qubit_array_release(i64 2, ptr %controls)
+  ret i64 0
+}
+
+declare void @__quantum__rt__qubit_array_allocate(i64, ptr, ptr)
+declare void @__quantum__rt__qubit_array_release(i64, ptr)
+declare ptr @__quantum__rt__qubit_allocate(ptr)
+declare void @__quantum__rt__qubit_release(ptr)
+declare void @__quantum__qis__h__body(ptr)
+declare void @__quantum__qis__x__ctl(ptr, ptr)
+declare void @__quantum__qis__h__ctladj(ptr, ptr)
+declare void @__quantum__qis__s__ctladj(ptr, ptr)
+declare void @__quantum__qis__rz__ctl(ptr, double, ptr)
+declare void @__quantum__qis__ry__ctladj(ptr, double, ptr)
+declare void @__quantum__qis__rx__ctl(ptr, ptr)
+declare void @__quantum__qis__mz__body(ptr, ptr writeonly) #1
+declare void @__quantum__rt__result_record_output(ptr, ptr)
+
+attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeling_schema"="schema_id" "required_num_results"="1" }
+attributes #1 = { "irreversible" }
+
+!llvm.module.flags = !{!0, !1, !2, !3, !4}
+!0 = !{i32 1, !"qir_major_version", i32 2}
+!1 = !{i32 7, !"qir_minor_version", i32 0}
+!2 = !{i32 1, !"dynamic_qubit_management", i1 true}
+!3 = !{i32 1, !"dynamic_result_management", i1 false}
+!4 = !{i32 1, !"arrays", i1 true}
@@ -0,0 +1,57 @@
+; `Controlled X([q0, q1], q2)` and `Controlled Rz([q0, q1], (0.5, q2))` as
+; emitted by the Q# compiler: the controls are a runtime `%Array*` and the
+; angle and target of the rotation are passed as a `{ double, %Qubit* }` tuple.
+; Both are lowered to stack arrays.
+
+%Array = type opaque
+%Qubit = type opaque
+%Result = type opaque
+%Tuple = type opaque
+
+@0 = internal constant [3 x i8] c"r0\00"
+
+define i64 @ENTRYPOINT__main() #0 {
+entry:
+  %controls = call %Array* @__quantum__rt__array_create_1d(i32 8, i64 2)
+  %0 = call i8* @__quantum__rt__array_get_element_ptr_1d(%Array* %controls, i64 0)
+  %1 = bitcast i8* %0 to %Qubit**
+  store %Qubit* null, %Qubit** %1, align 8
+  %2 = call i8* @__quantum__rt__array_get_element_ptr_1d(%Array* %controls, i64 1)
+  %3 = bitcast i8* %2 to %Qubit**
+  store %Qubit* inttoptr (i64 1 to %Qubit*), %Qubit** %3, align 8
+  call void @__quantum__rt__array_update_alias_count(%Array* %controls, i32 1)
+  call void @__quantum__qis__x__ctl(%Array* %controls, %Qubit* inttoptr (i64 2 to %Qubit*))
+  %4 = call %Tuple* @__quantum__rt__tuple_create(i64 16)
+  %5 = bitcast %Tuple* %4 to { double, %Qubit* }*
+  %6 = getelementptr inbounds { double, %Qubit* }, { double, %Qubit* }* %5, i32 0, i32 0
+  %7 = getelementptr inbounds { double, %Qubit* }, { double, %Qubit* }* %5, i32 0, i32 1
+  store double 5.000000e-01, double* %6, align 8
+  store %Qubit* inttoptr (i64 2 to %Qubit*), %Qubit** %7, align 8
+  call void @__quantum__qis__rz__ctl(%Array* %controls, { double, %Qubit* }* %5)
+  call void @__quantum__rt__array_update_alias_count(%Array* %controls, i32 -1)
+  call void @__quantum__rt__array_update_reference_count(%Array* %controls, i32 -1)
+  call void @__quantum__rt__tuple_update_reference_count(%Tuple* %4, i32 -1)
+  call void @__quantum__qis__mz__body(%Qubit* inttoptr (i64 2 to %Qubit*), %Result* writeonly null)
+  call void @__quantum__rt__result_record_output(%Result* null, i8* getelementptr inbounds ([3 x i8], [3 x i8]* @0, i32 0, i32 0))
+  ret i64 0
+}
+
+declare %Array* @__quantum__rt__array_create_1d(i32, i64)
+declare i8* @__quantum__rt__array_get_element_ptr_1d(%Array*, i64)
+declare void @__quantum__rt__array_update_alias_count(%Array*, i32)
+declare void @__quantum__rt__array_update_reference_count(%Array*, i32)
+declare %Tuple* @__quantum__rt__tuple_create(i64)
+declare void @__quantum__rt__tuple_update_reference_count(%Tuple*, i32)
+declare void @__quantum__qis__x__ctl(%Array*, %Qubit*)
+declare void @__quantum__qis__rz__ctl(%Array*, { double, %Qubit* }*)
+declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+declare void @__quantum__rt__result_record_output(%Result*, i8*)
+
+attributes #0 = { "entry_point" "qir_profiles"="adaptive_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="3" "required_num_results"="1" }
+attributes #1 = { "irreversible" }
+
+!llvm.module.flags = !{!0, !1, !2, !3}
+!0 = !{i32 1, !"qir_major_version", i32 1}
+!1 = !{i32 7, !"qir_minor_version", i32 0}
+!2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+!3 = !{i32 1, !"dynamic_result_management", i1 false}
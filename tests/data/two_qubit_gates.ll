@@ -0,0 +1,42 @@
+; ModuleID = 'two_qubit_gates'
+source_filename = "two_qubit_gates.ll"
+
+@0 = internal constant [3 x i8] c"r0\00"
+@1 = internal constant [3 x i8] c"r1\00"
+
+define i64 @Entry_Point_Name() #0 {
+entry:
+  call void @__quantum__rt__initialize(ptr null)
+  call void @__quantum__qis__ch__body(ptr null, ptr inttoptr (i64 1 to ptr))
+  call void @__quantum__qis__cy__body(ptr inttoptr (i64 1 to ptr), ptr null)
+  call void @__quantum__qis__swap__body(ptr null, ptr inttoptr (i64 1 to ptr))
+  call void @__quantum__qis__crz__body(double 0.5, ptr null, ptr inttoptr (i64 1 to ptr))
+  call void @__quantum__qis__cphase__body(double 1.25, ptr inttoptr (i64 1 to ptr), ptr null)
+  call void @__quantum__qis__rxx__body(double 0.75, ptr null, ptr inttoptr (i64 1 to ptr))
+  call void @__quantum__qis__ryy__body(double -0.75, ptr null, ptr inttoptr (i64 1 to ptr))
+  call void @__quantum__qis__mz__body(ptr null, ptr writeonly null)
+  call void @__quantum__qis__mz__body(ptr inttoptr (i64 1 to ptr), ptr writeonly inttoptr (i64 1 to ptr))
+  call void @__quantum__rt__result_record_output(ptr null, ptr @0)
+  call void @__quantum__rt__result_record_output(ptr inttoptr (i64 1 to ptr), ptr @1)
+  ret i64 0
+}
+
+declare void @__quantum__qis__ch__body(ptr, ptr)
+declare void @__quantum__qis__cy__body(ptr, ptr)
+declare void @__quantum__qis__swap__body(ptr, ptr)
+declare void @__quantum__qis__crz__body(double, ptr, ptr)
+declare void @__quantum__qis__cphase__body(double, ptr, ptr)
+declare void @__quantum__qis__rxx__body(double, ptr, ptr)
+declare void @__quantum__qis__ryy__body(double, ptr, ptr)
+declare void @__quantum__qis__mz__body(ptr, ptr writeonly) #1
+declare void @__quantum__rt__initialize(ptr)
+declare void @__quantum__rt__result_record_output(ptr, ptr)
+
+attributes #0 = { "entry_point" "qir_profiles"="base_profile" "output_labeling_schema"="schema_id" "required_num_qubits"="2" "required_num_results"="2" }
+attributes #1 = { "irreversible" }
+
+!llvm.module.flags = !{!0, !1, !2, !3}
+!0 = !{i32 1, !"qir_major_version", i32 2}
+!1 = !{i32 7, !"qir_minor_version", i32 0}
+!2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+!3 = !{i32 1, !"dynamic_result_management", i1 false}
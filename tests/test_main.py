@@ -1,7 +1,24 @@
 """Smoke tests for `main.py` output handling."""
 
+import io
 import subprocess
 import sys
+import warnings
+from concurrent.futures import ThreadPoolExecutor
+from contextlib import redirect_stderr
+from pathlib import Path
+
+from qir_qis import (
+    CompileOptions,
+    CompilerError,
+    QirWarning,
+    ValidationError,
+    qir_ll_to_bc,
+    qir_to_bundle,
+    qir_to_qis,
+    read_bundle,
+    validate_qir,
+)
 
 MAIN_TIMEOUT_SECONDS = 300
 
@@ -65,8 +82,215 @@ def test_dynamic_result_mixed_array_spec_output() -> None:
     assert "USER:RESULT_ARRAY" not in output, output  # noqa: S101
 
 
+def test_validation_error_exposes_kind_and_errors() -> None:
+    """`ValidationError` should carry the error variant and individual messages."""
+    ll_text = Path("tests/data/base.ll").read_text()
+    ll_text = ll_text.replace('"required_num_qubits"="2"', "")
+    try:
+        validate_qir(qir_ll_to_bc(ll_text))
+    except ValidationError as exc:
+        assert exc.kind == "validation"  # noqa: S101
+        assert exc.code == "C003"  # noqa: S101
+        assert exc.errors, exc  # noqa: S101
+        assert str(exc) == "; ".join(exc.errors)  # noqa: S101
+    else:
+        message = "expected ValidationError"
+        raise AssertionError(message)
+
+
+def test_parse_error_kind() -> None:
+    """Malformed LLVM IR should raise a `ValidationError` of kind `parse`."""
+    try:
+        qir_ll_to_bc("this is not LLVM IR")
+    except ValidationError as exc:
+        assert exc.kind == "parse"  # noqa: S101
+        assert exc.code == "C001"  # noqa: S101
+        assert exc.errors == [str(exc)]  # noqa: S101
+    else:
+        message = "expected ValidationError"
+        raise AssertionError(message)
+
+
+def test_lowering_warnings_are_issued_or_denied() -> None:
+    """Q# `m__body` calls should warn, and fail with `deny_warnings=True`."""
+    bc_bytes = qir_ll_to_bc(Path("tests/data/ArithOps_switch.ll").read_text())
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        qir_to_qis(bc_bytes, opt_level=0, target="native")
+    messages = [str(w.message) for w in caught if w.category is QirWarning]
+    assert any("warning[W003]" in m for m in messages), messages  # noqa: S101
+
+    try:
+        qir_to_qis(bc_bytes, opt_level=0, target="native", deny_warnings=True)
+    except CompilerError as exc:
+        assert exc.kind == "validation"  # noqa: S101
+        assert exc.errors, exc  # noqa: S101
+    else:
+        message = "expected CompilerError"
+        raise AssertionError(message)
+
+
+def test_emit_textual_ir() -> None:
+    """`emit="ll"` should return textual QIS LLVM IR."""
+    bc_bytes = qir_ll_to_bc(Path("tests/data/base.ll").read_text())
+    ir = qir_to_qis(bc_bytes, opt_level=0, target="native", emit="ll").decode()
+    assert "define" in ir, ir  # noqa: S101
+    try:
+        qir_to_qis(bc_bytes, opt_level=0, target="native", emit="exe")
+    except ValueError as exc:
+        assert "Invalid emit kind" in str(exc)  # noqa: S101
+    else:
+        message = "expected ValueError"
+        raise AssertionError(message)
+
+
+def test_bundle_round_trip() -> None:
+    """`read_bundle` should unpack what `qir_to_bundle` packed."""
+    bc_bytes = qir_ll_to_bc(Path("tests/data/base.ll").read_text())
+    bundle = read_bundle(qir_to_bundle(bc_bytes, opt_level=0, target="native"))
+    assert bundle.qis.startswith(b"BC\xc0\xde")  # noqa: S101
+    assert bundle.wasm is None  # noqa: S101
+    assert bundle.entry_attributes["required_num_qubits"] == "2"  # noqa: S101
+    assert bundle.capability_flags["arrays"] is False  # noqa: S101
+    assert bundle.target == "native"  # noqa: S101
+    try:
+        read_bundle(bc_bytes)
+    except ValidationError as exc:
+        assert exc.kind == "bundle"  # noqa: S101
+    else:
+        message = "expected ValidationError"
+        raise AssertionError(message)
+
+
+def test_print_after_and_stop_after() -> None:
+    """Stage dumps go to stderr and `stop_after` returns the partial module."""
+    bc_bytes = qir_ll_to_bc(Path("tests/data/base.ll").read_text())
+    stderr = io.StringIO()
+    with redirect_stderr(stderr):
+        ir = qir_to_qis(
+            bc_bytes,
+            opt_level=0,
+            target="native",
+            emit="ll",
+            print_after=["decompose"],
+            stop_after="lower-entry",
+        ).decode()
+    assert "; *** IR Dump After decompose ***" in stderr.getvalue()  # noqa: S101
+    assert "@___user_qir_Entry_Point_Name" in ir  # noqa: S101
+    assert "@qmain" not in ir  # noqa: S101
+    try:
+        qir_to_qis(bc_bytes, stop_after="peephole")
+    except ValueError as exc:
+        assert "Unknown pipeline stage" in str(exc)  # noqa: S101
+    else:
+        message = "expected ValueError"
+        raise AssertionError(message)
+
+
+def test_print_after_survives_failing_stage() -> None:
+    """Dumps of earlier stages are printed when a later stage fails."""
+    ll_text = Path("tests/data/base.ll").read_text()
+    ll_text = ll_text.replace(
+        "  call void @__quantum__qis__h__body(%Qubit* null)\n",
+        "  call void @__quantum__qis__mystery__body(%Qubit* null)\n",
+    )
+    ll_text += "\ndeclare void @__quantum__qis__mystery__body(%Qubit*)\n"
+    stderr = io.StringIO()
+    with redirect_stderr(stderr):
+        try:
+            qir_to_qis(qir_ll_to_bc(ll_text), print_after=["decompose"])
+        except CompilerError as exc:
+            assert exc.kind == "lowering"  # noqa: S101
+        else:
+            message = "expected CompilerError"
+            raise AssertionError(message)
+    assert "; *** IR Dump After decompose ***" in stderr.getvalue()  # noqa: S101
+
+
+def test_compile_options() -> None:
+    """`CompileOptions` should check its values and replace the keyword arguments."""
+    bc_bytes = qir_ll_to_bc(Path("tests/data/base.ll").read_text())
+    options = CompileOptions(opt_level=0, target="native", emit="ll")
+    assert options.target == "native"  # noqa: S101
+    assert options.wasm_bytes is None  # noqa: S101
+    ir = qir_to_qis(bc_bytes, options=options).decode()
+    assert "define" in ir, ir  # noqa: S101
+    for kwargs in ({"opt_level": 4}, {"target": "riscv"}, {"emit": "exe"}):
+        try:
+            CompileOptions(**kwargs)
+        except ValueError:
+            pass
+        else:
+            message = f"expected ValueError for {kwargs}"
+            raise AssertionError(message)
+
+
+def test_compile_options_exclude_keyword_arguments() -> None:
+    """`options=` cannot be combined with the keyword arguments it replaces."""
+    bc_bytes = qir_ll_to_bc(Path("tests/data/base.ll").read_text())
+    options = CompileOptions(opt_level=0, target="native")
+    for kwargs in ({"opt_level": 0}, {"target": "native"}, {"emit": "ll"}):
+        try:
+            qir_to_qis(bc_bytes, options=options, **kwargs)
+        except ValueError as exc:
+            assert "cannot be combined" in str(exc)  # noqa: S101
+        else:
+            message = f"expected ValueError for {kwargs}"
+            raise AssertionError(message)
+    try:
+        qir_to_bundle(bc_bytes, options=options, deny_warnings=True)
+    except ValueError as exc:
+        assert "cannot be combined" in str(exc)  # noqa: S101
+    else:
+        message = "expected ValueError for deny_warnings"
+        raise AssertionError(message)
+
+
+def test_wasm_calls_pass_through() -> None:
+    """WASM calls are left as they are for the runtime to resolve."""
+    ll_text = Path("tests/data/base.ll").read_text()
+    ll_text = ll_text.replace(
+        "  br label %measurements\n",
+        "  call void @log(i64 1)\n  br label %measurements\n",
+    )
+    ll_text += '\ndeclare void @log(i64) #2\n\nattributes #2 = { "wasm" }\n'
+    bc_bytes = qir_ll_to_bc(ll_text)
+    options = CompileOptions(opt_level=0, target="native", emit="ll")
+    ir = qir_to_qis(bc_bytes, options=options).decode()
+    assert "call void @log(i64 1)" in ir, ir  # noqa: S101
+
+
+def test_concurrent_qir_to_qis() -> None:
+    """Translations from several threads should match sequential ones."""
+    programs = [
+        qir_ll_to_bc(path.read_text())
+        for path in sorted(Path("tests/data").glob("*.ll"))
+    ]
+
+    def translate(bc_bytes: bytes) -> bytes:
+        return qir_to_qis(bc_bytes, emit="ll")
+
+    with warnings.catch_warnings():
+        warnings.simplefilter("ignore", QirWarning)
+        expected = [translate(bc_bytes) for bc_bytes in programs]
+        with ThreadPoolExecutor(max_workers=8) as pool:
+            for _ in range(4):
+                assert list(pool.map(translate, programs)) == expected  # noqa: S101
+
+
 if __name__ == "__main__":
     test_base_spec_output()
     test_base_plain_output()
     test_dynamic_result_array_spec_output()
     test_dynamic_result_mixed_array_spec_output()
+    test_validation_error_exposes_kind_and_errors()
+    test_parse_error_kind()
+    test_lowering_warnings_are_issued_or_denied()
+    test_emit_textual_ir()
+    test_bundle_round_trip()
+    test_print_after_and_stop_after()
+    test_print_after_survives_failing_stage()
+    test_compile_options()
+    test_compile_options_exclude_keyword_arguments()
+    test_wasm_calls_pass_through()
+    test_concurrent_qir_to_qis()